@@ -2,7 +2,9 @@
 import type { VercelRequest, VercelResponse } from '@vercel/node';
 
 export default async function handler(req: VercelRequest, res: VercelResponse) {
-  const { symbol, interval, range } = req.query;
+  const { interval, range } = req.query;
+  // Yahoo only knows uppercase symbols ("EURUSD=X", "VOD.L")
+  const symbol = String(req.query.symbol ?? '').trim().toUpperCase();
   if (!symbol || !interval || !range) {
     return res.status(400).json({ error: 'Missing symbol, interval, or range' });
   }
@@ -65,6 +67,7 @@ export default async function handler(req: VercelRequest, res: VercelResponse) {
     res.setHeader('Access-Control-Allow-Origin', '*');
     res.setHeader('Cache-Control', 'no-store');
     return res.json({
+      symbol,
       candles,
       current_price: currentPrice,
       previous_close: previousClose,
@@ -2,7 +2,8 @@
 import type { VercelRequest, VercelResponse } from '@vercel/node';
 
 export default async function handler(req: VercelRequest, res: VercelResponse) {
-  const { symbol } = req.query;
+  // Yahoo only knows uppercase symbols ("EURUSD=X", "VOD.L")
+  const symbol = String(req.query.symbol ?? '').trim().toUpperCase();
   if (!symbol) {
     return res.status(400).json({ error: 'Missing symbol' });
   }
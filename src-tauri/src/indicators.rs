@@ -0,0 +1,129 @@
+// Technical indicators over a close series. Every output has the same length
+// as the input and is None until enough bars have been seen.
+
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 {
+        return result;
+    }
+    let mut sum = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            result[i] = Some(sum / period as f64);
+        }
+    }
+    result
+}
+
+// Seeded with the SMA of the first `period` values, then smoothed with 2 / (period + 1)
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return result;
+    }
+    let k = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+    for i in period..values.len() {
+        current = values[i] * k + current * (1.0 - k);
+        result[i] = Some(current);
+    }
+    result
+}
+
+// Wilder's RSI: the first value needs `period` price changes, i.e. period + 1 bars
+pub fn rsi(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if period == 0 || values.len() <= period {
+        return result;
+    }
+    let mut avg_gain = 0.0;
+    let mut avg_loss = 0.0;
+    for i in 1..=period {
+        let change = values[i] - values[i - 1];
+        avg_gain += change.max(0.0);
+        avg_loss += (-change).max(0.0);
+    }
+    avg_gain /= period as f64;
+    avg_loss /= period as f64;
+    result[period] = Some(rsi_value(avg_gain, avg_loss));
+
+    for i in period + 1..values.len() {
+        let change = values[i] - values[i - 1];
+        avg_gain = (avg_gain * (period as f64 - 1.0) + change.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period as f64 - 1.0) + (-change).max(0.0)) / period as f64;
+        result[i] = Some(rsi_value(avg_gain, avg_loss));
+    }
+    result
+}
+
+fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        if avg_gain == 0.0 { 50.0 } else { 100.0 }
+    } else {
+        100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+    }
+}
+
+// Computes an indicator by name: "sma20", "ema50", "rsi14"
+pub fn compute(name: &str, closes: &[f64]) -> Result<Vec<Option<f64>>, String> {
+    let split = name.find(|c: char| c.is_ascii_digit()).unwrap_or(name.len());
+    let (kind, period) = name.split_at(split);
+    let indicator: fn(&[f64], usize) -> Vec<Option<f64>> = match kind.to_lowercase().as_str() {
+        "sma" => sma,
+        "ema" => ema,
+        "rsi" => rsi,
+        _ => return Err(format!("Unknown indicator '{}', expected sma, ema or rsi", name)),
+    };
+    let period: usize = period
+        .parse()
+        .ok()
+        .filter(|p| *p > 0)
+        .ok_or_else(|| format!("Indicator '{}' needs a positive period, e.g. '{}14'", name, kind))?;
+    Ok(indicator(closes, period))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounded(series: Vec<Option<f64>>) -> Vec<Option<f64>> {
+        series.into_iter().map(|v| v.map(|v| (v * 1e9).round() / 1e9)).collect()
+    }
+
+    #[test]
+    fn sma_averages_the_trailing_window() {
+        assert_eq!(sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3), vec![None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        assert_eq!(sma(&[1.0, 2.0], 3), vec![None, None]);
+    }
+
+    #[test]
+    fn ema_seeds_with_the_sma() {
+        // k = 2 / (3 + 1) = 0.5: 2, then 4*0.5 + 2*0.5 = 3, then 5*0.5 + 3*0.5 = 4
+        assert_eq!(ema(&[1.0, 2.0, 3.0, 4.0, 5.0], 3), vec![None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        // k = 2 / 3: 11, then 20*2/3 + 11/3 = 17
+        assert_eq!(rounded(ema(&[10.0, 12.0, 20.0], 2)), vec![None, Some(11.0), Some(17.0)]);
+    }
+
+    #[test]
+    fn rsi_uses_wilder_smoothing() {
+        // Seed: gains 1, losses 1 over 2 changes = 50; then +1: gain 0.75, loss 0.25 = 75
+        assert_eq!(rounded(rsi(&[1.0, 2.0, 1.0, 2.0], 2)), vec![None, None, Some(50.0), Some(75.0)]);
+        assert_eq!(rsi(&[1.0, 2.0, 3.0, 4.0], 3)[3], Some(100.0));
+        assert_eq!(rsi(&[5.0, 5.0, 5.0], 2)[2], Some(50.0));
+        assert_eq!(rsi(&[1.0, 2.0], 2), vec![None, None]);
+    }
+
+    #[test]
+    fn compute_parses_names() {
+        let closes = [1.0, 2.0, 3.0];
+        assert_eq!(compute("SMA2", &closes).unwrap(), sma(&closes, 2));
+        assert!(compute("macd12", &closes).is_err());
+        assert!(compute("ema0", &closes).is_err());
+        assert!(compute("rsi", &closes).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// Naive support/resistance from fractal pivots. A bar is a swing high
+// (resistance) when its high tops the `lookback` bars before it and is not
+// exceeded by the `lookback` bars after it; swing lows (support) mirror that.
+// Ties go to the earlier bar, so a flat top yields one pivot, and the last
+// `lookback` bars can't be confirmed yet so they never pivot.
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelKind {
+    Support,
+    Resistance,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Level {
+    pub kind: LevelKind,
+    pub price: f64,
+    // Time of the pivot bar
+    pub time: i64,
+}
+
+// Pivots over (time, high, low) bars, oldest first, in bar order
+pub fn find_levels(bars: &[(i64, f64, f64)], lookback: usize) -> Result<Vec<Level>, String> {
+    if lookback == 0 {
+        return Err("Level lookback must be at least 1 bar".to_string());
+    }
+    let mut levels = Vec::new();
+    if bars.len() <= 2 * lookback {
+        return Ok(levels);
+    }
+
+    for i in lookback..bars.len() - lookback {
+        let (time, high, low) = bars[i];
+        let before = &bars[i - lookback..i];
+        let after = &bars[i + 1..=i + lookback];
+        if before.iter().all(|b| high > b.1) && after.iter().all(|b| high >= b.1) {
+            levels.push(Level { kind: LevelKind::Resistance, price: high, time });
+        }
+        if before.iter().all(|b| low < b.2) && after.iter().all(|b| low <= b.2) {
+            levels.push(Level { kind: LevelKind::Support, price: low, time });
+        }
+    }
+    Ok(levels)
+}
+
+// Keeps the pivots on bars inside (first, last) bar times, e.g. a returned
+// page; None is an empty page and keeps none
+pub fn trim_to_span(levels: &mut Vec<Level>, span: Option<(i64, i64)>) {
+    levels.retain(|level| span.is_some_and(|(first, last)| (first..=last).contains(&level.time)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (time, high, low) bars with times 0, 1, 2...
+    fn bars(highs_lows: &[(f64, f64)]) -> Vec<(i64, f64, f64)> {
+        highs_lows.iter().enumerate().map(|(i, &(h, l))| (i as i64, h, l)).collect()
+    }
+
+    #[test]
+    fn finds_swing_highs_and_lows() {
+        let bars = bars(&[(10.0, 9.0), (12.0, 10.0), (15.0, 11.0), (13.0, 8.0), (13.0, 9.0), (14.0, 12.0), (11.0, 7.0), (12.0, 8.0)]);
+        let levels = find_levels(&bars, 2).unwrap();
+        let found: Vec<(LevelKind, f64, i64)> = levels.iter().map(|l| (l.kind, l.price, l.time)).collect();
+        assert_eq!(found, vec![
+            (LevelKind::Resistance, 15.0, 2),
+            (LevelKind::Support, 8.0, 3),
+            (LevelKind::Resistance, 14.0, 5),
+        ]);
+    }
+
+    #[test]
+    fn flat_top_pivots_on_the_earlier_bar() {
+        let bars = bars(&[(10.0, 5.0), (12.0, 6.0), (12.0, 6.0), (10.0, 5.5), (9.0, 5.0)]);
+        let tops: Vec<i64> = find_levels(&bars, 1).unwrap().iter()
+            .filter(|l| l.kind == LevelKind::Resistance)
+            .map(|l| l.time)
+            .collect();
+        assert_eq!(tops, vec![1]);
+    }
+
+    #[test]
+    fn flat_bottom_pivots_on_the_earlier_bar() {
+        let bars = bars(&[(10.0, 5.0), (9.0, 3.0), (9.0, 3.0), (10.0, 4.0), (11.0, 6.0)]);
+        let bottoms: Vec<i64> = find_levels(&bars, 1).unwrap().iter()
+            .filter(|l| l.kind == LevelKind::Support)
+            .map(|l| l.time)
+            .collect();
+        assert_eq!(bottoms, vec![1]);
+    }
+
+    #[test]
+    fn zero_lookback_is_an_error() {
+        assert!(find_levels(&bars(&[(1.0, 1.0); 5]), 0).is_err());
+    }
+
+    #[test]
+    fn too_few_bars_have_no_levels() {
+        // 2 * lookback bars can't confirm a pivot on both sides
+        let bars = bars(&[(10.0, 9.0), (15.0, 5.0), (10.0, 9.0), (11.0, 9.0)]);
+        assert!(find_levels(&bars, 2).unwrap().is_empty());
+        assert!(find_levels(&[], 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn trims_to_the_page_span() {
+        let level = |time| Level { kind: LevelKind::Support, price: 1.0, time };
+        let mut levels = vec![level(1), level(5), level(9)];
+        trim_to_span(&mut levels, Some((5, 9)));
+        assert_eq!(levels.iter().map(|l| l.time).collect::<Vec<_>>(), vec![5, 9]);
+        trim_to_span(&mut levels, None);
+        assert!(levels.is_empty());
+    }
+}
@@ -1,11 +1,68 @@
 use tauri::{Manager, Emitter};
-use tauri_plugin_updater::UpdaterExt;
-use std::sync::Mutex;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+
+mod indicators;
+mod levels;
+mod orders;
+mod performance;
+mod positions;
+
+// State mutexes guard plain data that every writer leaves consistent, so a
+// panic while one was held is no reason to fail every later command: take the
+// guard back from a poisoned lock instead of unwrapping
+trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
 
 // Store update state
 struct UpdateState {
     update_available: Mutex<Option<UpdateInfo>>,
+    checked_at: Mutex<Option<Instant>>,
+    // Resolved handle from the last check, reused by install_update
+    pending_update: Mutex<Option<Update>>,
+    // Set by cancel_update to abort an in-flight download
+    cancel_requested: AtomicBool,
+    // An update is installed and waiting for confirm_restart
+    restart_pending: AtomicBool,
+}
+
+// Stored update info older than this is treated as stale and not returned
+const UPDATE_INFO_TTL: Duration = Duration::from_secs(30 * 60);
+
+// Time the UI gets to show "Installing, app will restart" before the window goes away
+const DEFAULT_RESTART_DELAY_MS: u64 = 1500;
+
+#[derive(Clone, Serialize)]
+struct UpdateReady {
+    version: String,
+    // False when the app waits for confirm_restart instead of restarting itself
+    auto_restart: bool,
+    restart_delay_ms: u64,
+}
+
+// The updater checks the minisign signature against the configured pubkey
+// while downloading, so a successful download is a verified one
+#[derive(Clone, Serialize)]
+struct UpdateVerified {
+    version: String,
+    signature_verified: bool,
+    // Base64 minisign signature from the update manifest
+    signature: String,
 }
 
 #[derive(Clone, Serialize)]
@@ -37,7 +94,9 @@ async fn check_for_update(app: tauri::AppHandle) -> Result<UpdateCheckResult, St
 
             // Store the update info for later
             if let Some(state) = app.try_state::<UpdateState>() {
-                *state.update_available.lock().unwrap() = Some(info.clone());
+                *state.update_available.lock_or_recover() = Some(info.clone());
+                *state.checked_at.lock_or_recover() = Some(Instant::now());
+                *state.pending_update.lock_or_recover() = Some(update);
             }
 
             Ok(UpdateCheckResult {
@@ -48,6 +107,13 @@ async fn check_for_update(app: tauri::AppHandle) -> Result<UpdateCheckResult, St
             })
         }
         Ok(None) => {
+            // Drop anything cached from an earlier check
+            if let Some(state) = app.try_state::<UpdateState>() {
+                *state.update_available.lock_or_recover() = None;
+                *state.checked_at.lock_or_recover() = Some(Instant::now());
+                *state.pending_update.lock_or_recover() = None;
+            }
+
             // No update available - get current version from Cargo.toml
             let current = env!("CARGO_PKG_VERSION").to_string();
             Ok(UpdateCheckResult {
@@ -61,18 +127,96 @@ async fn check_for_update(app: tauri::AppHandle) -> Result<UpdateCheckResult, St
     }
 }
 
+// Returns the update found by the last check without hitting the network.
+// None means nothing is cached or the cached info is stale, so the UI should re-check.
 #[tauri::command]
-async fn install_update(app: tauri::AppHandle, window: tauri::Window) -> Result<(), String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
+fn get_pending_update(state: tauri::State<'_, UpdateState>) -> Option<UpdateCheckResult> {
+    let checked_at = (*state.checked_at.lock_or_recover())?;
+    if checked_at.elapsed() > UPDATE_INFO_TTL {
+        return None;
+    }
+
+    let info = state.update_available.lock_or_recover().clone()?;
+    Some(UpdateCheckResult {
+        available: true,
+        current_version: info.current_version,
+        new_version: Some(info.new_version),
+        notes: Some(info.notes),
+    })
+}
+
+// Restarts on its own after `restart_delay_ms` unless `auto_restart` is false,
+// in which case the installed update waits for confirm_restart
+#[tauri::command]
+async fn install_update(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    restart_delay_ms: Option<u64>,
+    auto_restart: Option<bool>,
+) -> Result<(), String> {
+    let state = app.state::<UpdateState>();
+    let restart_delay_ms = restart_delay_ms.unwrap_or(DEFAULT_RESTART_DELAY_MS);
+    let auto_restart = auto_restart.unwrap_or(true);
 
-    let update = updater.check().await.map_err(|e| e.to_string())?;
+    // Reuse the update resolved by check_for_update if it's still fresh
+    let is_fresh = state.checked_at.lock_or_recover()
+        .is_some_and(|checked_at| checked_at.elapsed() <= UPDATE_INFO_TTL);
+    let cached = if is_fresh {
+        state.pending_update.lock_or_recover().clone()
+    } else {
+        None
+    };
+
+    let update = match cached {
+        Some(update) => Some(update),
+        None => {
+            let updater = app.updater().map_err(|e| e.to_string())?;
+            let update = updater.check().await.map_err(|e| e.to_string())?;
+            // A re-check can find a newer release than the one the user was
+            // shown; don't install something they didn't agree to. The cached
+            // update is the one they were shown, so it needs no such check.
+            let advertised = state.update_available.lock_or_recover()
+                .as_ref()
+                .map(|info| info.new_version.clone());
+            if let (Some(advertised), Some(update)) = (advertised, update.as_ref()) {
+                if advertised != update.version {
+                    let message = format!(
+                        "Update changed since last check: expected {}, found {}",
+                        advertised, update.version
+                    );
+                    let _ = window.emit("update-error", &message);
+                    return Err(message);
+                }
+            }
+            update
+        }
+    };
 
     if let Some(update) = update {
+        // Never install a package the manifest didn't sign
+        if update.signature.trim().is_empty() {
+            let message = format!("Update {} has no signature in its manifest; refusing to install", update.version);
+            let _ = window.emit("update-error", &message);
+            return Err(message);
+        }
+
+        state.cancel_requested.store(false, Ordering::SeqCst);
+
         // Emit progress events to the frontend
         let window_clone = window.clone();
+        let ready_window = window.clone();
+        let ready = UpdateReady {
+            version: update.version.clone(),
+            auto_restart,
+            restart_delay_ms,
+        };
+        let cancel_requested = &state.cancel_requested;
 
-        update.download_and_install(
+        let download = update.download(
             move |downloaded, total| {
+                if cancel_requested.load(Ordering::SeqCst) {
+                    return;
+                }
                 let progress = if let Some(total) = total {
                     if total > 0 {
                         (downloaded as f64 / total as f64 * 100.0) as u32
@@ -84,798 +228,6610 @@ async fn install_update(app: tauri::AppHandle, window: tauri::Window) -> Result<
                 };
                 let _ = window_clone.emit("update-progress", progress);
             },
-            || {
-                // Download complete
+            move || {
+                let _ = ready_window.emit("update-ready", ready);
+            }
+        );
+
+        let cancelled = async {
+            while !cancel_requested.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+
+        // Dropping the download future aborts the request. The package is only
+        // buffered in memory until install, so nothing partial is left on disk.
+        let bytes = tokio::select! {
+            result = download => match result {
+                Ok(bytes) => bytes,
+                // Signature mismatches surface here, before anything is installed
+                Err(e) => {
+                    let message = format!("Update {} failed download or signature verification: {}", update.version, e);
+                    let _ = window.emit("update-error", &message);
+                    return Err(message);
+                }
+            },
+            _ = cancelled => {
+                let _ = window.emit("update-cancelled", ());
+                return Ok(());
             }
-        ).await.map_err(|e| e.to_string())?;
+        };
+
+        // Cancelled between the last chunk and install
+        if cancel_requested.load(Ordering::SeqCst) {
+            let _ = window.emit("update-cancelled", ());
+            return Ok(());
+        }
+
+        update.install(bytes).map_err(|e| e.to_string())?;
+        let _ = window.emit("update-verified", UpdateVerified {
+            version: update.version.clone(),
+            signature_verified: true,
+            signature: update.signature.clone(),
+        });
+
+        if !auto_restart {
+            state.restart_pending.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
 
-        // Restart the app
+        // Give the frontend a moment to show its final message, then restart
+        tokio::time::sleep(Duration::from_millis(restart_delay_ms)).await;
         app.restart();
     }
 
     Ok(())
 }
 
+#[tauri::command]
+fn confirm_restart(app: tauri::AppHandle, state: tauri::State<'_, UpdateState>) -> Result<(), String> {
+    if !state.restart_pending.load(Ordering::SeqCst) {
+        return Err("No installed update is waiting for a restart".to_string());
+    }
+    app.restart();
+}
+
+#[tauri::command]
+fn cancel_update(state: tauri::State<'_, UpdateState>) {
+    state.cancel_requested.store(true, Ordering::SeqCst);
+}
+
 #[tauri::command]
 fn get_current_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
+// Semver comparison against the running build, so a channel advertising an
+// older (or equal) version never gets offered as an update
+#[tauri::command]
+fn is_version_newer(candidate: String) -> Result<bool, String> {
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))
+        .map_err(|e| format!("Invalid current version: {}", e))?;
+    let candidate_version = semver::Version::parse(candidate.trim().trim_start_matches('v'))
+        .map_err(|e| format!("Invalid version '{}': {}", candidate, e))?;
+    Ok(candidate_version > current)
+}
+
 #[tauri::command]
 fn get_changelog() -> String {
     include_str!("../../CHANGELOG.md").to_string()
 }
 
-// Yahoo Finance response structures
-#[derive(Debug, Deserialize)]
-struct YahooChartResponse {
-    chart: YahooChartResult,
+#[derive(Debug, Clone, Serialize)]
+struct ChangelogEntry {
+    version: String,
+    date: Option<String>,
+    changes: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct YahooChartResult {
-    result: Option<Vec<YahooChartData>>,
-    error: Option<serde_json::Value>,
+// Parses "## [x.y.z] - date" sections and their bullet lists.
+// Malformed headers are skipped along with their bullets.
+fn parse_changelog(markdown: &str) -> Vec<ChangelogEntry> {
+    let mut entries: Vec<ChangelogEntry> = Vec::new();
+    let mut in_section = false;
+
+    for line in markdown.lines() {
+        let line = line.trim();
+
+        if let Some(header) = line.strip_prefix("## ") {
+            in_section = false;
+
+            let header = header.trim();
+            let Some(rest) = header.strip_prefix('[') else { continue };
+            let Some((version, rest)) = rest.split_once(']') else { continue };
+            let version = version.trim();
+
+            let is_unreleased = version.eq_ignore_ascii_case("unreleased");
+            let numeric = version.trim_start_matches('v');
+            let looks_like_version = numeric.contains('.')
+                && numeric.starts_with(|c: char| c.is_ascii_digit());
+            if !is_unreleased && !looks_like_version {
+                continue;
+            }
+
+            let date = rest.trim().trim_start_matches('-').trim();
+            entries.push(ChangelogEntry {
+                version: if is_unreleased { "Unreleased".to_string() } else { version.to_string() },
+                date: if date.is_empty() { None } else { Some(date.to_string()) },
+                changes: Vec::new(),
+            });
+            in_section = true;
+            continue;
+        }
+
+        if !in_section {
+            continue;
+        }
+
+        if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            if let Some(entry) = entries.last_mut() {
+                let item = item.trim();
+                if !item.is_empty() {
+                    entry.changes.push(item.to_string());
+                }
+            }
+        }
+    }
+
+    entries
 }
 
-// Trading period info
-#[derive(Debug, Deserialize, Default)]
-struct TradingPeriod {
-    #[serde(default)]
-    start: i64,
-    #[serde(default)]
-    end: i64,
+#[tauri::command]
+fn get_changelog_structured() -> Vec<ChangelogEntry> {
+    parse_changelog(include_str!("../../CHANGELOG.md"))
 }
 
-#[derive(Debug, Deserialize, Default)]
-struct CurrentTradingPeriod {
-    #[serde(default)]
-    pre: TradingPeriod,
-    #[serde(default)]
-    regular: TradingPeriod,
-    #[serde(default)]
-    post: TradingPeriod,
+// Upstream endpoints and User-Agent, overridable from api_config.json in the
+// app data dir so endpoint breakage can be fixed without a release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ApiConfig {
+    user_agent: String,
+    yahoo_hosts: Vec<String>,
+    jupiter_base_url: String,
+    raydium_base_url: String,
+    gecko_terminal_base_url: String,
+    dexscreener_base_url: String,
+    coingecko_base_url: String,
+    // Daily history fallback when every Yahoo host is blocked
+    stooq_base_url: String,
+    // Uniswap-format token list covering the EVM chains
+    evm_token_list_url: String,
+    // Birdeye-style price WebSocket (including any api key query param); empty disables streaming
+    solana_stream_url: String,
+    // Per-request timeout for Yahoo; a stalled host fails over instead of hanging
+    yahoo_timeout_secs: u64,
+    quote_refresh: QuoteRefresh,
+    dex_source_timeouts: DexSourceTimeouts,
+    // DEX prices from pools with less USD liquidity than this are flagged low_liquidity
+    low_liquidity_usd: f64,
+    // http(s)://[user:pass@]host:port for networks that only allow proxied
+    // traffic; unset means every request goes direct
+    proxy_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct YahooChartMeta {
-    symbol: Option<String>,
-    #[serde(rename = "regularMarketPrice")]
-    regular_market_price: Option<f64>,
-    #[serde(rename = "previousClose")]
-    previous_close: Option<f64>,
-    #[serde(rename = "regularMarketDayHigh")]
-    regular_market_day_high: Option<f64>,
-    #[serde(rename = "regularMarketDayLow")]
-    regular_market_day_low: Option<f64>,
-    #[serde(rename = "regularMarketVolume")]
-    regular_market_volume: Option<i64>,
-    // Extended hours
-    #[serde(rename = "postMarketPrice")]
-    post_market_price: Option<f64>,
-    #[serde(rename = "preMarketPrice")]
-    pre_market_price: Option<f64>,
-    #[serde(rename = "postMarketChange")]
-    post_market_change: Option<f64>,
-    #[serde(rename = "preMarketChange")]
-    pre_market_change: Option<f64>,
-    #[serde(rename = "currentTradingPeriod")]
-    current_trading_period: Option<CurrentTradingPeriod>,
+// Suggested quote polling cadence per market status; no need to poll a closed
+// market every couple of seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct QuoteRefresh {
+    regular_secs: u64,
+    extended_secs: u64,
+    closed_secs: u64,
 }
 
-#[derive(Debug, Deserialize)]
-struct YahooChartData {
-    meta: YahooChartMeta,
-    timestamp: Option<Vec<i64>>,
-    indicators: YahooIndicators,
+impl Default for QuoteRefresh {
+    fn default() -> Self {
+        QuoteRefresh {
+            regular_secs: 2,
+            extended_secs: 10,
+            closed_secs: 60,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct YahooIndicators {
-    quote: Vec<YahooQuoteData>,
+impl QuoteRefresh {
+    fn for_status(&self, market_status: MarketStatus) -> u64 {
+        match market_status {
+            MarketStatus::Regular => self.regular_secs,
+            MarketStatus::Pre | MarketStatus::Post => self.extended_secs,
+            MarketStatus::Closed => self.closed_secs,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct YahooQuoteData {
-    #[serde(default)]
-    open: Vec<Option<f64>>,
-    #[serde(default)]
-    high: Vec<Option<f64>>,
-    #[serde(default)]
-    low: Vec<Option<f64>>,
-    #[serde(default)]
-    close: Vec<Option<f64>>,
-    #[serde(default)]
-    volume: Vec<Option<i64>>,
+// Per-source budget inside fetch_dex_price, so one degraded source can't eat
+// the time the fallbacks need
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct DexSourceTimeouts {
+    jupiter_ms: u64,
+    raydium_ms: u64,
+    gecko_ms: u64,
+    dexscreener_ms: u64,
+    coingecko_ms: u64,
 }
 
-#[derive(Debug, Serialize)]
-struct StockCandle {
-    time: i64,
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: i64,
+impl Default for DexSourceTimeouts {
+    fn default() -> Self {
+        DexSourceTimeouts {
+            jupiter_ms: 2_000,
+            raydium_ms: 2_000,
+            gecko_ms: 4_000,
+            dexscreener_ms: 4_000,
+            coingecko_ms: 4_000,
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct StockChartResponse {
-    candles: Vec<StockCandle>,
-    current_price: f64,
-    previous_close: f64,
-    day_high: f64,
-    day_low: f64,
-    volume: i64,
+// Shorter budgets can't fit a round trip, so a misconfigured 0 would fail every lookup
+const MIN_DEX_SOURCE_TIMEOUT_MS: u64 = 250;
+
+impl DexSourceTimeouts {
+    fn for_source(&self, source: &str) -> Duration {
+        let ms = match source {
+            "jupiter" => self.jupiter_ms,
+            "raydium" => self.raydium_ms,
+            "gecko" => self.gecko_ms,
+            "dexscreener" => self.dexscreener_ms,
+            _ => self.coingecko_ms,
+        };
+        Duration::from_millis(ms.max(MIN_DEX_SOURCE_TIMEOUT_MS))
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct StockQuote {
-    symbol: String,
-    price: f64,
-    change: f64,
-    change_percent: f64,
-    high: f64,
-    low: f64,
-    volume: i64,
-    market_status: String, // "pre", "regular", "post", "closed"
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36".to_string(),
+            yahoo_hosts: vec![
+                "query2.finance.yahoo.com".to_string(),
+                "query1.finance.yahoo.com".to_string(),
+            ],
+            jupiter_base_url: "https://lite-api.jup.ag".to_string(),
+            raydium_base_url: "https://api-v3.raydium.io".to_string(),
+            gecko_terminal_base_url: "https://api.geckoterminal.com/api/v2".to_string(),
+            dexscreener_base_url: "https://api.dexscreener.com".to_string(),
+            coingecko_base_url: "https://api.coingecko.com/api/v3".to_string(),
+            stooq_base_url: "https://stooq.com".to_string(),
+            evm_token_list_url: "https://tokens.uniswap.org".to_string(),
+            solana_stream_url: String::new(),
+            yahoo_timeout_secs: 10,
+            quote_refresh: QuoteRefresh::default(),
+            dex_source_timeouts: DexSourceTimeouts::default(),
+            low_liquidity_usd: 10_000.0,
+            proxy_url: None,
+        }
+    }
+}
+
+struct ApiConfigState {
+    config: Mutex<ApiConfig>,
+}
+
+impl ApiConfigState {
+    // Snapshot so no lock is held across awaits
+    fn get(&self) -> ApiConfig {
+        self.config.lock_or_recover().clone()
+    }
+}
+
+fn api_config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("api_config.json"))
+}
+
+// A bad proxy_url is kept rather than dropped so requests fail with the
+// reason instead of quietly going direct
+fn load_api_config(app: &tauri::AppHandle) -> ApiConfig {
+    let Ok(path) = api_config_path(app) else { return ApiConfig::default() };
+    let mut config = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            log::warn!("[config] Ignoring invalid {}: {}", path.display(), e);
+            ApiConfig::default()
+        }),
+        Err(_) => ApiConfig::default(),
+    };
+    if let Some(Err(e)) = configured_proxy(&config).map(proxy_from_url) {
+        log::error!("[config] {}; requests will fail until it is fixed", e);
+    }
+    if let Err(e) = validate_yahoo_timeout(config.yahoo_timeout_secs) {
+        log::warn!("[config] {}; using the default", e);
+        config.yahoo_timeout_secs = ApiConfig::default().yahoo_timeout_secs;
+    }
+    config
 }
 
+// A zero timeout would fail every Yahoo request before it is sent
+fn validate_yahoo_timeout(secs: u64) -> Result<(), String> {
+    if secs == 0 {
+        return Err("yahoo_timeout_secs must be at least 1".to_string());
+    }
+    Ok(())
+}
 
 #[tauri::command]
-async fn fetch_stock_candles(symbol: String, interval: String, range: String) -> Result<StockChartResponse, String> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+fn get_api_config(api_config: tauri::State<'_, ApiConfigState>) -> ApiConfig {
+    api_config.get()
+}
 
-    let client = reqwest::Client::builder()
-        .no_proxy()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+fn set_api_config(app: tauri::AppHandle, api_config: tauri::State<'_, ApiConfigState>, config: ApiConfig) -> Result<(), String> {
+    let path = api_config_path(&app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    if let Some(url) = configured_proxy(&config) {
+        proxy_from_url(url)?;
+    }
+    validate_yahoo_timeout(config.yahoo_timeout_secs)?;
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
 
-    let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
-    let hosts = ["query2.finance.yahoo.com", "query1.finance.yahoo.com"];
-    let mut last_error = String::from("No Yahoo Finance host responded");
+    *api_config.config.lock_or_recover() = config;
+    Ok(())
+}
 
-    for host in &hosts {
-        let url = format!(
-            "https://{}/v8/finance/chart/{}?interval={}&range={}&_t={}",
-            host, symbol, interval, range, timestamp
-        );
+#[tauri::command]
+fn reset_api_config(app: tauri::AppHandle, api_config: tauri::State<'_, ApiConfigState>) -> Result<ApiConfig, String> {
+    let path = api_config_path(&app)?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
 
-        let response = match client
-            .get(&url)
-            .header("User-Agent", ua)
-            .header("Accept", "application/json")
-            .header("Accept-Language", "en-US,en;q=0.9")
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                eprintln!("[Yahoo] {} request failed: {}", host, e);
-                last_error = format!("{} request failed: {}", host, e);
-                continue;
-            }
-        };
+    let config = ApiConfig::default();
+    *api_config.config.lock_or_recover() = config.clone();
+    Ok(config)
+}
 
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            eprintln!("[Yahoo] {} returned HTTP {}: {}", host, status, &body[..body.len().min(500)]);
-            last_error = format!("HTTP {} from {}", status, host);
-            continue;
-        }
+// Single-flight: concurrent callers with the same key share one upstream request
+type SharedFetch<T, E> = Shared<BoxFuture<'static, Result<T, E>>>;
 
-        let data: YahooChartResponse = match response.json().await {
-            Ok(d) => d,
-            Err(e) => {
-                eprintln!("[Yahoo] {} JSON parse error: {}", host, e);
-                last_error = format!("JSON parse error: {}", e);
-                continue;
-            }
-        };
+struct InFlight<T, E = String> {
+    requests: Mutex<HashMap<String, SharedFetch<T, E>>>,
+}
 
-        // Check for API-level error
-        if let Some(ref err) = data.chart.error {
-            eprintln!("[Yahoo] API error: {:?}", err);
-            last_error = format!("Yahoo API error: {}", err);
-            continue;
+impl<T: Clone + Send + Sync + 'static, E: Clone + Send + Sync + 'static> InFlight<T, E> {
+    fn new() -> Self {
+        InFlight {
+            requests: Mutex::new(HashMap::new()),
         }
+    }
 
-        if let Some(results) = data.chart.result {
-            if let Some(result) = results.first() {
-                let meta = &result.meta;
-                let regular_price = meta.regular_market_price.unwrap_or(0.0);
-                let previous_close = meta.previous_close.unwrap_or(0.0);
-                let day_high = meta.regular_market_day_high.unwrap_or(0.0);
-                let day_low = meta.regular_market_day_low.unwrap_or(0.0);
-                let volume = meta.regular_market_volume.unwrap_or(0);
-
-                let current_price = meta.post_market_price
-                    .or(meta.pre_market_price)
-                    .unwrap_or(regular_price);
-
-                if let Some(timestamps) = &result.timestamp {
-                    if let Some(quote) = result.indicators.quote.first() {
-                        let mut candles = Vec::new();
-
-                        for i in 0..timestamps.len() {
-                            if let (Some(open), Some(high), Some(low), Some(close)) = (
-                                quote.open.get(i).and_then(|v| *v),
-                                quote.high.get(i).and_then(|v| *v),
-                                quote.low.get(i).and_then(|v| *v),
-                                quote.close.get(i).and_then(|v| *v),
-                            ) {
-                                candles.push(StockCandle {
-                                    time: timestamps[i] * 1000,
-                                    open,
-                                    high,
-                                    low,
-                                    close,
-                                    volume: quote.volume.get(i).and_then(|v| *v).unwrap_or(0),
-                                });
-                            }
-                        }
-
-                        eprintln!("[Yahoo] {} OK: {} candles for {}", host, candles.len(), symbol);
-                        return Ok(StockChartResponse {
-                            candles,
-                            current_price,
-                            previous_close,
-                            day_high,
-                            day_low,
-                            volume,
-                        });
-                    }
+    async fn run<F>(&self, key: String, fetch: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let shared = {
+            let mut requests = self.requests.lock_or_recover();
+            match requests.get(&key) {
+                Some(existing) => existing.clone(),
+                None => {
+                    let shared = fetch.boxed().shared();
+                    requests.insert(key.clone(), shared.clone());
+                    shared
                 }
             }
-        }
+        };
 
-        last_error = format!("No chart data in response from {}", host);
+        // Removes the entry even if this caller is cancelled mid-await, so an
+        // abandoned fetch is dropped rather than left for the next caller
+        let _guard = InFlightGuard { requests: &self.requests, key, shared: shared.clone() };
+        shared.await
     }
+}
 
-    Err(last_error)
+struct InFlightGuard<'a, T, E> {
+    requests: &'a Mutex<HashMap<String, SharedFetch<T, E>>>,
+    key: String,
+    shared: SharedFetch<T, E>,
 }
 
-#[tauri::command]
-async fn fetch_stock_quote(symbol: String) -> Result<StockQuote, String> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let client = reqwest::Client::builder()
-        .no_proxy()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+impl<T, E> Drop for InFlightGuard<'_, T, E> {
+    fn drop(&mut self) {
+        // Only remove our own entry; a newer request may already have replaced it
+        let mut requests = self.requests.lock_or_recover();
+        if requests.get(&self.key).is_some_and(|current| current.ptr_eq(&self.shared)) {
+            requests.remove(&self.key);
+        }
+    }
+}
 
-    let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
-    let hosts = ["query2.finance.yahoo.com", "query1.finance.yahoo.com"];
-    let mut last_error = String::from("No Yahoo Finance host responded");
+// Lets a caller tag a request with an id (e.g. the chart slot); a newer request
+// with the same id aborts the older one instead of letting stuck polls pile up
+struct SupersedeRequests {
+    next_generation: std::sync::atomic::AtomicU64,
+    active: Mutex<HashMap<String, (u64, futures::future::AbortHandle)>>,
+}
 
-    for host in &hosts {
-        let url = format!(
-            "https://{}/v8/finance/chart/{}?interval=1m&range=1d&includePrePost=true&_t={}",
-            host, symbol, timestamp
-        );
+impl SupersedeRequests {
+    fn new() -> Self {
+        SupersedeRequests {
+            next_generation: std::sync::atomic::AtomicU64::new(0),
+            active: Mutex::new(HashMap::new()),
+        }
+    }
 
-        let response = match client
-            .get(&url)
-            .header("User-Agent", ua)
-            .header("Accept", "application/json")
-            .header("Accept-Language", "en-US,en;q=0.9")
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                eprintln!("[Yahoo] quote {} failed: {}", host, e);
-                last_error = format!("{} request failed: {}", host, e);
-                continue;
-            }
-        };
+    async fn run<T, F>(&self, request_id: Option<String>, fetch: F) -> Result<T, TradingError>
+    where
+        F: Future<Output = Result<T, TradingError>>,
+    {
+        let Some(request_id) = request_id else { return fetch.await };
 
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            eprintln!("[Yahoo] quote {} HTTP {}: {}", host, status, &body[..body.len().min(500)]);
-            last_error = format!("HTTP {} from {}", status, host);
-            continue;
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let (handle, registration) = futures::future::AbortHandle::new_pair();
+        if let Some((_, previous)) = self.active.lock_or_recover().insert(request_id.clone(), (generation, handle)) {
+            previous.abort();
         }
 
-        let data: YahooChartResponse = match response.json().await {
-            Ok(d) => d,
-            Err(e) => {
-                eprintln!("[Yahoo] quote {} parse error: {}", host, e);
-                last_error = format!("JSON parse error: {}", e);
-                continue;
-            }
-        };
+        let result = futures::future::Abortable::new(fetch, registration).await;
 
-        if let Some(ref err) = data.chart.error {
-            eprintln!("[Yahoo] quote API error: {:?}", err);
-            last_error = format!("Yahoo API error: {}", err);
-            continue;
+        let mut active = self.active.lock_or_recover();
+        if active.get(&request_id).is_some_and(|(current, _)| *current == generation) {
+            active.remove(&request_id);
         }
+        drop(active);
 
-        if let Some(results) = data.chart.result {
-            if let Some(result) = results.first() {
-                let meta = &result.meta;
-                let regular_price = meta.regular_market_price.unwrap_or(0.0);
-                let previous_close = meta.previous_close.unwrap_or(regular_price);
-
-                let now = timestamp as i64;
-                let market_status = if let Some(ref period) = meta.current_trading_period {
-                    if now >= period.pre.start && now < period.pre.end {
-                        "pre"
-                    } else if now >= period.regular.start && now < period.regular.end {
-                        "regular"
-                    } else if now >= period.post.start && now < period.post.end {
-                        "post"
-                    } else {
-                        "closed"
-                    }
-                } else {
-                    "regular"
-                };
+        result.unwrap_or_else(|_| Err(TradingError::Cancelled {
+            message: format!("Request '{}' was superseded", request_id),
+        }))
+    }
+}
 
-                let last_candle_price = if let Some(ref timestamps) = result.timestamp {
-                    if let Some(quote) = result.indicators.quote.first() {
-                        let mut last_price = regular_price;
-                        for i in (0..timestamps.len()).rev() {
-                            if let Some(Some(close)) = quote.close.get(i) {
-                                last_price = *close;
-                                break;
-                            }
-                        }
-                        last_price
-                    } else {
-                        regular_price
-                    }
-                } else {
-                    regular_price
-                };
+struct InFlightRequests {
+    candles: InFlight<StockChartResponse, TradingError>,
+    quotes: InFlight<StockQuote, TradingError>,
+    dex_prices: InFlight<DexPriceResult, DexPriceError>,
+    dex_stats: InFlight<DexPriceResult>,
+    supersede: SupersedeRequests,
+}
 
-                let (price, change) = match market_status {
-                    "post" => {
-                        let current_price = meta.post_market_price.unwrap_or(last_candle_price);
-                        let price_change = meta.post_market_change.unwrap_or(current_price - previous_close);
-                        (current_price, price_change)
-                    }
-                    "pre" => {
-                        let current_price = meta.pre_market_price.unwrap_or(last_candle_price);
-                        let price_change = meta.pre_market_change.unwrap_or(current_price - previous_close);
-                        (current_price, price_change)
-                    }
-                    "closed" => {
-                        (regular_price, regular_price - previous_close)
-                    }
-                    _ => {
-                        (regular_price, regular_price - previous_close)
-                    }
-                };
+// Parameters for one candle fetch; also the single-flight key
+#[derive(Debug, Clone)]
+struct CandleRequest {
+    symbol: String,
+    interval: String,
+    range: String,
+    adjusted: bool,
+    fill_gaps: bool,
+    // Adds pre/post-market bars, each tagged with its session
+    include_extended: bool,
+    bypass_cache: bool,
+}
 
-                let change_percent = if previous_close > 0.0 {
-                    (change / previous_close) * 100.0
-                } else {
-                    0.0
-                };
+impl CandleRequest {
+    fn key(&self) -> String {
+        // bypass_cache is in the key so a busting request never joins a cached one
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            self.symbol, self.interval, self.range, self.adjusted, self.fill_gaps, self.include_extended, self.bypass_cache
+        )
+    }
+}
 
-                return Ok(StockQuote {
-                    symbol: meta.symbol.clone().unwrap_or(symbol),
-                    price,
-                    change,
-                    change_percent,
-                    high: meta.regular_market_day_high.unwrap_or(0.0),
-                    low: meta.regular_market_day_low.unwrap_or(0.0),
-                    volume: meta.regular_market_volume.unwrap_or(0),
-                    market_status: market_status.to_string(),
-                });
-            }
-        }
+// In-flight key for a quote; like CandleRequest::key, it includes bypass_cache.
+// The clock's second is in it too, so callers at different simulated instants
+// never share a result.
+fn quote_key(symbol: &str, bypass_cache: bool, now_ms: i64) -> String {
+    format!("{}|{}|{}", symbol, bypass_cache, now_ms.div_euclid(1000))
+}
 
-        last_error = format!("No quote data in response from {}", host);
+#[derive(Debug, Clone, Deserialize)]
+struct DexPriceRequest {
+    chain_id: String,
+    address: String,
+    #[serde(default)]
+    pair_address: Option<String>,
+    #[serde(default)]
+    preferred_source: Option<String>,
+    #[serde(default)]
+    coingecko_id: Option<String>,
+    // Full fallback sequence, overriding preferred_source; on Solana it applies after Jupiter
+    #[serde(default)]
+    source_order: Option<Vec<String>>,
+    // Price from this venue's pairs only (DexScreener dexId, e.g. "raydium", "orca")
+    #[serde(default)]
+    dex_id: Option<String>,
+    // Query the chain's two fast sources at once and take the first good price
+    #[serde(default)]
+    race: bool,
+}
+
+impl DexPriceRequest {
+    // The token being priced: chain, address and pinned venue
+    fn token_key(&self) -> String {
+        match self.dex_id {
+            Some(ref dex_id) => format!("{}|{}|{}", self.chain_id.to_lowercase(), self.address, dex_id.to_lowercase()),
+            None => format!("{}|{}", self.chain_id.to_lowercase(), self.address),
+        }
     }
 
-    Err(last_error)
+    // Single-flight key: every field that changes which sources run or what
+    // they are asked, so only identical lookups share a result or an error
+    fn key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}",
+            self.token_key(),
+            self.pair_address.as_deref().unwrap_or(""),
+            self.coingecko_id.as_deref().unwrap_or(""),
+            self.preferred_source.as_deref().unwrap_or(""),
+            self.source_order.as_ref().map(|order| order.join(",")).unwrap_or_default(),
+            self.race
+        )
+    }
 }
 
-// DexScreener response structures
-#[derive(Debug, Deserialize)]
-struct DexScreenerResponse {
-    pairs: Option<Vec<DexPair>>,
-    pair: Option<DexPair>,
+// Where quotes, candles and DEX prices come from. Live hits the real APIs;
+// Mock serves a deterministic random walk so demos and tests work offline;
+// Sandbox steps seeded per-symbol walks for practising the order flow.
+trait DataSource: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn stock_candles(&self, api: ApiConfig, request: CandleRequest) -> BoxFuture<'static, Result<StockChartResponse, TradingError>>;
+    // `now_ms` is the instant session status is evaluated at (see ClockState)
+    fn stock_quote(&self, api: ApiConfig, symbol: String, bypass_cache: bool, now_ms: i64) -> BoxFuture<'static, Result<StockQuote, TradingError>>;
+    fn dex_price(&self, api: ApiConfig, request: DexPriceRequest) -> BoxFuture<'static, Result<DexPriceResult, DexPriceError>>;
+
+    // Results line up with `requests`; sources that can batch should override this
+    fn dex_prices(&self, api: ApiConfig, requests: Vec<DexPriceRequest>) -> BoxFuture<'static, Vec<Result<DexPriceResult, DexPriceError>>> {
+        let lookups: Vec<_> = requests.into_iter().map(|request| self.dex_price(api.clone(), request)).collect();
+        futures::future::join_all(lookups).boxed()
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct DexPair {
-    #[serde(rename = "chainId")]
-    chain_id: Option<String>,
-    #[serde(rename = "pairAddress")]
-    pair_address: Option<String>,
-    #[serde(rename = "priceUsd")]
-    price_usd: Option<String>,
-    volume: Option<DexVolume>,
-    #[serde(rename = "priceChange")]
-    price_change: Option<DexPriceChange>,
-    liquidity: Option<DexLiquidity>,
+struct LiveDataSource {
+    breakers: std::sync::Arc<SourceBreakers>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct DexVolume {
-    h24: Option<f64>,
+impl DataSource for LiveDataSource {
+    fn name(&self) -> &'static str {
+        "live"
+    }
+
+    fn stock_candles(&self, api: ApiConfig, request: CandleRequest) -> BoxFuture<'static, Result<StockChartResponse, TradingError>> {
+        load_stock_candles(api, self.breakers.clone(), request).boxed()
+    }
+
+    fn stock_quote(&self, api: ApiConfig, symbol: String, bypass_cache: bool, now_ms: i64) -> BoxFuture<'static, Result<StockQuote, TradingError>> {
+        load_stock_quote(api, self.breakers.clone(), symbol, bypass_cache, now_ms).boxed()
+    }
+
+    fn dex_price(&self, api: ApiConfig, request: DexPriceRequest) -> BoxFuture<'static, Result<DexPriceResult, DexPriceError>> {
+        load_dex_price(api, self.breakers.clone(), request, Vec::new()).boxed()
+    }
+
+    fn dex_prices(&self, api: ApiConfig, requests: Vec<DexPriceRequest>) -> BoxFuture<'static, Vec<Result<DexPriceResult, DexPriceError>>> {
+        load_dex_prices(api, self.breakers.clone(), requests).boxed()
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct DexPriceChange {
-    h24: Option<f64>,
+struct MockDataSource;
+
+// Bar length in seconds for an interval string ("5m", "4h", "1d", "1wk", "3mo").
+// Accepts any multiple, not just the intervals Yahoo serves natively.
+fn interval_secs(interval: &str) -> Option<i64> {
+    let split = interval.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = interval.split_at(split);
+    let count: i64 = count.parse().ok().filter(|n| *n > 0)?;
+    let per_unit = match unit {
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "wk" => 7 * 86_400,
+        "mo" => 30 * 86_400,
+        _ => return None,
+    };
+    count.checked_mul(per_unit)
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct DexLiquidity {
-    usd: Option<f64>,
+// FNV-1a, so a symbol always maps to the same mock series
+fn seed_for(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
 }
 
-// Jupiter Lite API v3 response — top-level is HashMap<mint, data>, no "data" wrapper
-#[derive(Debug, Deserialize)]
-struct JupiterV3PriceData {
-    #[serde(rename = "usdPrice")]
-    usd_price: Option<f64>,
-    #[serde(rename = "priceChange24h")]
-    price_change_24h: Option<f64>,
+// xorshift64* — tiny deterministic generator, uniform in [0, 1)
+struct MockRng(u64);
+
+impl MockRng {
+    fn new(seed: u64) -> Self {
+        MockRng(seed.max(1))
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
 }
 
-// GeckoTerminal simple token price response
-#[derive(Debug, Deserialize)]
-struct GeckoTokenPriceResponse {
-    data: Option<GeckoTokenPriceData>,
+const MOCK_MAX_CANDLES: i64 = 500;
+
+fn mock_candles(key: &str, interval: &str, range: &str) -> Vec<StockCandle> {
+    let step = interval_secs(interval).unwrap_or(86_400);
+    let span = range_days(range).map(|d| d.min(3_650) as i64 * 86_400).unwrap_or(step);
+    let count = (span / step).clamp(1, MOCK_MAX_CANDLES);
+
+    let mut rng = MockRng::new(seed_for(key));
+    let now = now_millis() / 1000;
+    let end = now - now % step;
+    let mut price = 20.0 + rng.next_f64() * 480.0;
+
+    (0..count)
+        .map(|i| {
+            let open = price;
+            let close = open * (1.0 + (rng.next_f64() - 0.5) * 0.02);
+            let high = open.max(close) * (1.0 + rng.next_f64() * 0.005);
+            let low = open.min(close) * (1.0 - rng.next_f64() * 0.005);
+            price = close;
+            StockCandle {
+                time: (end - (count - 1 - i) * step) * 1000,
+                open,
+                high,
+                low,
+                close,
+                volume: (rng.next_f64() * 1_000_000.0) as i64,
+                filled: Vec::new(),
+                session: None,
+                time_iso: None,
+            }
+        })
+        .collect()
 }
 
-#[derive(Debug, Deserialize)]
-struct GeckoTokenPriceData {
-    attributes: Option<GeckoTokenPriceAttributes>,
+impl DataSource for MockDataSource {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn stock_candles(&self, _api: ApiConfig, request: CandleRequest) -> BoxFuture<'static, Result<StockChartResponse, TradingError>> {
+        let candles = mock_candles(&request.symbol, &request.interval, &request.range);
+        let last = candles.last().map(|c| c.close).unwrap_or(0.0);
+        let first = candles.first().map(|c| c.open).unwrap_or(0.0);
+        let response = StockChartResponse {
+            symbol: request.symbol.clone(),
+            current_price: last,
+            previous_close: first,
+            day_high: candles.iter().map(|c| c.high).fold(f64::MIN, f64::max),
+            day_low: candles.iter().map(|c| c.low).fold(f64::MAX, f64::min),
+            volume: candles.iter().map(|c| c.volume).sum(),
+            period_change: (candles.len() >= 2).then_some(last - first),
+            period_change_percent: if candles.len() >= 2 { percent_change(first, last) } else { None },
+            indicators: HashMap::new(),
+            levels: Vec::new(),
+            last_bar_complete: true,
+            timezone: None,
+            gmt_offset_secs: 0,
+            gaps: None,
+            total_candles: None,
+            candles,
+        };
+        futures::future::ready(Ok(response)).boxed()
+    }
+
+    fn stock_quote(&self, api: ApiConfig, symbol: String, _bypass_cache: bool, now_ms: i64) -> BoxFuture<'static, Result<StockQuote, TradingError>> {
+        let candles = mock_candles(&symbol, "1m", "1d");
+        let price = candles.last().map(|c| c.close).unwrap_or(0.0);
+        let previous_close = candles.first().map(|c| c.open).unwrap_or(price);
+        let change = price - previous_close;
+        let quote = StockQuote {
+            asset_class: asset_class(&symbol).to_string(),
+            exchange: None,
+            timezone: None,
+            gmt_offset_secs: 0,
+            symbol,
+            price,
+            change,
+            change_percent: percent_change(previous_close, price),
+            high: candles.iter().map(|c| c.high).fold(f64::MIN, f64::max),
+            low: candles.iter().map(|c| c.low).fold(f64::MAX, f64::min),
+            volume: candles.iter().map(|c| c.volume).sum(),
+            market_status: MarketStatus::Regular,
+            regular_price: price,
+            extended_price: None,
+            session_change: None,
+            session_change_percent: None,
+            suggested_refresh_secs: api.quote_refresh.for_status(MarketStatus::Regular),
+            as_of: now_ms,
+            as_of_iso: None,
+        };
+        futures::future::ready(Ok(quote)).boxed()
+    }
+
+    fn dex_price(&self, _api: ApiConfig, request: DexPriceRequest) -> BoxFuture<'static, Result<DexPriceResult, DexPriceError>> {
+        let candles = mock_candles(&request.token_key(), "5m", "1d");
+        let price = candles.last().map(|c| c.close).unwrap_or(0.0);
+        let open = candles.first().map(|c| c.open).unwrap_or(price);
+        let result = DexPriceResult {
+            change_24h: percent_change(open, price),
+            volume_24h: candles.iter().map(|c| c.volume as f64).sum(),
+            ..DexPriceResult::new("mock", price, price.to_string(), request.pair_address)
+        };
+        futures::future::ready(Ok(result)).boxed()
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct GeckoTokenPriceAttributes {
-    token_prices: Option<std::collections::HashMap<String, Option<String>>>,
+// Paper-trading sandbox: every symbol or DEX token follows its own geometric
+// Brownian motion, seeded from the sandbox seed and its key, and steps once per
+// quote or price lookup. The same seed and call order replay the same prices.
+#[derive(Debug, Clone, Copy)]
+struct SandboxConfig {
+    seed: u64,
+    // Standard deviation and mean of each step's log return (0.01 = 1%)
+    volatility: f64,
+    drift: f64,
 }
 
-// Raydium price API response
-#[derive(Debug, Deserialize)]
-struct RaydiumPriceResponse {
-    data: Option<std::collections::HashMap<String, String>>,
+const SANDBOX_DEFAULTS: SandboxConfig = SandboxConfig { seed: 0, volatility: 0.01, drift: 0.0 };
+
+struct SandboxWalk {
+    rng: MockRng,
+    start: f64,
+    // (epoch ms, price) after each step, oldest first, capped at MOCK_MAX_CANDLES
+    path: std::collections::VecDeque<(i64, f64)>,
 }
 
-fn chain_to_gecko_network(chain_id: &str) -> Option<&'static str> {
-    match chain_id.to_lowercase().as_str() {
-        "solana" => Some("solana"),
-        "ethereum" => Some("eth"),
-        "bsc" => Some("bsc"),
-        "base" => Some("base"),
-        "arbitrum" => Some("arbitrum"),
-        "polygon" => Some("polygon_pos"),
-        "avalanche" => Some("avax"),
-        "optimism" => Some("optimism"),
-        _ => None,
+impl SandboxWalk {
+    fn new(config: &SandboxConfig, key: &str) -> Self {
+        let mut rng = MockRng::new(seed_for(key) ^ config.seed);
+        let start = 20.0 + rng.next_f64() * 480.0;
+        SandboxWalk { rng, start, path: std::collections::VecDeque::new() }
+    }
+
+    fn price(&self) -> f64 {
+        self.path.back().map_or(self.start, |(_, price)| *price)
+    }
+
+    fn step(&mut self, config: &SandboxConfig, now_ms: i64) -> f64 {
+        // Box-Muller; 1 - u keeps the log argument away from zero
+        let u1 = 1.0 - self.rng.next_f64();
+        let u2 = self.rng.next_f64();
+        let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        let log_return = config.drift - config.volatility.powi(2) / 2.0 + config.volatility * z;
+        let price = self.price() * log_return.exp();
+        if self.path.len() as i64 >= MOCK_MAX_CANDLES {
+            self.path.pop_front();
+        }
+        self.path.push_back((now_ms, price));
+        price
+    }
+
+    // Highest and lowest price on the recorded path, including the start
+    fn range(&self) -> (f64, f64) {
+        self.path.iter().fold((self.start, self.start), |(high, low), (_, p)| (high.max(*p), low.min(*p)))
     }
 }
 
-#[derive(Debug, Serialize)]
-struct DexPriceResult {
-    price: f64,
-    change_24h: f64,
-    volume_24h: f64,
-    pair_address: String,
-    source: String,
+// Walks live behind an Arc so the returned futures can step them when polled
+struct SandboxDataSource {
+    config: SandboxConfig,
+    walks: std::sync::Arc<Mutex<HashMap<String, SandboxWalk>>>,
 }
 
-#[tauri::command]
-async fn fetch_dex_price(chain_id: String, address: String, pair_address: Option<String>, preferred_source: Option<String>) -> Result<DexPriceResult, String> {
-    let client = reqwest::Client::builder()
-        .no_proxy()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .map_err(|e| e.to_string())?;
+// Steps `key`'s walk and returns (price, start, high, low). Path times are
+// always wall-clock; the sim clock doesn't apply to the sandbox, so candles
+// built from the path stay in order.
+fn sandbox_tick(walks: &Mutex<HashMap<String, SandboxWalk>>, config: &SandboxConfig, key: &str) -> (f64, f64, f64, f64) {
+    let mut walks = walks.lock_or_recover();
+    let walk = walks.entry(key.to_string()).or_insert_with(|| SandboxWalk::new(config, key));
+    let price = walk.step(config, now_millis());
+    let (high, low) = walk.range();
+    (price, walk.start, high, low)
+}
 
-    let pref = preferred_source.as_deref().unwrap_or("");
-    let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+impl SandboxDataSource {
+    fn new(config: SandboxConfig) -> Result<Self, String> {
+        if !(config.volatility.is_finite() && (0.0..=1.0).contains(&config.volatility)) {
+            return Err(format!("Sandbox volatility must be in [0, 1], got {}", config.volatility));
+        }
+        if !(config.drift.is_finite() && config.drift.abs() <= 1.0) {
+            return Err(format!("Sandbox drift must be in [-1, 1], got {}", config.drift));
+        }
+        Ok(SandboxDataSource { config, walks: Default::default() })
+    }
+}
 
-    // Helper closures for each source
-    let try_jupiter = |client: &reqwest::Client, address: &str, pair_address: &Option<String>| {
-        let client = client.clone();
-        let address = address.to_string();
-        let pa = pair_address.clone();
-        let ua = ua.to_string();
+// Each lookup steps its walk when first polled, not when built, so requests
+// that in-flight coalescing joins or supersede cancels don't advance prices
+impl DataSource for SandboxDataSource {
+    fn name(&self) -> &'static str {
+        "sandbox"
+    }
+
+    // One bar per step taken so far; charting doesn't advance the walk unless
+    // the symbol has never been quoted
+    fn stock_candles(&self, _api: ApiConfig, request: CandleRequest) -> BoxFuture<'static, Result<StockChartResponse, TradingError>> {
+        let (walks, config) = (self.walks.clone(), self.config);
         async move {
-            // Jupiter Lite API v3 — free, no API key, 60 req/min
-            // Response is top-level HashMap<mint, {usdPrice, priceChange24h, ...}>
-            let url = format!("https://lite-api.jup.ag/price/v3?ids={}", address);
-            let response = client.get(&url)
-                .header("User-Agent", &ua)
-                .header("Accept", "application/json")
-                .send()
-                .await.map_err(|e| format!("Jupiter request: {}", e))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(format!("Jupiter status {}", status));
+            let mut walks = walks.lock_or_recover();
+            let walk = walks.entry(request.symbol.clone()).or_insert_with(|| SandboxWalk::new(&config, &request.symbol));
+            if walk.path.is_empty() {
+                walk.step(&config, now_millis());
             }
-            let data: std::collections::HashMap<String, JupiterV3PriceData> = response.json().await
-                .map_err(|e| format!("Jupiter parse: {}", e))?;
-            let token = data.get(&address).ok_or("Jupiter: token not found")?;
-            let price = token.usd_price.ok_or("Jupiter: no usdPrice")?;
-            if price <= 0.0 { return Err("Jupiter: price zero".to_string()); }
-            let change_24h = token.price_change_24h.unwrap_or(0.0);
-            eprintln!("[price] Jupiter v3 OK: ${} (24h: {:.2}%)", price, change_24h);
-            Ok(DexPriceResult {
-                price, change_24h, volume_24h: 0.0,
-                pair_address: pa.unwrap_or_default(),
-                source: "jupiter".to_string(),
+            let mut open = walk.start;
+            let candles: Vec<StockCandle> = walk.path.iter()
+                .map(|&(time, close)| {
+                    let candle = StockCandle {
+                        time,
+                        open,
+                        high: open.max(close),
+                        low: open.min(close),
+                        close,
+                        volume: 0,
+                        filled: Vec::new(),
+                        session: None,
+                        time_iso: None,
+                    };
+                    open = close;
+                    candle
+                })
+                .collect();
+            let (high, low) = walk.range();
+            let last = walk.price();
+            Ok(StockChartResponse {
+                symbol: request.symbol.clone(),
+                current_price: last,
+                previous_close: walk.start,
+                day_high: high,
+                day_low: low,
+                volume: 0,
+                period_change: Some(last - walk.start),
+                period_change_percent: percent_change(walk.start, last),
+                indicators: HashMap::new(),
+                levels: Vec::new(),
+                last_bar_complete: true,
+                timezone: None,
+                gmt_offset_secs: 0,
+                gaps: None,
+                total_candles: None,
+                candles,
             })
         }
-    };
+        .boxed()
+    }
 
-    let try_raydium = |client: &reqwest::Client, address: &str, pair_address: &Option<String>| {
-        let client = client.clone();
-        let address = address.to_string();
-        let pa = pair_address.clone();
-        let ua = ua.to_string();
+    fn stock_quote(&self, api: ApiConfig, symbol: String, _bypass_cache: bool, _now_ms: i64) -> BoxFuture<'static, Result<StockQuote, TradingError>> {
+        let (walks, config) = (self.walks.clone(), self.config);
         async move {
-            // Raydium API v3 — free, no API key
-            let url = format!("https://api-v3.raydium.io/mint/price?mints={}", address);
-            let response = client.get(&url)
-                .header("User-Agent", &ua)
-                .header("Accept", "application/json")
-                .send()
-                .await.map_err(|e| format!("Raydium request: {}", e))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(format!("Raydium status {}", status));
-            }
-            let data: RaydiumPriceResponse = response.json().await
-                .map_err(|e| format!("Raydium parse: {}", e))?;
-            let prices = data.data.ok_or("Raydium: no data")?;
-            let price_str = prices.get(&address).ok_or("Raydium: token not found")?;
-            let price: f64 = price_str.parse().map_err(|_| "Raydium: invalid price")?;
-            if price <= 0.0 { return Err("Raydium: price zero".to_string()); }
-            eprintln!("[price] Raydium OK: ${}", price);
-            Ok(DexPriceResult {
-                price, change_24h: 0.0, volume_24h: 0.0,
-                pair_address: pa.unwrap_or_default(),
-                source: "raydium".to_string(),
+            let (price, start, high, low) = sandbox_tick(&walks, &config, &symbol);
+            Ok(StockQuote {
+                asset_class: asset_class(&symbol).to_string(),
+                exchange: None,
+                timezone: None,
+                gmt_offset_secs: 0,
+                symbol,
+                price,
+                change: price - start,
+                change_percent: percent_change(start, price),
+                high,
+                low,
+                volume: 0,
+                market_status: MarketStatus::Regular,
+                regular_price: price,
+                extended_price: None,
+                session_change: None,
+                session_change_percent: None,
+                suggested_refresh_secs: api.quote_refresh.for_status(MarketStatus::Regular),
+                as_of: now_millis(),
+                as_of_iso: None,
             })
         }
-    };
+        .boxed()
+    }
 
-    let try_gecko = |client: &reqwest::Client, chain_id: &str, address: &str, pair_address: &Option<String>| {
-        let client = client.clone();
-        let address = address.to_string();
-        let pa = pair_address.clone();
-        let network = chain_to_gecko_network(chain_id).unwrap_or("").to_string();
-        let ua = ua.to_string();
+    fn dex_price(&self, _api: ApiConfig, request: DexPriceRequest) -> BoxFuture<'static, Result<DexPriceResult, DexPriceError>> {
+        let (walks, config) = (self.walks.clone(), self.config);
         async move {
-            if network.is_empty() { return Err("Gecko: unsupported chain".to_string()); }
-            let url = format!(
-                "https://api.geckoterminal.com/api/v2/simple/networks/{}/token_price/{}",
-                network, address
-            );
-            let response = client.get(&url)
-                .header("User-Agent", &ua)
-                .header("Accept", "application/json")
-                .send()
-                .await.map_err(|e| format!("Gecko request: {}", e))?;
-            let status = response.status();
-            if !status.is_success() {
-                return Err(format!("Gecko status {}", status));
-            }
-            let data: GeckoTokenPriceResponse = response.json().await
-                .map_err(|e| format!("Gecko parse: {}", e))?;
-            let price_data = data.data.ok_or("Gecko: no data")?;
-            let attrs = price_data.attributes.ok_or("Gecko: no attributes")?;
-            let prices = attrs.token_prices.ok_or("Gecko: no token_prices")?;
-            let price_opt = prices.get(&address).or_else(|| prices.get(&address.to_lowercase()));
-            let price_str = price_opt
-                .and_then(|v| v.as_ref())
-                .ok_or("Gecko: token not in results")?;
-            let price: f64 = price_str.parse().map_err(|_| "Gecko: invalid price")?;
-            if price <= 0.0 { return Err("Gecko: price zero".to_string()); }
-            eprintln!("[price] GeckoTerminal OK: ${}", price);
+            let (price, start, _, _) = sandbox_tick(&walks, &config, &request.token_key());
             Ok(DexPriceResult {
-                price, change_24h: 0.0, volume_24h: 0.0,
-                pair_address: pa.unwrap_or_default(),
-                source: "gecko".to_string(),
+                change_24h: percent_change(start, price),
+                ..DexPriceResult::new("sandbox", price, price.to_string(), request.pair_address)
             })
         }
+        .boxed()
+    }
+}
+
+fn data_source_for(name: &str, breakers: &std::sync::Arc<SourceBreakers>) -> Result<std::sync::Arc<dyn DataSource>, String> {
+    match name {
+        "live" => Ok(std::sync::Arc::new(LiveDataSource { breakers: breakers.clone() })),
+        "mock" => Ok(std::sync::Arc::new(MockDataSource)),
+        "sandbox" => Ok(std::sync::Arc::new(SandboxDataSource::new(SANDBOX_DEFAULTS)?)),
+        _ => Err(format!("Unknown data source '{}', expected 'live', 'mock' or 'sandbox'", name)),
+    }
+}
+
+struct DataSourceState {
+    source: Mutex<std::sync::Arc<dyn DataSource>>,
+    // Shared across source switches so breaker history survives a mock round trip
+    breakers: std::sync::Arc<SourceBreakers>,
+}
+
+impl DataSourceState {
+    fn get(&self) -> std::sync::Arc<dyn DataSource> {
+        self.source.lock_or_recover().clone()
+    }
+}
+
+#[tauri::command]
+fn set_data_source(data_source: tauri::State<'_, DataSourceState>, name: String) -> Result<(), String> {
+    let source = data_source_for(&name, &data_source.breakers)?;
+    log::info!("[data] Using {} data source", source.name());
+    *data_source.source.lock_or_recover() = source;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_data_source(data_source: tauri::State<'_, DataSourceState>) -> String {
+    data_source.get().name().to_string()
+}
+
+// Switches to a fresh sandbox; every walk restarts from the new seed
+#[tauri::command]
+fn set_sandbox(data_source: tauri::State<'_, DataSourceState>, seed: u64, volatility: Option<f64>, drift: Option<f64>) -> Result<(), String> {
+    let config = SandboxConfig {
+        seed,
+        volatility: volatility.unwrap_or(SANDBOX_DEFAULTS.volatility),
+        drift: drift.unwrap_or(SANDBOX_DEFAULTS.drift),
     };
+    let source = SandboxDataSource::new(config)?;
+    log::info!("[data] Using sandbox data source ({:?})", config);
+    *data_source.source.lock_or_recover() = std::sync::Arc::new(source);
+    Ok(())
+}
 
-    let try_dexscreener = |client: &reqwest::Client, chain_id: &str, address: &str, pair_address: &Option<String>| {
-        let client = client.clone();
-        let chain_id = chain_id.to_string();
-        let address = address.to_string();
-        let pa = pair_address.clone();
-        let ua = ua.to_string();
-        async move {
-            // Try pairs endpoint first
-            if let Some(ref pa_str) = pa {
-                let url = format!("https://api.dexscreener.com/latest/dex/pairs/{}/{}", chain_id, pa_str);
-                if let Ok(response) = client.get(&url)
-                    .header("User-Agent", &ua)
-                    .send().await
-                {
-                    if let Ok(data) = response.json::<DexScreenerResponse>().await {
-                        let pair = data.pairs.as_ref().and_then(|p| p.first()).or(data.pair.as_ref());
-                        if let Some(pair) = pair {
-                            if let Some(ref ps) = pair.price_usd {
-                                if let Ok(price) = ps.parse::<f64>() {
-                                    if price > 0.0 {
-                                        eprintln!("[price] DexScreener OK: ${}", price);
-                                        return Ok(DexPriceResult {
-                                            price,
-                                            change_24h: pair.price_change.as_ref().and_then(|p| p.h24).unwrap_or(0.0),
-                                            volume_24h: pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0),
-                                            pair_address: pair.pair_address.clone().unwrap_or_default(),
-                                            source: "dexscreener".to_string(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
+// Opt-in schema drift check for debug builds (TRADING_SIM_STRICT_PARSE=1): the
+// structs stay lenient, but each payload is compared against what they model
+// and every field upstream added or dropped is logged once. Release builds
+// never pay for the extra pass.
+fn strict_parse_enabled() -> bool {
+    cfg!(debug_assertions) && std::env::var("TRADING_SIM_STRICT_PARSE").is_ok_and(|v| v == "1")
+}
+
+async fn read_json<T: serde::de::DeserializeOwned + Serialize>(source: &str, response: reqwest::Response) -> Result<T, String> {
+    if !strict_parse_enabled() {
+        return response.json().await.map_err(|e| e.to_string());
+    }
+    let raw: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let parsed: T = serde_json::from_value(raw.clone()).map_err(|e| e.to_string())?;
+    if let Ok(modeled) = serde_json::to_value(&parsed) {
+        let mut drift = Vec::new();
+        schema_drift("", &raw, &modeled, &mut drift);
+        report_schema_drift(source, drift);
+    }
+    Ok(parsed)
+}
+
+// "+path" for fields upstream sent that aren't modeled, "-path" for modeled
+// fields upstream left out. Array elements share one "[]" path.
+fn schema_drift(path: &str, raw: &serde_json::Value, modeled: &serde_json::Value, drift: &mut Vec<String>) {
+    use serde_json::Value;
+    match (raw, modeled) {
+        (Value::Object(raw), Value::Object(modeled)) => {
+            for (key, value) in raw {
+                let field = format!("{}.{}", path, key);
+                match modeled.get(key) {
+                    Some(modeled_value) => schema_drift(&field, value, modeled_value, drift),
+                    None => drift.push(format!("+{}", field)),
                 }
             }
-            // Fall back to tokens endpoint
-            let url = format!("https://api.dexscreener.com/latest/dex/tokens/{}", address);
-            let response = client.get(&url)
-                .header("User-Agent", &ua)
-                .send().await.map_err(|e| format!("DexScreener: {}", e))?;
-            let data: DexScreenerResponse = response.json().await
-                .map_err(|e| format!("DexScreener parse: {}", e))?;
-            let pairs = data.pairs.ok_or("DexScreener: no pairs")?;
-            let best = pairs.iter()
-                .filter(|p| p.chain_id.as_ref().map(|c| c.to_lowercase()) == Some(chain_id.to_lowercase()))
-                .max_by(|a, b| {
-                    let la = a.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
-                    let lb = b.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
-                    la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
-                })
-                .or_else(|| pairs.first())
-                .ok_or("DexScreener: no suitable pair")?;
-            let price: f64 = best.price_usd.as_ref().ok_or("DexScreener: no price")?
-                .parse().map_err(|_| "DexScreener: invalid price")?;
-            eprintln!("[price] DexScreener OK: ${}", price);
-            Ok(DexPriceResult {
-                price,
-                change_24h: best.price_change.as_ref().and_then(|p| p.h24).unwrap_or(0.0),
-                volume_24h: best.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0),
-                pair_address: best.pair_address.clone().unwrap_or_default(),
-                source: "dexscreener".to_string(),
-            })
+            drift.extend(modeled.keys().filter(|k| !raw.contains_key(*k)).map(|k| format!("-{}.{}", path, k)));
+        }
+        (Value::Array(raw), Value::Array(modeled)) => {
+            let element = format!("{}[]", path);
+            for (raw, modeled) in raw.iter().zip(modeled) {
+                schema_drift(&element, raw, modeled, drift);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn report_schema_drift(source: &str, drift: Vec<String>) {
+    static SEEN: std::sync::OnceLock<Mutex<std::collections::HashSet<String>>> = std::sync::OnceLock::new();
+    let mut seen = SEEN.get_or_init(Default::default).lock_or_recover();
+    for field in drift {
+        if seen.insert(format!("{}{}", source, field)) {
+            let (change, path) = field.split_at(1);
+            let what = if change == "+" { "unmodeled field" } else { "missing field" };
+            log::warn!("[schema] {} {} {}", source, what, path);
+        }
+    }
+}
+
+// Typed errors for the stock commands, serialized as { kind, message, ... }
+// so the UI can react to the kind instead of parsing strings
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+enum TradingError {
+    InvalidRange { message: String },
+    // Blank after trimming
+    InvalidSymbol { message: String },
+    InvalidIndicator { message: String },
+    // Retry-After is passed through verbatim (seconds or an HTTP date)
+    RateLimited { message: String, retry_after: Option<String> },
+    // Yahoo doesn't know the symbol (typo or delisted)
+    NotFound { message: String },
+    // A newer request with the same request_id replaced this one
+    Cancelled { message: String },
+    Upstream { message: String },
+}
+
+impl std::fmt::Display for TradingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradingError::InvalidRange { message } => write!(f, "{}", message),
+            TradingError::InvalidSymbol { message } => write!(f, "{}", message),
+            TradingError::InvalidIndicator { message } => write!(f, "{}", message),
+            TradingError::RateLimited { message, retry_after: Some(retry_after) } => {
+                write!(f, "{} (retry after {})", message, retry_after)
+            }
+            TradingError::RateLimited { message, retry_after: None } => write!(f, "{}", message),
+            TradingError::NotFound { message } => write!(f, "{}", message),
+            TradingError::Cancelled { message } => write!(f, "{}", message),
+            TradingError::Upstream { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for TradingError {
+    fn from(message: String) -> Self {
+        TradingError::Upstream { message }
+    }
+}
+
+fn configured_proxy(api: &ApiConfig) -> Option<&str> {
+    api.proxy_url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+}
+
+// Only http(s) proxies; reqwest is built without SOCKS support. The URL is
+// left out of errors since it may carry credentials.
+fn proxy_from_url(url: &str) -> Result<reqwest::Proxy, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid proxy_url: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err("Invalid proxy_url: expected http(s)://host:port".to_string());
+    }
+    reqwest::Proxy::all(parsed.as_str()).map_err(|e| format!("Invalid proxy_url: {}", e))
+}
+
+type ClientKey = (Duration, Option<String>);
+
+// Shared HTTP clients, one per timeout and proxy, so connections and TLS
+// sessions are reused across commands. A bad proxy_url is an error: falling
+// back would send traffic direct, around the proxy. Without a proxy, if the
+// build fails (TLS backend, system config) warn once and fall back to a plain
+// client that still has the timeout; that one isn't cached, so the next call
+// tries the configured build again. Errors only if the fallback fails too.
+fn http_client(api: &ApiConfig, timeout: Duration) -> Result<reqwest::Client, String> {
+    static CLIENTS: std::sync::OnceLock<Mutex<HashMap<ClientKey, reqwest::Client>>> = std::sync::OnceLock::new();
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let proxy_url = configured_proxy(api);
+    let key = (timeout, proxy_url.map(str::to_string));
+    let mut clients = CLIENTS.get_or_init(|| Mutex::new(HashMap::new())).lock_or_recover();
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let builder = reqwest::Client::builder().timeout(timeout);
+    let builder = match proxy_url {
+        Some(url) => builder.proxy(proxy_from_url(url)?),
+        None => builder.no_proxy(),
+    };
+    match builder.build() {
+        Ok(client) => {
+            clients.insert(key, client.clone());
+            Ok(client)
+        }
+        Err(e) if proxy_url.is_some() => Err(format!("HTTP client unavailable: {}", e)),
+        Err(e) => {
+            if !WARNED.swap(true, Ordering::Relaxed) {
+                log::error!("[http] Client build failed, falling back to defaults (system proxy): {}", e);
+            }
+            reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .map_err(|fallback| format!("HTTP client unavailable: {} (fallback: {})", e, fallback))
+        }
+    }
+}
+
+// A unique `_t` query param defeats every cache between us and Yahoo, which
+// guarantees fresh data but also skips Yahoo's own CDN and counts fully against
+// its rate limit. Polled quotes are fine with a few seconds of CDN caching, so
+// busting is opt-in for callers that must see the very latest bar.
+fn cache_buster(bypass_cache: bool, timestamp: u64) -> String {
+    if bypass_cache {
+        format!("&_t={}", timestamp)
+    } else {
+        String::new()
+    }
+}
+
+// Longest back-off honored from a Retry-After header, so a bogus date can't
+// silence a source for good
+const MAX_RETRY_AFTER_SECS: i64 = 3_600;
+
+// Retry-After is either delay seconds or an HTTP-date ("Wed, 21 Oct 2015 07:28:00 GMT")
+fn parse_retry_after(value: &str, now_ms: i64) -> Option<i64> {
+    let value = value.trim();
+    let until = match value.parse::<i64>() {
+        Ok(secs) => now_ms + secs.max(0) * 1000,
+        Err(_) => {
+            const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+            let mut parts = value.split_whitespace().skip(1);
+            let day: i64 = parts.next()?.parse().ok()?;
+            let month = parts.next().and_then(|m| MONTHS.iter().position(|name| *name == m))? as i64 + 1;
+            let year: i64 = parts.next()?.parse().ok()?;
+            let mut clock = parts.next()?.split(':').map(|p| p.parse::<i64>().ok());
+            let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+            (days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second) * 1000
+        }
+    };
+    Some(until.min(now_ms + MAX_RETRY_AFTER_SECS * 1000))
+}
+
+// Fails fast while Yahoo's last Retry-After is still running
+fn yahoo_backoff(breakers: &SourceBreakers) -> Result<(), TradingError> {
+    match breakers.retry_after_secs("yahoo") {
+        Some(wait) => Err(TradingError::RateLimited {
+            message: format!("Backing off Yahoo for another {}s after a rate limit", wait),
+            retry_after: Some(wait.to_string()),
+        }),
+        None => Ok(()),
+    }
+}
+
+// Yahoo answers 429 with an HTML or empty body, so catch it before parsing.
+// Other hosts share the same limit; callers should stop rather than fail over.
+fn rate_limit_error(breakers: &SourceBreakers, host: &str, response: &reqwest::Response) -> Option<TradingError> {
+    if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    breakers.note_retry_after("yahoo", response);
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    Some(TradingError::RateLimited {
+        message: format!("Rate limited by {}", host),
+        retry_after,
+    })
+}
+
+// Longest range (in days) Yahoo serves for each intraday interval.
+// None means any range is accepted.
+fn max_range_days(interval: &str) -> Result<Option<u32>, TradingError> {
+    match interval {
+        "1m" => Ok(Some(7)),
+        "2m" | "5m" | "15m" | "30m" | "90m" => Ok(Some(60)),
+        "60m" | "1h" => Ok(Some(730)),
+        "1d" | "5d" | "1wk" | "1mo" | "3mo" => Ok(None),
+        _ => Err(TradingError::InvalidRange {
+            message: format!("Unsupported interval '{}'", interval),
+        }),
+    }
+}
+
+// Approximate length of a Yahoo range string ("5d", "3mo", "1y", "ytd", "max") in days
+fn range_days(range: &str) -> Option<u32> {
+    match range {
+        "ytd" => return Some(365),
+        "max" => return Some(u32::MAX),
+        _ => {}
+    }
+    let split = range.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = range.split_at(split);
+    let count: u32 = count.parse().ok()?;
+    let per_unit = match unit {
+        "d" => 1,
+        "wk" => 7,
+        "mo" => 31,
+        "y" => 365,
+        _ => return None,
+    };
+    Some(count.saturating_mul(per_unit))
+}
+
+// Bar length for resampling `source` bars up to `target`; the target must be
+// a whole multiple of the source so every bucket is made of complete bars
+fn resample_secs(source: &str, target: &str) -> Result<i64, TradingError> {
+    let source_secs = interval_secs(source).ok_or_else(|| TradingError::InvalidRange {
+        message: format!("Unsupported interval '{}'", source),
+    })?;
+    let target_secs = interval_secs(target).ok_or_else(|| TradingError::InvalidRange {
+        message: format!("Unsupported target interval '{}'", target),
+    })?;
+    if target_secs < source_secs {
+        return Err(TradingError::InvalidRange {
+            message: format!(
+                "Target interval '{}' is finer than source interval '{}'; request a finer source",
+                target, source
+            ),
+        });
+    }
+    if target_secs % source_secs != 0 {
+        return Err(TradingError::InvalidRange {
+            message: format!(
+                "Target interval '{}' is not a multiple of source interval '{}'",
+                target, source
+            ),
+        });
+    }
+    Ok(target_secs)
+}
+
+fn validate_interval_range(interval: &str, range: &str) -> Result<(), TradingError> {
+    let max_days = max_range_days(interval)?;
+    let days = range_days(range).ok_or_else(|| TradingError::InvalidRange {
+        message: format!("Unsupported range '{}'", range),
+    })?;
+    if let Some(max_days) = max_days {
+        if days > max_days {
+            return Err(TradingError::InvalidRange {
+                message: format!(
+                    "Interval '{}' only supports ranges up to {} days, got '{}'",
+                    interval, max_days, range
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+// Yahoo Finance response structures
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooChartResponse {
+    chart: YahooChartResult,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooChartResult {
+    result: Option<Vec<YahooChartData>>,
+    error: Option<YahooError>,
+}
+
+// Yahoo's error envelope, e.g. { code: "Not Found", description: "No data found, symbol may be delisted" }.
+// Some hosts send a bare string instead; it is taken as the code.
+#[derive(Debug, Serialize)]
+struct YahooError {
+    code: String,
+    description: String,
+}
+
+impl<'de> Deserialize<'de> for YahooError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Envelope {
+            Object {
+                #[serde(default)]
+                code: String,
+                #[serde(default)]
+                description: String,
+            },
+            Text(String),
+            Other(serde_json::Value),
+        }
+        Ok(match Envelope::deserialize(deserializer)? {
+            Envelope::Object { code, description } => YahooError { code, description },
+            Envelope::Text(code) => YahooError { code, description: String::new() },
+            Envelope::Other(value) => YahooError { code: value.to_string(), description: String::new() },
+        })
+    }
+}
+
+impl YahooError {
+    // Bad or delisted symbols come back as "Not Found"; other hosts will say the same
+    fn not_found(&self) -> Option<TradingError> {
+        self.code.eq_ignore_ascii_case("Not Found").then(|| TradingError::NotFound {
+            message: if self.description.is_empty() { self.code.clone() } else { self.description.clone() },
+        })
+    }
+}
+
+impl std::fmt::Display for YahooError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.description.is_empty() {
+            write!(f, "{}", self.code)
+        } else {
+            write!(f, "{}: {}", self.code, self.description)
         }
+    }
+}
+
+// Yahoo also sends the envelope with non-2xx statuses (404 for unknown symbols)
+fn yahoo_error_from_body(body: &str) -> Option<YahooError> {
+    serde_json::from_str::<YahooChartResponse>(body).ok()?.chart.error
+}
+
+// Stooq CSV history: https://stooq.com/q/d/l/?s=aapl.us&i=d
+fn stooq_interval(interval: &str) -> Option<&'static str> {
+    match interval {
+        "1d" => Some("d"),
+        "1wk" => Some("w"),
+        "1mo" => Some("m"),
+        _ => None,
+    }
+}
+
+// Stooq suffixes US listings with ".us"; symbols that already carry an exchange
+// suffix pass through. Yahoo-style indices, futures and forex have no mapping.
+fn stooq_symbol(symbol: &str) -> Option<String> {
+    if symbol.contains(['^', '=']) {
+        return None;
+    }
+    let symbol = symbol.to_lowercase();
+    Some(if symbol.contains('.') { symbol } else { format!("{}.us", symbol) })
+}
+
+// "Date,Open,High,Low,Close,Volume" rows; indices omit the volume column
+fn parse_stooq_csv(csv: &str) -> Vec<StockCandle> {
+    csv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim().split(',').collect();
+            let mut date = fields.first()?.split('-').map(|p| p.parse::<i64>().ok());
+            let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+            let price = |i: usize| fields.get(i).and_then(|v| v.parse::<f64>().ok()).filter(|p| p.is_finite());
+            Some(StockCandle {
+                time: days_from_civil(year, month, day) * 86_400_000,
+                open: price(1)?,
+                high: price(2)?,
+                low: price(3)?,
+                close: price(4)?,
+                volume: price(5).unwrap_or(0.0) as i64,
+                filled: Vec::new(),
+                session: None,
+                time_iso: None,
+            })
+        })
+        .collect()
+}
+
+async fn try_stooq(client: &reqwest::Client, api: &ApiConfig, symbol: &str, interval: &str, range: &str) -> Result<StockChartResponse, String> {
+    let stooq_symbol = stooq_symbol(symbol).ok_or_else(|| format!("No stooq symbol for {}", symbol))?;
+    let url = format!("{}/q/d/l/?s={}&i={}", api.stooq_base_url, stooq_symbol, interval);
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .send()
+        .await
+        .map_err(|e| format!("stooq request: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("stooq status {}", status));
+    }
+    let body = response.text().await.map_err(|e| format!("stooq body: {}", e))?;
+
+    // Stooq always returns full history; trim to the requested range
+    let days = range_days(range).unwrap_or(u32::MAX) as i64;
+    let cutoff = now_millis().saturating_sub(days.saturating_mul(86_400_000));
+    let candles: Vec<StockCandle> = parse_stooq_csv(&body).into_iter().filter(|c| c.time >= cutoff).collect();
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return Err(format!("stooq: no data for {}", stooq_symbol));
     };
+    log::info!("[stooq] OK: {} candles for {}", candles.len(), stooq_symbol);
+
+    let previous_close = candles.len().checked_sub(2).map(|i| candles[i].close).unwrap_or(last.open);
+    Ok(StockChartResponse {
+        symbol: symbol.to_string(),
+        current_price: last.close,
+        previous_close,
+        day_high: last.high,
+        day_low: last.low,
+        volume: last.volume,
+        period_change: (candles.len() >= 2).then_some(last.close - first.open),
+        period_change_percent: if candles.len() >= 2 { percent_change(first.open, last.close) } else { None },
+        indicators: HashMap::new(),
+        levels: Vec::new(),
+        last_bar_complete: true,
+        timezone: None,
+        gmt_offset_secs: 0,
+        gaps: None,
+        total_candles: None,
+        candles,
+    })
+}
+
+// Trading period info
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct TradingPeriod {
+    #[serde(default)]
+    start: i64,
+    #[serde(default)]
+    end: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct CurrentTradingPeriod {
+    #[serde(default)]
+    pre: TradingPeriod,
+    #[serde(default)]
+    regular: TradingPeriod,
+    #[serde(default)]
+    post: TradingPeriod,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooChartMeta {
+    symbol: Option<String>,
+    #[serde(rename = "regularMarketPrice")]
+    regular_market_price: Option<f64>,
+    #[serde(rename = "previousClose")]
+    previous_close: Option<f64>,
+    #[serde(rename = "regularMarketDayHigh")]
+    regular_market_day_high: Option<f64>,
+    #[serde(rename = "regularMarketDayLow")]
+    regular_market_day_low: Option<f64>,
+    #[serde(rename = "regularMarketVolume")]
+    regular_market_volume: Option<i64>,
+    // Extended hours
+    #[serde(rename = "postMarketPrice")]
+    post_market_price: Option<f64>,
+    #[serde(rename = "preMarketPrice")]
+    pre_market_price: Option<f64>,
+    #[serde(rename = "postMarketChange")]
+    post_market_change: Option<f64>,
+    #[serde(rename = "preMarketChange")]
+    pre_market_change: Option<f64>,
+    #[serde(rename = "currentTradingPeriod")]
+    current_trading_period: Option<CurrentTradingPeriod>,
+    // Listing exchange, e.g. "LSE" / "London", and its zone with the current offset
+    #[serde(rename = "exchangeName")]
+    exchange_name: Option<String>,
+    #[serde(rename = "fullExchangeName")]
+    full_exchange_name: Option<String>,
+    #[serde(rename = "exchangeTimezoneName")]
+    exchange_timezone_name: Option<String>,
+    gmtoffset: Option<i64>,
+    #[serde(rename = "longName")]
+    long_name: Option<String>,
+    #[serde(rename = "shortName")]
+    short_name: Option<String>,
+    // Per-day session windows; an object of pre/regular/post arrays with
+    // includePrePost, a plain array of regular windows without it
+    #[serde(rename = "tradingPeriods")]
+    trading_periods: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct YahooTradingPeriods {
+    #[serde(default)]
+    pre: Vec<Vec<TradingPeriod>>,
+    #[serde(default)]
+    regular: Vec<Vec<TradingPeriod>>,
+    #[serde(default)]
+    post: Vec<Vec<TradingPeriod>>,
+}
+
+// (start, end, session) windows in epoch seconds from the chart meta
+fn session_windows(meta: &YahooChartMeta) -> Vec<(i64, i64, MarketStatus)> {
+    let mut windows = Vec::new();
+    let periods: YahooTradingPeriods = meta.trading_periods.clone()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    for (days, session) in [(&periods.pre, MarketStatus::Pre), (&periods.regular, MarketStatus::Regular), (&periods.post, MarketStatus::Post)] {
+        windows.extend(days.iter().flatten().map(|p| (p.start, p.end, session)));
+    }
+    if let Some(ref current) = meta.current_trading_period {
+        windows.push((current.pre.start, current.pre.end, MarketStatus::Pre));
+        windows.push((current.regular.start, current.regular.end, MarketStatus::Regular));
+        windows.push((current.post.start, current.post.end, MarketStatus::Post));
+    }
+    windows
+}
+
+// Bars outside every known window are treated as regular session
+fn session_at(windows: &[(i64, i64, MarketStatus)], time: i64) -> MarketStatus {
+    windows.iter()
+        .find(|(start, end, _)| time >= *start && time < *end)
+        .map(|(_, _, session)| *session)
+        .unwrap_or(MarketStatus::Regular)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooChartData {
+    meta: YahooChartMeta,
+    timestamp: Option<Vec<i64>>,
+    indicators: YahooIndicators,
+    // Only present when the request asks for events=div,split
+    #[serde(default)]
+    events: Option<YahooEvents>,
+}
+
+// Keyed by the event's epoch-seconds timestamp as a string
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct YahooEvents {
+    #[serde(default)]
+    dividends: HashMap<String, YahooDividend>,
+    #[serde(default)]
+    splits: HashMap<String, YahooSplit>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooDividend {
+    amount: f64,
+    date: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooSplit {
+    date: i64,
+    numerator: f64,
+    denominator: f64,
+    #[serde(rename = "splitRatio", default)]
+    split_ratio: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooIndicators {
+    quote: Vec<YahooQuoteData>,
+    // Split/dividend adjusted closes, only present for daily and longer intervals
+    #[serde(default)]
+    adjclose: Vec<YahooAdjClose>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooAdjClose {
+    #[serde(default)]
+    adjclose: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct YahooQuoteData {
+    #[serde(default)]
+    open: Vec<Option<f64>>,
+    #[serde(default)]
+    high: Vec<Option<f64>>,
+    #[serde(default)]
+    low: Vec<Option<f64>>,
+    #[serde(default)]
+    close: Vec<Option<f64>>,
+    #[serde(default)]
+    volume: Vec<Option<i64>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StockCandle {
+    time: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: i64,
+    // Fields synthesized by fill_gaps: a missing close/open carries forward the
+    // previous close, a missing high/low is the max/min of open and close
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    filled: Vec<String>,
+    // Pre, regular or post; only set when extended hours were requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<MarketStatus>,
+    // UTC ISO-8601 form of `time`, only set when iso_timestamps was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_iso: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StockChartResponse {
+    // Normalized form of the requested symbol, for the UI to store
+    symbol: String,
+    candles: Vec<StockCandle>,
+    current_price: f64,
+    previous_close: f64,
+    day_high: f64,
+    day_low: f64,
+    volume: i64,
+    // First candle's open to last candle's close, None with fewer than two candles
+    period_change: Option<f64>,
+    period_change_percent: Option<f64>,
+    // Requested indicators keyed by name, aligned index-for-index with candles
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    indicators: HashMap<String, Vec<Option<f64>>>,
+    // Swing-high/low pivots when levels_lookback was requested, oldest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    levels: Vec<levels::Level>,
+    // Whether the last returned bar's interval has ended; intraday feeds usually
+    // end with a bar that is still forming. Set by fetch_stock_candles.
+    last_bar_complete: bool,
+    // Candle times stay UTC; these let the UI render exchange-local times.
+    // The offset is the exchange's current one, so prefer the IANA zone when
+    // a range spans a DST change.
+    timezone: Option<String>,
+    gmt_offset_secs: i64,
+    // Expected-but-missing session dates (epoch ms of local midnight) when
+    // mark_gaps was requested on daily bars; holidays are not gaps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gaps: Option<Vec<i64>>,
+    // Length of the full series when limit/offset returned only a page of it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_candles: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StockQuote {
+    symbol: String,
+    price: f64, // latest trade, including pre/post-market
+    // Regular-session change against the previous close, even in pre/post;
+    // the extended-hours move is session_change
+    change: f64,
+    change_percent: Option<f64>, // None when previous_close is missing or not positive
+    high: f64,
+    low: f64,
+    volume: i64,
+    market_status: MarketStatus,
+    asset_class: String, // "equity", "future", "forex"
+    exchange: Option<String>,
+    timezone: Option<String>, // IANA name, e.g. "Europe/London"
+    gmt_offset_secs: i64,
+    // Regular-session price alongside the pre/post-market one, so both can be shown
+    regular_price: f64,
+    extended_price: Option<f64>,
+    // Pre/post-market move from the last regular close; None in regular hours
+    session_change: Option<f64>,
+    session_change_percent: Option<f64>,
+    // How long the UI or a background poller should wait before refreshing
+    suggested_refresh_secs: u64,
+    // Epoch ms the quote was taken at, and its UTC ISO-8601 form when
+    // iso_timestamps was requested
+    as_of: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    as_of_iso: Option<String>,
+}
+
+
+// Percent move from `from` to `to`; None when `from` isn't a positive price,
+// so bad data shows as unknown rather than a misleading 0%
+fn percent_change(from: f64, to: f64) -> Option<f64> {
+    (from.is_finite() && to.is_finite() && from > 0.0).then(|| (to - from) / from * 100.0)
+}
+
+// How resampled bars are grouped. Intraday buckets are aligned to the epoch,
+// days and weeks to exchange-local midnight (weeks start on Monday), and
+// months to calendar months.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bucket {
+    Secs(i64),
+    Days(i64),
+    Weeks(i64),
+    Months(i64),
+}
+
+impl Bucket {
+    // `secs` is the target's length from resample_secs
+    fn for_target(target: &str, secs: i64) -> Bucket {
+        if target.ends_with("mo") {
+            Bucket::Months(secs / (30 * 86_400))
+        } else if target.ends_with("wk") {
+            Bucket::Weeks(secs / (7 * 86_400))
+        } else if secs % 86_400 == 0 {
+            Bucket::Days(secs / 86_400)
+        } else {
+            Bucket::Secs(secs)
+        }
+    }
+
+    // Epoch ms where the bucket holding `time_ms` opens and where the next one does
+    fn span(self, time_ms: i64, gmt_offset_secs: i64) -> (i64, i64) {
+        let local_day = (time_ms.div_euclid(1000) + gmt_offset_secs).div_euclid(86_400);
+        let to_ms = |day: i64| (day * 86_400 - gmt_offset_secs) * 1000;
+        match self {
+            Bucket::Secs(secs) => {
+                let open = time_ms - time_ms.rem_euclid(secs * 1000);
+                (open, open + secs * 1000)
+            }
+            Bucket::Days(n) => {
+                let open = local_day - local_day.rem_euclid(n);
+                (to_ms(open), to_ms(open + n))
+            }
+            Bucket::Weeks(n) => {
+                // Whole weeks since Monday 1970-01-05 (day 4)
+                let week = (local_day - weekday(local_day) - 4) / 7;
+                let open = 4 + (week - week.rem_euclid(n)) * 7;
+                (to_ms(open), to_ms(open + 7 * n))
+            }
+            Bucket::Months(n) => {
+                let (year, month, _) = civil_from_days(local_day);
+                let index = year * 12 + month - 1;
+                let open = index - index.rem_euclid(n);
+                let first_day = |index: i64| days_from_civil(index.div_euclid(12), index.rem_euclid(12) + 1, 1);
+                (to_ms(first_day(open)), to_ms(first_day(open + n)))
+            }
+        }
+    }
+}
+
+// Aggregate bars into `bucket`s, each stamped at its open:
+// open=first, high=max, low=min, close=last, volume=sum
+fn resample_candles(candles: &[StockCandle], bucket: Bucket, gmt_offset_secs: i64) -> Vec<StockCandle> {
+    let mut result: Vec<StockCandle> = Vec::new();
+    for candle in candles {
+        let (bucket, _) = bucket.span(candle.time, gmt_offset_secs);
+        match result.last_mut() {
+            Some(bar) if bar.time == bucket => {
+                bar.high = bar.high.max(candle.high);
+                bar.low = bar.low.min(candle.low);
+                bar.close = candle.close;
+                bar.volume += candle.volume;
+                for field in &candle.filled {
+                    if !bar.filled.contains(field) {
+                        bar.filled.push(field.clone());
+                    }
+                }
+            }
+            _ => result.push(StockCandle { time: bucket, ..candle.clone() }),
+        }
+    }
+    result
+}
+
+// Converts Yahoo's parallel OHLCV arrays into candles. Without fill_gaps any bar
+// with a null price field is dropped; with it, bars are kept whenever a close is
+// known or can be carried forward.
+fn build_candles(timestamps: &[i64], quote: &YahooQuoteData, adjclose: Option<&[Option<f64>]>, fill_gaps: bool) -> Vec<StockCandle> {
+    let mut candles = Vec::with_capacity(timestamps.len());
+    let mut prev_close: Option<f64> = None;
+
+    for (i, &time) in timestamps.iter().enumerate() {
+        let open = quote.open.get(i).and_then(|v| *v);
+        let high = quote.high.get(i).and_then(|v| *v);
+        let low = quote.low.get(i).and_then(|v| *v);
+        let close = quote.close.get(i).and_then(|v| *v);
+
+        let (open, high, low, close, filled) = match (open, high, low, close) {
+            (Some(open), Some(high), Some(low), Some(close)) => (open, high, low, close, Vec::new()),
+            _ if !fill_gaps => continue,
+            (None, None, None, None) => continue,
+            _ => {
+                let mut filled = Vec::new();
+                if close.is_none() {
+                    filled.push("close".to_string());
+                }
+                let Some(close) = close.or(prev_close) else { continue };
+                let open = open.unwrap_or_else(|| {
+                    filled.push("open".to_string());
+                    prev_close.unwrap_or(close)
+                });
+                let high = high.unwrap_or_else(|| {
+                    filled.push("high".to_string());
+                    open.max(close)
+                });
+                let low = low.unwrap_or_else(|| {
+                    filled.push("low".to_string());
+                    open.min(close)
+                });
+                (open, high, low, close, filled)
+            }
+        };
+        prev_close = Some(close);
+
+        // Scale the whole bar by adjclose/close so OHLC stay consistent
+        let factor = adjclose
+            .and_then(|a| a.get(i).and_then(|v| *v))
+            .filter(|_| close != 0.0)
+            .map(|adj| adj / close)
+            .unwrap_or(1.0);
+
+        candles.push(StockCandle {
+            time: time * 1000,
+            open: open * factor,
+            high: high * factor,
+            low: low * factor,
+            close: close * factor,
+            volume: quote.volume.get(i).and_then(|v| *v).unwrap_or(0),
+            filled,
+            session: None,
+            time_iso: None,
+        });
+    }
+
+    candles
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn fetch_stock_candles(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol: String,
+    interval: String,
+    range: String,
+    adjusted: Option<bool>,
+    fill_gaps: Option<bool>,
+    target_interval: Option<String>,
+    indicators: Option<Vec<String>>,
+    request_id: Option<String>,
+    bypass_cache: Option<bool>,
+    drop_incomplete: Option<bool>,
+    include_extended: Option<bool>,
+    mark_gaps: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    levels_lookback: Option<usize>,
+    iso_timestamps: Option<bool>,
+) -> Result<StockChartResponse, TradingError> {
+    let mark_gaps = mark_gaps.unwrap_or(false);
+    if mark_gaps && interval != "1d" {
+        return Err(TradingError::InvalidRange {
+            message: format!("mark_gaps needs daily bars, got interval '{}'", interval),
+        });
+    }
+    let bucket = match target_interval.filter(|t| *t != interval) {
+        Some(target) => Some(Bucket::for_target(&target, resample_secs(&interval, &target)?)),
+        None => None,
+    };
+    let bar_secs = interval_secs(&interval);
+    let request = CandleRequest {
+        symbol: required_stock_symbol(&symbol)?,
+        interval,
+        range,
+        adjusted: adjusted.unwrap_or(false),
+        fill_gaps: fill_gaps.unwrap_or(false),
+        include_extended: include_extended.unwrap_or(false),
+        bypass_cache: bypass_cache.unwrap_or(false),
+    };
+    validate_interval_range(&request.interval, &request.range)?;
+
+    let key = request.key();
+    let symbol = request.symbol.clone();
+    let fetch = data_source.get().stock_candles(api_config.get(), request);
+    let mut response = in_flight.supersede.run(request_id, in_flight.candles.run(key, fetch)).await?;
+    response.symbol = symbol;
+    // Before resampling, while there is still one bar per session
+    if mark_gaps {
+        response.gaps = Some(missing_sessions(&response.candles, response.gmt_offset_secs));
+    }
+    let now = now_millis();
+    match bucket {
+        Some(bucket) => {
+            response.candles = resample_candles(&response.candles, bucket, response.gmt_offset_secs);
+            response.last_bar_complete = response.candles.last()
+                .map_or(true, |last| bucket.span(last.time, response.gmt_offset_secs).1 <= now);
+        }
+        None => response.last_bar_complete = last_bar_complete(&response.candles, bar_secs, now),
+    }
+    if drop_incomplete.unwrap_or(false) && !response.last_bar_complete {
+        response.candles.pop();
+        response.last_bar_complete = true;
+    }
+    let closes: Vec<f64> = response.candles.iter().map(|c| c.close).collect();
+    for name in indicators.unwrap_or_default() {
+        let values = indicators::compute(&name, &closes)
+            .map_err(|message| TradingError::InvalidIndicator { message })?;
+        response.indicators.insert(name, values);
+    }
+    if let Some(lookback) = levels_lookback {
+        let bars: Vec<(i64, f64, f64)> = response.candles.iter().map(|c| (c.time, c.high, c.low)).collect();
+        response.levels = levels::find_levels(&bars, lookback)
+            .map_err(|message| TradingError::InvalidIndicator { message })?;
+    }
+    // Page after indicators so their warm-up sees the whole series
+    if limit.is_some() || offset.is_some() {
+        let total = response.candles.len();
+        let page = page_range(total, limit, offset.unwrap_or(0));
+        if let Some(gaps) = response.gaps.as_mut() {
+            *gaps = page_gaps(gaps, &response.candles, &page, response.gmt_offset_secs);
+        }
+        // A bar with a newer one after it has closed; an empty page has no last bar
+        if page.end < total || page.is_empty() {
+            response.last_bar_complete = true;
+        }
+        response.candles = response.candles[page.clone()].to_vec();
+        for values in response.indicators.values_mut() {
+            *values = values[page.clone()].to_vec();
+        }
+        let span = response.candles.first().zip(response.candles.last()).map(|(a, b)| (a.time, b.time));
+        levels::trim_to_span(&mut response.levels, span);
+        response.total_candles = Some(total);
+    }
+    if iso_timestamps.unwrap_or(false) {
+        for candle in &mut response.candles {
+            candle.time_iso = Some(iso8601_utc(candle.time));
+        }
+    }
+    Ok(response)
+}
+
+// Index range of a page counted back from the newest bar: offset skips that
+// many of the most recent bars, limit caps the page (None = everything older)
+fn page_range(len: usize, limit: Option<usize>, offset: usize) -> std::ops::Range<usize> {
+    let end = len.saturating_sub(offset);
+    let start = limit.map_or(0, |limit| end.saturating_sub(limit));
+    start..end
+}
+
+// Gaps from the page's first session up to the bar after the page, so each
+// gap of the series lands on exactly one page; an empty page has none
+fn page_gaps(gaps: &[i64], candles: &[StockCandle], page: &std::ops::Range<usize>, gmt_offset_secs: i64) -> Vec<i64> {
+    if page.is_empty() {
+        return Vec::new();
+    }
+    let session = |i: usize| candles.get(i).map(|c| local_day(c.time, gmt_offset_secs) * 86_400_000);
+    let (from, until) = (session(page.start), session(page.end));
+    gaps.iter()
+        .copied()
+        .filter(|&gap| from.map_or(true, |f| gap >= f) && until.map_or(true, |u| gap < u))
+        .collect()
+}
+
+// A bar stamped at its open is complete once a full interval has passed;
+// unknown intervals are assumed complete
+fn last_bar_complete(candles: &[StockCandle], bar_secs: Option<i64>, now_ms: i64) -> bool {
+    match (candles.last(), bar_secs) {
+        (Some(last), Some(secs)) => last.time + secs * 1000 <= now_ms,
+        _ => true,
+    }
+}
+
+// Yahoo can return several results (comma-joined symbols); prefer the one
+// for `symbol`, then the rest in order
+fn results_for<'a>(results: &'a [YahooChartData], symbol: &'a str) -> impl Iterator<Item = &'a YahooChartData> {
+    let matches = move |r: &&YahooChartData| r.meta.symbol.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(symbol));
+    results.iter().filter(matches).chain(results.iter().filter(move |r| !matches(r)))
+}
+
+// Yahoo symbol shapes: "ES=F" futures, "EURUSD=X" forex, everything else trades equity sessions
+fn asset_class(symbol: &str) -> &'static str {
+    let symbol = symbol.to_ascii_uppercase();
+    if symbol.ends_with("=F") {
+        "future"
+    } else if symbol.ends_with("=X") {
+        "forex"
+    } else {
+        "equity"
+    }
+}
+
+// Days since the epoch for a proleptic Gregorian date (Howard Hinnant)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Inverse of days_from_civil: (year, month, day). Howard Hinnant's
+// civil-from-days, proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + yoe + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// 0 = Monday through 6 = Sunday; the epoch was a Thursday
+fn weekday(days: i64) -> i64 {
+    (days + 3).rem_euclid(7)
+}
+
+// The n-th `weekday` of a month, counting from 1; n = 0 is the last one
+fn nth_weekday(year: i64, month: i64, weekday_index: i64, n: i64) -> i64 {
+    if n == 0 {
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let last = days_from_civil(next_year, next_month, 1) - 1;
+        return last - (weekday(last) - weekday_index).rem_euclid(7);
+    }
+    let first = days_from_civil(year, month, 1);
+    first + (weekday_index - weekday(first)).rem_euclid(7) + 7 * (n - 1)
+}
+
+// Gregorian Easter Sunday (anonymous Gregorian algorithm)
+fn easter_sunday(year: i64) -> i64 {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    days_from_civil(year, month, day)
+}
+
+// Major NYSE holidays. Fixed-date ones shift to the Friday before or Monday
+// after a weekend, except New Year's Day on a Saturday, which isn't made up.
+fn is_us_market_holiday(days: i64) -> bool {
+    let (year, _, _) = civil_from_days(days);
+    let observed = |month: i64, day: i64| {
+        let date = days_from_civil(year, month, day);
+        match weekday(date) {
+            5 => date - 1,
+            6 => date + 1,
+            _ => date,
+        }
+    };
+    let mut holidays = vec![
+        nth_weekday(year, 1, 0, 3), // Martin Luther King Jr. Day
+        nth_weekday(year, 2, 0, 3), // Presidents' Day
+        easter_sunday(year) - 2, // Good Friday
+        nth_weekday(year, 5, 0, 0), // Memorial Day
+        observed(7, 4), // Independence Day
+        nth_weekday(year, 9, 0, 1), // Labor Day
+        nth_weekday(year, 11, 3, 4), // Thanksgiving
+        observed(12, 25), // Christmas
+    ];
+    if weekday(days_from_civil(year, 1, 1)) != 5 {
+        holidays.push(observed(1, 1));
+    }
+    if year >= 2022 {
+        holidays.push(observed(6, 19)); // Juneteenth
+    }
+    holidays.contains(&days)
+}
+
+// Days since the epoch of the exchange-local date of `time_ms`
+fn local_day(time_ms: i64, gmt_offset_secs: i64) -> i64 {
+    (time_ms.div_euclid(1000) + gmt_offset_secs).div_euclid(86_400)
+}
+
+// Weekday, non-holiday dates between the first and last daily bar that have
+// no bar, as epoch ms of their midnight. Bars are dated in exchange-local time.
+fn missing_sessions(candles: &[StockCandle], gmt_offset_secs: i64) -> Vec<i64> {
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else { return Vec::new() };
+    let present: std::collections::HashSet<i64> = candles.iter().map(|c| local_day(c.time, gmt_offset_secs)).collect();
+    (local_day(first.time, gmt_offset_secs)..=local_day(last.time, gmt_offset_secs))
+        .filter(|day| weekday(*day) < 5 && !present.contains(day) && !is_us_market_holiday(*day))
+        .map(|day| day * 86_400_000)
+        .collect()
+}
+
+// New York UTC offset: EDT from the second Sunday of March to the first Sunday
+// of November, switching at 02:00 local
+fn new_york_offset_secs(now: i64) -> i64 {
+    // Average-length years; only off around New Year, far from either switch
+    let year = 1970 + now.div_euclid(31_557_600);
+    let sunday_on_or_after = |days: i64| days + (6 - weekday(days));
+    let dst_start = sunday_on_or_after(days_from_civil(year, 3, 8)) * 86_400 + 7 * 3_600;
+    let dst_end = sunday_on_or_after(days_from_civil(year, 11, 1)) * 86_400 + 6 * 3_600;
+    if now >= dst_start && now < dst_end { -4 * 3_600 } else { -5 * 3_600 }
+}
+
+// Futures (CME Globex) trade Sunday 18:00 to Friday 17:00 New York time with a
+// daily halt at 17:00; forex trades Sunday 17:00 to Friday 17:00 without one.
+// Exchange holidays aren't modelled.
+fn around_the_clock_status(asset_class: &str, now: i64) -> MarketStatus {
+    let local = now + new_york_offset_secs(now);
+    let minute = local.rem_euclid(86_400) / 60;
+    let close = 17 * 60;
+    let open = if asset_class == "future" { 18 * 60 } else { close };
+
+    let trading = match weekday(local.div_euclid(86_400)) {
+        5 => false,
+        6 => minute >= open,
+        4 => minute < close,
+        _ => minute < close || minute >= open,
+    };
+    if trading { MarketStatus::Regular } else { MarketStatus::Closed }
+}
+
+fn is_local_weekend(now: i64, gmt_offset_secs: i64) -> bool {
+    weekday((now + gmt_offset_secs).div_euclid(86_400)) >= 5
+}
+
+// Session of a quote or bar; serializes as "pre", "regular", "post", "closed"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MarketStatus {
+    Pre,
+    Regular,
+    Post,
+    Closed,
+}
+
+// Without session windows, an equity whose last 1m bar is older than this is
+// taken as closed; thin names can go a few minutes without a trade
+const LIVE_BAR_GAP_SECS: i64 = 15 * 60;
+
+// Futures, forex and crypto follow the around-the-clock schedule; equities use
+// the session windows Yahoo reports for the current day. When it reports none,
+// `last_candle_ts` (epoch seconds of the latest bar with a close) stands in:
+// no recent bar means nothing is trading.
+fn market_status(meta: &YahooChartMeta, now: i64, last_candle_ts: Option<i64>) -> MarketStatus {
+    let class = meta.symbol.as_deref().map(asset_class).unwrap_or("equity");
+    if class != "equity" {
+        return around_the_clock_status(class, now);
+    }
+    // tradingPeriods covers earlier days too, so a simulated past instant is
+    // judged against its own day's sessions rather than today's
+    let windows = session_windows(meta);
+    if !windows.is_empty() {
+        return windows.iter()
+            .find(|(start, end, _)| now >= *start && now < *end)
+            .map_or(MarketStatus::Closed, |(_, _, session)| *session);
+    }
+    // No session windows: at least don't call an exchange open on its own weekend
+    if meta.gmtoffset.is_some_and(|offset| is_local_weekend(now, offset)) {
+        return MarketStatus::Closed;
+    }
+    match last_candle_ts {
+        Some(time) if now - time > LIVE_BAR_GAP_SECS => MarketStatus::Closed,
+        _ => MarketStatus::Regular,
+    }
+}
+
+// Chart window for a quote as of `now` (epoch seconds). A live quote takes the
+// latest day; an earlier simulated instant asks for the days around it so its
+// bars and trading periods are in the response.
+fn quote_window(now: i64, wall_now: i64) -> String {
+    if now >= wall_now - LIVE_BAR_GAP_SECS {
+        "range=1d".to_string()
+    } else {
+        format!("period1={}&period2={}", now - 86_400, now + 86_400)
+    }
+}
+
+// Session-aware price picked from one chart result; shared by quotes and candles
+struct PriceSelection {
+    market_status: MarketStatus,
+    price: f64,
+    change: f64, // regular session only
+    regular_price: f64,
+    previous_close: f64,
+    extended_price: Option<f64>,
+    session_change: Option<f64>, // relative to regular_price
+}
+
+fn select_price(result: &YahooChartData, now: i64) -> PriceSelection {
+    let meta = &result.meta;
+    let regular_price = meta.regular_market_price.unwrap_or(0.0);
+    let previous_close = meta.previous_close.unwrap_or(regular_price);
+
+    let last_bar = match (&result.timestamp, result.indicators.quote.first()) {
+        (Some(timestamps), Some(quote)) => last_valid_bar(timestamps, &quote.close, now),
+        _ => None,
+    };
+    let market_status = market_status(meta, now, last_bar.map(|(time, _)| time));
+    let last_candle_price = last_bar.map(|(_, close)| close).unwrap_or(regular_price);
+
+    // Yahoo's pre/post changes are measured from the last regular close, which
+    // is regular_price both after hours and before the open
+    let (extended_price, session_change) = match market_status {
+        MarketStatus::Post => {
+            let ext_price = meta.post_market_price.unwrap_or(last_candle_price);
+            (Some(ext_price), Some(meta.post_market_change.unwrap_or(ext_price - regular_price)))
+        }
+        MarketStatus::Pre => {
+            let ext_price = meta.pre_market_price.unwrap_or(last_candle_price);
+            (Some(ext_price), Some(meta.pre_market_change.unwrap_or(ext_price - regular_price)))
+        }
+        _ => (None, None),
+    };
+
+    PriceSelection {
+        market_status,
+        price: extended_price.unwrap_or(regular_price),
+        change: regular_price - previous_close,
+        regular_price,
+        previous_close,
+        extended_price,
+        session_change,
+    }
+}
+
+// Meta's day high/low are sometimes null or 0 mid-session for thin names while
+// the bars are fine; fill a missing side from the regular-session 1m bars
+fn day_range(result: &YahooChartData) -> (f64, f64) {
+    let meta = &result.meta;
+    let valid = |value: Option<f64>| value.filter(|v| v.is_finite() && *v > 0.0);
+    let (meta_high, meta_low) = (valid(meta.regular_market_day_high), valid(meta.regular_market_day_low));
+    if let (Some(high), Some(low)) = (meta_high, meta_low) {
+        return (high, low);
+    }
+
+    // Only bars inside a known regular window count: without trading periods
+    // there's no telling pre/post-market bars from regular ones, so no fallback
+    let mut bars: Option<(f64, f64)> = None;
+    if let (Some(timestamps), Some(quote)) = (&result.timestamp, result.indicators.quote.first()) {
+        let windows = session_windows(meta);
+        let in_regular = |time: i64| windows.iter()
+            .any(|&(start, end, session)| session == MarketStatus::Regular && time >= start && time < end);
+        for (i, &time) in timestamps.iter().enumerate() {
+            if !in_regular(time) {
+                continue;
+            }
+            let (Some(high), Some(low)) = (valid(quote.high.get(i).copied().flatten()), valid(quote.low.get(i).copied().flatten())) else {
+                continue;
+            };
+            bars = Some(match bars {
+                Some((max, min)) => (max.max(high), min.min(low)),
+                None => (high, low),
+            });
+        }
+    }
+    let (bar_high, bar_low) = bars.unwrap_or((0.0, 0.0));
+    (meta_high.unwrap_or(bar_high), meta_low.unwrap_or(bar_low))
+}
+
+fn chart_to_quote(result: &YahooChartData, symbol: &str, now: i64, refresh: &QuoteRefresh) -> StockQuote {
+    let meta = &result.meta;
+    let selection = select_price(result, now);
+    let (high, low) = day_range(result);
+    let change_percent = percent_change(selection.previous_close, selection.previous_close + selection.change);
+
+    let symbol = meta.symbol.clone().unwrap_or_else(|| symbol.to_string());
+    StockQuote {
+        asset_class: asset_class(&symbol).to_string(),
+        exchange: meta.full_exchange_name.clone().or_else(|| meta.exchange_name.clone()),
+        timezone: meta.exchange_timezone_name.clone(),
+        gmt_offset_secs: meta.gmtoffset.unwrap_or(0),
+        symbol,
+        price: selection.price,
+        change: selection.change,
+        change_percent,
+        high,
+        low,
+        volume: meta.regular_market_volume.unwrap_or(0),
+        market_status: selection.market_status,
+        regular_price: selection.regular_price,
+        extended_price: selection.extended_price,
+        session_change: selection.session_change,
+        session_change_percent: selection.session_change
+            .and_then(|change| percent_change(selection.regular_price, selection.regular_price + change)),
+        suggested_refresh_secs: refresh.for_status(selection.market_status),
+        as_of: now * 1000,
+        as_of_iso: None,
+    }
+}
+
+// None when the result carries neither bars nor a regular market price
+fn chart_to_response(result: &YahooChartData, adjusted: bool, fill_gaps: bool, include_extended: bool, now: i64) -> Option<StockChartResponse> {
+    let meta = &result.meta;
+    let adjclose = if adjusted {
+        result.indicators.adjclose.first().map(|a| a.adjclose.as_slice())
+    } else {
+        None
+    };
+    // Illiquid tickers before the open can come back with meta but no bars;
+    // answer with no candles and the meta prices so the quote header still shows
+    let mut candles = match (result.timestamp.as_deref(), result.indicators.quote.first()) {
+        (Some(timestamps), Some(quote)) => build_candles(timestamps, quote, adjclose, fill_gaps),
+        _ if meta.regular_market_price.is_some() => Vec::new(),
+        _ => return None,
+    };
+    if include_extended {
+        let windows = session_windows(meta);
+        for candle in &mut candles {
+            candle.session = Some(session_at(&windows, candle.time / 1000));
+        }
+    }
+
+    let (period_change, period_change_percent) = match (candles.first(), candles.last()) {
+        (Some(first), Some(last)) if candles.len() >= 2 => {
+            (Some(last.close - first.open), percent_change(first.open, last.close))
+        }
+        _ => (None, None),
+    };
+
+    Some(StockChartResponse {
+        symbol: meta.symbol.clone().unwrap_or_default(),
+        candles,
+        current_price: select_price(result, now).price,
+        previous_close: meta.previous_close.unwrap_or(0.0),
+        day_high: meta.regular_market_day_high.unwrap_or(0.0),
+        day_low: meta.regular_market_day_low.unwrap_or(0.0),
+        volume: meta.regular_market_volume.unwrap_or(0),
+        period_change,
+        period_change_percent,
+        indicators: HashMap::new(),
+        levels: Vec::new(),
+        last_bar_complete: true,
+        timezone: meta.exchange_timezone_name.clone(),
+        gmt_offset_secs: meta.gmtoffset.unwrap_or(0),
+        gaps: None,
+        total_candles: None,
+    })
+}
+
+async fn load_stock_candles(api: ApiConfig, breakers: std::sync::Arc<SourceBreakers>, request: CandleRequest) -> Result<StockChartResponse, TradingError> {
+    yahoo_backoff(&breakers)?;
+    let CandleRequest { symbol, interval, range, adjusted, fill_gaps, include_extended, bypass_cache } = request;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let client = http_client(&api, Duration::from_secs(api.yahoo_timeout_secs))?;
+
+    let ua = api.user_agent.as_str();
+    let hosts = &api.yahoo_hosts;
+    let mut last_error = String::from("No Yahoo Finance host responded");
+
+    for host in hosts {
+        let url = format!(
+            "https://{}/v8/finance/chart/{}?interval={}&range={}{}{}",
+            host, symbol, interval, range,
+            if include_extended { "&includePrePost=true" } else { "" },
+            cache_buster(bypass_cache, timestamp)
+        );
+
+        let response = match client
+            .get(&url)
+            .header("User-Agent", ua)
+            .header("Accept", "application/json")
+            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("[Yahoo] {} request failed: {}", host, e);
+                last_error = format!("{} request failed: {}", host, e);
+                continue;
+            }
+        };
+
+        if let Some(err) = rate_limit_error(&breakers, host, &response) {
+            log::warn!("[Yahoo] {}", err);
+            return Err(err);
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            log::warn!("[Yahoo] {} returned HTTP {}: {}", host, status, &body[..body.len().min(500)]);
+            if let Some(err) = yahoo_error_from_body(&body).and_then(|e| e.not_found()) {
+                return Err(err);
+            }
+            last_error = format!("HTTP {} from {}", status, host);
+            continue;
+        }
+
+        let data: YahooChartResponse = match read_json("Yahoo chart", response).await {
+            Ok(d) => d,
+            Err(e) => {
+                log::warn!("[Yahoo] {} JSON parse error: {}", host, e);
+                last_error = format!("JSON parse error: {}", e);
+                continue;
+            }
+        };
+
+        // Check for API-level error
+        if let Some(ref err) = data.chart.error {
+            log::warn!("[Yahoo] API error: {:?}", err);
+            if let Some(not_found) = err.not_found() {
+                return Err(not_found);
+            }
+            last_error = format!("Yahoo API error: {}", err);
+            continue;
+        }
+
+        if let Some(results) = data.chart.result {
+            let now = timestamp as i64;
+            let response = results_for(&results, &symbol)
+                .find_map(|result| chart_to_response(result, adjusted, fill_gaps, include_extended, now));
+            if let Some(response) = response {
+                log::info!("[Yahoo] {} OK: {} candles for {}", host, response.candles.len(), symbol);
+                return Ok(response);
+            }
+        }
+
+        last_error = format!("No chart data in response from {}", host);
+    }
+
+    // Yahoo unreachable (not a bad symbol, which returned above): daily-or-longer
+    // bars can still come from stooq
+    if let Some(stooq_interval) = stooq_interval(&interval) {
+        log::warn!("[Yahoo] All hosts failed ({}), trying stooq", last_error);
+        match try_stooq(&client, &api, &symbol, stooq_interval, &range).await {
+            Ok(response) => return Ok(response),
+            Err(e) => log::warn!("[stooq] {}", e),
+        }
+    }
+
+    Err(last_error.into())
+}
+
+// Yahoo symbols are uppercase, suffixes included ("EURUSD=X", "VOD.L", "^GSPC");
+// lowercase or padded input just comes back empty
+fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_uppercase()
+}
+
+// Normalized, and never blank, so a bad symbol fails before any upstream call
+fn required_symbol(symbol: &str) -> Result<String, String> {
+    let symbol = normalize_symbol(symbol);
+    if symbol.is_empty() {
+        return Err("Symbol is required".to_string());
+    }
+    Ok(symbol)
+}
+
+// For the commands that return TradingError
+fn required_stock_symbol(symbol: &str) -> Result<String, TradingError> {
+    required_symbol(symbol).map_err(|message| TradingError::InvalidSymbol { message })
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn fetch_stock_quote(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    clock: tauri::State<'_, ClockState>,
+    prefetched: tauri::State<'_, PrefetchCache>,
+    symbol: String,
+    request_id: Option<String>,
+    bypass_cache: Option<bool>,
+    iso_timestamps: Option<bool>,
+) -> Result<StockQuote, TradingError> {
+    let symbol = required_stock_symbol(&symbol)?;
+    let bypass_cache = bypass_cache.unwrap_or(false);
+    let prefetched = if bypass_cache { None } else { take_prefetched(&prefetched.quotes, &symbol) };
+    let mut quote = match prefetched {
+        Some(quote) => quote,
+        None => {
+            let now_ms = clock.now_millis();
+            let key = quote_key(&symbol, bypass_cache, now_ms);
+            let fetch = data_source.get().stock_quote(api_config.get(), symbol, bypass_cache, now_ms);
+            in_flight.supersede.run(request_id, in_flight.quotes.run(key, fetch)).await?
+        }
+    };
+    if iso_timestamps.unwrap_or(false) {
+        quote.as_of_iso = Some(iso8601_utc(quote.as_of));
+    }
+    Ok(quote)
+}
+
+// During the pre-market roll Yahoo sometimes appends a placeholder bar stamped
+// slightly in the future; never take a close from one
+const FUTURE_BAR_TOLERANCE_SECS: i64 = 60;
+
+// Time and close of the most recent non-null close at or before `now` (epoch seconds)
+fn last_valid_bar(timestamps: &[i64], closes: &[Option<f64>], now: i64) -> Option<(i64, f64)> {
+    timestamps.iter()
+        .zip(closes)
+        .rev()
+        .filter(|(time, _)| **time <= now + FUTURE_BAR_TOLERANCE_SECS)
+        .find_map(|(time, close)| close.map(|close| (*time, close)))
+}
+
+async fn load_stock_quote(api: ApiConfig, breakers: std::sync::Arc<SourceBreakers>, symbol: String, bypass_cache: bool, now_ms: i64) -> Result<StockQuote, TradingError> {
+    yahoo_backoff(&breakers)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let client = http_client(&api, Duration::from_secs(api.yahoo_timeout_secs))?;
+
+    let ua = api.user_agent.as_str();
+    let hosts = &api.yahoo_hosts;
+    let mut last_error = String::from("No Yahoo Finance host responded");
+
+    for host in hosts {
+        let url = format!(
+            "https://{}/v8/finance/chart/{}?interval=1m&{}&includePrePost=true{}",
+            host, symbol, quote_window(now_ms.div_euclid(1000), timestamp as i64), cache_buster(bypass_cache, timestamp)
+        );
+
+        let response = match client
+            .get(&url)
+            .header("User-Agent", ua)
+            .header("Accept", "application/json")
+            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("[Yahoo] quote {} failed: {}", host, e);
+                last_error = format!("{} request failed: {}", host, e);
+                continue;
+            }
+        };
+
+        if let Some(err) = rate_limit_error(&breakers, host, &response) {
+            log::warn!("[Yahoo] quote {}", err);
+            return Err(err);
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            log::warn!("[Yahoo] quote {} HTTP {}: {}", host, status, &body[..body.len().min(500)]);
+            if let Some(err) = yahoo_error_from_body(&body).and_then(|e| e.not_found()) {
+                return Err(err);
+            }
+            last_error = format!("HTTP {} from {}", status, host);
+            continue;
+        }
+
+        let data: YahooChartResponse = match read_json("Yahoo chart", response).await {
+            Ok(d) => d,
+            Err(e) => {
+                log::warn!("[Yahoo] quote {} parse error: {}", host, e);
+                last_error = format!("JSON parse error: {}", e);
+                continue;
+            }
+        };
+
+        if let Some(ref err) = data.chart.error {
+            log::warn!("[Yahoo] quote API error: {:?}", err);
+            if let Some(not_found) = err.not_found() {
+                return Err(not_found);
+            }
+            last_error = format!("Yahoo API error: {}", err);
+            continue;
+        }
+
+        if let Some(results) = data.chart.result {
+            if let Some(result) = results_for(&results, &symbol).next() {
+                return Ok(chart_to_quote(result, &symbol, now_ms.div_euclid(1000), &api.quote_refresh));
+            }
+        }
+
+        last_error = format!("No quote data in response from {}", host);
+    }
+
+    Err(last_error.into())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SymbolValidity {
+    symbol: String,
+    // "valid", "not_found", or "unavailable" when Yahoo couldn't be reached;
+    // only "not_found" means the ticker is dead
+    status: String,
+    name: Option<String>,
+    exchange: Option<String>,
+    error: Option<String>,
+}
+
+// Smallest possible chart request, just to see whether Yahoo knows the symbol
+async fn load_chart_meta(api: &ApiConfig, breakers: &SourceBreakers, symbol: &str) -> Result<YahooChartMeta, TradingError> {
+    yahoo_backoff(breakers)?;
+    let client = http_client(api, Duration::from_secs(api.yahoo_timeout_secs))?;
+
+    let mut last_error = String::from("No Yahoo Finance host responded");
+    for host in &api.yahoo_hosts {
+        let url = format!("https://{}/v8/finance/chart/{}?interval=1d&range=1d", host, symbol);
+        let response = match client.get(&url)
+            .header("User-Agent", &api.user_agent)
+            .header("Accept", "application/json")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                last_error = format!("{} request failed: {}", host, e);
+                continue;
+            }
+        };
+        if let Some(err) = rate_limit_error(breakers, host, &response) {
+            return Err(err);
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if let Some(err) = yahoo_error_from_body(&body).and_then(|e| e.not_found()) {
+            return Err(err);
+        }
+        if !status.is_success() {
+            last_error = format!("HTTP {} from {}", status, host);
+            continue;
+        }
+        let data: YahooChartResponse = match serde_json::from_str(&body) {
+            Ok(d) => d,
+            Err(e) => {
+                last_error = format!("JSON parse error: {}", e);
+                continue;
+            }
+        };
+        if let Some(meta) = data.chart.result.and_then(|results| results.into_iter().next()).map(|r| r.meta) {
+            return Ok(meta);
+        }
+        last_error = format!("No chart data in response from {}", host);
+    }
+    Err(last_error.into())
+}
+
+#[tauri::command]
+async fn validate_symbol(api_config: tauri::State<'_, ApiConfigState>, data_source: tauri::State<'_, DataSourceState>, symbol: String) -> Result<SymbolValidity, String> {
+    let symbol = required_symbol(&symbol)?;
+
+    let validity = match load_chart_meta(&api_config.get(), &data_source.breakers, &symbol).await {
+        Ok(meta) => SymbolValidity {
+            symbol: meta.symbol.clone().unwrap_or(symbol),
+            status: "valid".to_string(),
+            name: meta.long_name.or(meta.short_name),
+            exchange: meta.full_exchange_name.or(meta.exchange_name),
+            error: None,
+        },
+        Err(e) => SymbolValidity {
+            symbol,
+            status: if matches!(e, TradingError::NotFound { .. }) { "not_found" } else { "unavailable" }.to_string(),
+            name: None,
+            exchange: None,
+            error: Some(e.to_string()),
+        },
+    };
+    Ok(validity)
+}
+
+// Epoch milliseconds to an ISO-8601 UTC string, e.g. 2024-03-01T14:30:00.000Z
+fn iso8601_utc(millis: i64) -> String {
+    let secs = millis.div_euclid(1000);
+    let days = secs.div_euclid(86_400);
+    let secs_of_day = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        millis.rem_euclid(1000)
+    )
+}
+
+#[derive(Serialize)]
+struct ExportedCandle<'a> {
+    time: i64,
+    iso_time: String,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: i64,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    filled: &'a [String],
+}
+
+// Serializes candles for download: "csv" or "jsonl" (one JSON object per line).
+// The frontend owns the save dialog; this only returns the text.
+#[tauri::command]
+async fn export_candles(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol: String,
+    interval: String,
+    range: String,
+    format: String,
+) -> Result<String, String> {
+    let format = format.to_lowercase();
+    if format != "csv" && format != "jsonl" {
+        return Err(format!("Unsupported export format '{}', expected 'csv' or 'jsonl'", format));
+    }
+
+    let request = CandleRequest {
+        symbol: required_symbol(&symbol)?,
+        interval,
+        range,
+        adjusted: false,
+        fill_gaps: false,
+        include_extended: false,
+        bypass_cache: false,
+    };
+    validate_interval_range(&request.interval, &request.range).map_err(|e| e.to_string())?;
+    let key = request.key();
+    let fetch = data_source.get().stock_candles(api_config.get(), request);
+    let response = in_flight.candles.run(key, fetch).await.map_err(|e| e.to_string())?;
+
+    let mut out = String::new();
+    if format == "csv" {
+        out.push_str("time,iso_time,open,high,low,close,volume\n");
+        for c in &response.candles {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                c.time, iso8601_utc(c.time), c.open, c.high, c.low, c.close, c.volume
+            ));
+        }
+    } else {
+        for c in &response.candles {
+            let row = ExportedCandle {
+                time: c.time,
+                iso_time: iso8601_utc(c.time),
+                open: c.open,
+                high: c.high,
+                low: c.low,
+                close: c.close,
+                volume: c.volume,
+                filled: &c.filled,
+            };
+            out.push_str(&serde_json::to_string(&row).map_err(|e| e.to_string())?);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Serialize)]
+struct DailyCloses {
+    // Union of exchange-local trading dates (epoch ms of that date's UTC
+    // midnight) across all returned symbols
+    dates: Vec<i64>,
+    // Per symbol (date, close) pairs on `dates`, starting at the symbol's first bar;
+    // a day the symbol didn't trade carries the previous close forward
+    closes: HashMap<String, Vec<(i64, f64)>>,
+    // Symbols that failed to load or returned no bars
+    missing: Vec<String>,
+}
+
+// Split/dividend adjusted daily closes per symbol, keyed by exchange-local
+// date, plus the symbols that failed to load or returned no bars
+async fn load_daily_bars(
+    api: ApiConfig,
+    source: std::sync::Arc<dyn DataSource>,
+    in_flight: &InFlightRequests,
+    symbols: Vec<String>,
+    range: &str,
+) -> Result<(Vec<(String, std::collections::BTreeMap<i64, f64>)>, Vec<String>), String> {
+    validate_interval_range("1d", range).map_err(|e| e.to_string())?;
+
+    let lookups = symbols.into_iter().map(|symbol| {
+        let request = CandleRequest {
+            symbol: symbol.clone(),
+            interval: "1d".to_string(),
+            range: range.to_string(),
+            adjusted: true,
+            fill_gaps: false,
+            include_extended: false,
+            bypass_cache: false,
+        };
+        let key = request.key();
+        let fetch = source.stock_candles(api.clone(), request);
+        async move { (symbol, in_flight.candles.run(key, fetch).await) }
+    });
+
+    let mut by_symbol: Vec<(String, std::collections::BTreeMap<i64, f64>)> = Vec::new();
+    let mut missing = Vec::new();
+    for (symbol, result) in futures::future::join_all(lookups).await {
+        match result {
+            Ok(response) if !response.candles.is_empty() => {
+                by_symbol.push((symbol, closes_by_date(&response.candles, response.gmt_offset_secs)));
+            }
+            Ok(_) => missing.push(symbol),
+            Err(e) => {
+                log::warn!("[Yahoo] daily closes for {} failed: {}", symbol, e);
+                missing.push(symbol);
+            }
+        }
+    }
+    Ok((by_symbol, missing))
+}
+
+// Bars are stamped at each exchange's open, which is the previous UTC day for
+// venues east of UTC (Tokyo, Sydney); the local date lines markets up
+fn closes_by_date(candles: &[StockCandle], gmt_offset_secs: i64) -> std::collections::BTreeMap<i64, f64> {
+    candles.iter()
+        .map(|c| (local_day(c.time, gmt_offset_secs) * 86_400_000, c.close))
+        .collect()
+}
+
+// Split/dividend adjusted daily closes for many symbols, aligned for equity curves
+#[tauri::command]
+async fn fetch_daily_closes(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbols: Vec<String>,
+    range: String,
+) -> Result<DailyCloses, String> {
+    let symbols = symbols.iter().map(|s| required_symbol(s)).collect::<Result<Vec<_>, _>>()?;
+    let (by_symbol, missing) = load_daily_bars(api_config.get(), data_source.get(), in_flight.inner(), symbols, &range).await?;
+
+    let mut dates: Vec<i64> = by_symbol.iter().flat_map(|(_, days)| days.keys().copied()).collect();
+    dates.sort_unstable();
+    dates.dedup();
+
+    let closes = by_symbol.into_iter()
+        .map(|(symbol, days)| {
+            let mut last = None;
+            let series = dates.iter()
+                .filter_map(|date| {
+                    last = days.get(date).copied().or(last);
+                    last.map(|close| (*date, close))
+                })
+                .collect();
+            (symbol, series)
+        })
+        .collect();
+
+    Ok(DailyCloses { dates, closes, missing })
+}
+
+// Fewer overlapping daily returns than this make a correlation mostly noise
+const MIN_CORRELATION_RETURNS: usize = 20;
+
+// Pearson correlation of two symbols' daily returns over `range`. Only days
+// both traded count, so a holiday on one exchange isn't read as a flat day.
+#[tauri::command]
+async fn compute_correlation(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol_a: String,
+    symbol_b: String,
+    range: String,
+) -> Result<f64, String> {
+    let symbols = vec![required_symbol(&symbol_a)?, required_symbol(&symbol_b)?];
+    if symbols[0] == symbols[1] {
+        return Err("Correlation needs two different symbols".to_string());
+    }
+    let (by_symbol, missing) = load_daily_bars(api_config.get(), data_source.get(), in_flight.inner(), symbols, &range).await?;
+    if !missing.is_empty() {
+        return Err(format!("No daily bars for {}", missing.join(", ")));
+    }
+    // Both loaded, in request order
+    let (a, b) = (&by_symbol[0].1, &by_symbol[1].1);
+
+    let common: Vec<(f64, f64)> = a.iter()
+        .filter_map(|(date, close_a)| b.get(date).map(|close_b| (*close_a, *close_b)))
+        .collect();
+    let (returns_a, returns_b): (Vec<f64>, Vec<f64>) = common.windows(2)
+        .map(|w| (w[1].0 / w[0].0 - 1.0, w[1].1 / w[0].1 - 1.0))
+        .unzip();
+    if returns_a.len() < MIN_CORRELATION_RETURNS {
+        return Err(format!(
+            "Need at least {} overlapping daily returns, got {}",
+            MIN_CORRELATION_RETURNS,
+            returns_a.len()
+        ));
+    }
+    performance::correlation(&returns_a, &returns_b)
+}
+
+// Volume-weighted average of each bar's typical price (high + low + close) / 3;
+// None until some volume has traded
+fn vwap(candles: &[StockCandle]) -> Option<f64> {
+    let (weighted, volume) = candles.iter()
+        .filter(|c| c.volume > 0)
+        .fold((0.0, 0.0), |(weighted, volume), c| {
+            let typical = (c.high + c.low + c.close) / 3.0;
+            (weighted + typical * c.volume as f64, volume + c.volume as f64)
+        });
+    (volume > 0.0).then(|| weighted / volume)
+}
+
+// Intraday VWAP over today's regular-session 1m bars
+#[tauri::command]
+async fn fetch_vwap(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol: String,
+) -> Result<f64, TradingError> {
+    let symbol = required_stock_symbol(&symbol)?;
+    let request = CandleRequest {
+        symbol: symbol.clone(),
+        interval: "1m".to_string(),
+        range: "1d".to_string(),
+        adjusted: false,
+        fill_gaps: false,
+        include_extended: false,
+        bypass_cache: false,
+    };
+    let key = request.key();
+    let fetch = data_source.get().stock_candles(api_config.get(), request);
+    let response = in_flight.candles.run(key, fetch).await?;
+    vwap(&response.candles).ok_or_else(|| format!("No volume traded yet for {}", symbol).into())
+}
+
+// Fewer daily bars than this over a 1y range can't stand in for a 52-week range
+const MIN_52W_BARS: usize = 200;
+
+// 52-week (low, high) from a year of daily bars, for when quoteSummary is blocked
+#[tauri::command]
+async fn fetch_52w_range(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol: String,
+) -> Result<(f64, f64), TradingError> {
+    let symbol = required_stock_symbol(&symbol)?;
+    let request = CandleRequest {
+        symbol: symbol.clone(),
+        interval: "1d".to_string(),
+        range: "1y".to_string(),
+        adjusted: false,
+        fill_gaps: false,
+        include_extended: false,
+        bypass_cache: false,
+    };
+    let key = request.key();
+    let fetch = data_source.get().stock_candles(api_config.get(), request);
+    let response = in_flight.candles.run(key, fetch).await?;
+
+    let bars: Vec<&StockCandle> = response.candles.iter()
+        .filter(|c| c.high.is_finite() && c.low.is_finite() && c.low > 0.0)
+        .collect();
+    if bars.len() < MIN_52W_BARS {
+        return Err(format!(
+            "Only {} daily bars for {} over 1y; need at least {} for a 52-week range",
+            bars.len(), symbol, MIN_52W_BARS
+        ).into());
+    }
+    let high = bars.iter().map(|c| c.high).fold(f64::MIN, f64::max);
+    let low = bars.iter().map(|c| c.low).fold(f64::MAX, f64::min);
+    Ok((low, high))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RatioCandle {
+    time: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+}
+
+// A/B per bar on timestamps both series share. The intrabar extremes aren't
+// known, so high/low are the widest the ratio could have reached
+fn ratio_candles(a: &[StockCandle], b: &[StockCandle]) -> Vec<RatioCandle> {
+    let by_time: HashMap<i64, &StockCandle> = b.iter().map(|c| (c.time, c)).collect();
+    a.iter()
+        .filter_map(|a| {
+            let b = by_time.get(&a.time)?;
+            if b.open <= 0.0 || b.high <= 0.0 || b.low <= 0.0 || b.close <= 0.0 {
+                return None;
+            }
+            Some(RatioCandle {
+                time: a.time,
+                open: a.open / b.open,
+                high: a.high / b.low,
+                low: a.low / b.high,
+                close: a.close / b.close,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn fetch_ratio_candles(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    symbol_a: String,
+    symbol_b: String,
+    interval: String,
+    range: String,
+) -> Result<Vec<RatioCandle>, TradingError> {
+    validate_interval_range(&interval, &range)?;
+    let (symbol_a, symbol_b) = (required_stock_symbol(&symbol_a)?, required_stock_symbol(&symbol_b)?);
+
+    let api = api_config.get();
+    let source = data_source.get();
+    let load = |symbol: String| {
+        let request = CandleRequest {
+            symbol,
+            interval: interval.clone(),
+            range: range.clone(),
+            adjusted: true,
+            fill_gaps: false,
+            include_extended: false,
+            bypass_cache: false,
+        };
+        let key = request.key();
+        in_flight.candles.run(key, source.stock_candles(api.clone(), request))
+    };
+    let (a, b) = futures::future::join(load(symbol_a), load(symbol_b)).await;
+    Ok(ratio_candles(&a?.candles, &b?.candles))
+}
+
+// Index proxies for the dashboard header
+const MARKET_OVERVIEW_SYMBOLS: &[&str] = &["SPY", "QQQ", "DIA", "IWM", "^VIX"];
+
+#[derive(Debug, Serialize)]
+struct MarketOverview {
+    // In request order, skipping symbols that failed
+    quotes: Vec<StockQuote>,
+    missing: Vec<String>,
+}
+
+#[tauri::command]
+async fn fetch_market_overview(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    clock: tauri::State<'_, ClockState>,
+    symbols: Option<Vec<String>>,
+) -> Result<MarketOverview, String> {
+    let symbols = symbols
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| MARKET_OVERVIEW_SYMBOLS.iter().map(|s| s.to_string()).collect());
+
+    let api = api_config.get();
+    let source = data_source.get();
+    let in_flight = in_flight.inner();
+    let now_ms = clock.now_millis();
+    let lookups = symbols.into_iter().map(|symbol| {
+        let fetch = source.stock_quote(api.clone(), symbol.clone(), false, now_ms);
+        let lookup = in_flight.quotes.run(quote_key(&symbol, false, now_ms), fetch);
+        async move { (symbol, lookup.await) }
+    });
+
+    let mut overview = MarketOverview { quotes: Vec::new(), missing: Vec::new() };
+    for (symbol, result) in futures::future::join_all(lookups).await {
+        match result {
+            Ok(quote) => overview.quotes.push(quote),
+            Err(e) => {
+                log::warn!("[Yahoo] overview quote for {} failed: {}", symbol, e);
+                overview.missing.push(symbol);
+            }
+        }
+    }
+    Ok(overview)
+}
+
+// Yahoo quoteSummary response structures
+#[derive(Debug, Deserialize)]
+struct YahooSummaryResponse {
+    #[serde(rename = "quoteSummary")]
+    quote_summary: YahooSummaryResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct YahooSummaryResult {
+    result: Option<Vec<YahooSummaryData>>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YahooSummaryData {
+    #[serde(rename = "summaryDetail")]
+    summary_detail: Option<YahooSummaryDetail>,
+    #[serde(rename = "defaultKeyStatistics")]
+    default_key_statistics: Option<YahooKeyStatistics>,
+}
+
+// Yahoo wraps numbers as { raw, fmt }, or {} when the value is missing
+#[derive(Debug, Deserialize)]
+struct YahooRawValue {
+    raw: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YahooSummaryDetail {
+    #[serde(rename = "marketCap")]
+    market_cap: Option<YahooRawValue>,
+    #[serde(rename = "trailingPE")]
+    trailing_pe: Option<YahooRawValue>,
+    #[serde(rename = "fiftyTwoWeekHigh")]
+    fifty_two_week_high: Option<YahooRawValue>,
+    #[serde(rename = "fiftyTwoWeekLow")]
+    fifty_two_week_low: Option<YahooRawValue>,
+    #[serde(rename = "dividendYield")]
+    dividend_yield: Option<YahooRawValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YahooKeyStatistics {
+    #[serde(rename = "forwardPE")]
+    forward_pe: Option<YahooRawValue>,
+}
+
+#[derive(Debug, Serialize)]
+struct StockSummary {
+    symbol: String,
+    market_cap: Option<f64>,
+    pe_ratio: Option<f64>,
+    forward_pe: Option<f64>,
+    fifty_two_week_high: Option<f64>,
+    fifty_two_week_low: Option<f64>,
+    dividend_yield: Option<f64>,
+}
+
+fn raw_value(value: &Option<YahooRawValue>) -> Option<f64> {
+    value.as_ref().and_then(|v| v.raw)
+}
+
+#[tauri::command]
+async fn fetch_stock_summary(api_config: tauri::State<'_, ApiConfigState>, data_source: tauri::State<'_, DataSourceState>, symbol: String) -> Result<StockSummary, String> {
+    let symbol = required_symbol(&symbol)?;
+    yahoo_backoff(&data_source.breakers).map_err(|e| e.to_string())?;
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(api.yahoo_timeout_secs))?;
+
+    let ua = api.user_agent.as_str();
+    let mut last_error = String::from("No Yahoo Finance host responded");
+
+    for host in &api.yahoo_hosts {
+        let url = format!(
+            "https://{}/v10/finance/quoteSummary/{}?modules=summaryDetail,defaultKeyStatistics",
+            host, symbol
+        );
+
+        let response = match client
+            .get(&url)
+            .header("User-Agent", ua)
+            .header("Accept", "application/json")
+            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("[Yahoo] summary {} failed: {}", host, e);
+                last_error = format!("{} request failed: {}", host, e);
+                continue;
+            }
+        };
+        if let Some(err) = rate_limit_error(&data_source.breakers, host, &response) {
+            return Err(err.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            log::warn!("[Yahoo] summary {} HTTP {}", host, status);
+            last_error = format!("HTTP {} from {}", status, host);
+            continue;
+        }
+
+        let data: YahooSummaryResponse = match response.json().await {
+            Ok(d) => d,
+            Err(e) => {
+                log::warn!("[Yahoo] summary {} parse error: {}", host, e);
+                last_error = format!("JSON parse error: {}", e);
+                continue;
+            }
+        };
+
+        if let Some(ref err) = data.quote_summary.error {
+            last_error = format!("Yahoo API error: {}", err);
+            continue;
+        }
+
+        if let Some(result) = data.quote_summary.result.and_then(|r| r.into_iter().next()) {
+            // Either module may be missing for funds, ADRs, etc.
+            let detail = result.summary_detail.as_ref();
+            let stats = result.default_key_statistics.as_ref();
+            return Ok(StockSummary {
+                symbol,
+                market_cap: detail.and_then(|d| raw_value(&d.market_cap)),
+                pe_ratio: detail.and_then(|d| raw_value(&d.trailing_pe)),
+                forward_pe: stats.and_then(|s| raw_value(&s.forward_pe)),
+                fifty_two_week_high: detail.and_then(|d| raw_value(&d.fifty_two_week_high)),
+                fifty_two_week_low: detail.and_then(|d| raw_value(&d.fifty_two_week_low)),
+                dividend_yield: detail.and_then(|d| raw_value(&d.dividend_yield)),
+            });
+        }
+
+        last_error = format!("No summary data in response from {}", host);
+    }
+
+    Err(last_error)
+}
+
+#[derive(Debug, Serialize)]
+struct Split {
+    date: i64, // epoch ms
+    numerator: f64,
+    denominator: f64,
+    ratio: String, // e.g. "4:1"
+}
+
+#[derive(Debug, Serialize)]
+struct Dividend {
+    date: i64, // epoch ms (ex-dividend date)
+    amount: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct CorporateActions {
+    symbol: String,
+    splits: Vec<Split>,
+    dividends: Vec<Dividend>,
+}
+
+#[tauri::command]
+async fn fetch_corporate_actions(api_config: tauri::State<'_, ApiConfigState>, data_source: tauri::State<'_, DataSourceState>, symbol: String, range: String) -> Result<CorporateActions, String> {
+    let symbol = required_symbol(&symbol)?;
+    validate_interval_range("1d", &range).map_err(|e| e.to_string())?;
+
+    yahoo_backoff(&data_source.breakers).map_err(|e| e.to_string())?;
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(api.yahoo_timeout_secs))?;
+
+    let ua = api.user_agent.as_str();
+    let mut last_error = String::from("No Yahoo Finance host responded");
+
+    for host in &api.yahoo_hosts {
+        let url = format!(
+            "https://{}/v8/finance/chart/{}?interval=1d&range={}&events=div,split",
+            host, symbol, range
+        );
+
+        let response = match client
+            .get(&url)
+            .header("User-Agent", ua)
+            .header("Accept", "application/json")
+            .header("Accept-Encoding", "gzip, deflate, br")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("[Yahoo] events {} failed: {}", host, e);
+                last_error = format!("{} request failed: {}", host, e);
+                continue;
+            }
+        };
+        if let Some(err) = rate_limit_error(&data_source.breakers, host, &response) {
+            return Err(err.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            log::warn!("[Yahoo] events {} HTTP {}", host, status);
+            last_error = format!("HTTP {} from {}", status, host);
+            continue;
+        }
+
+        let data: YahooChartResponse = match response.json().await {
+            Ok(d) => d,
+            Err(e) => {
+                log::warn!("[Yahoo] events {} parse error: {}", host, e);
+                last_error = format!("JSON parse error: {}", e);
+                continue;
+            }
+        };
+
+        if let Some(ref err) = data.chart.error {
+            last_error = format!("Yahoo API error: {}", err);
+            continue;
+        }
+
+        if let Some(result) = data.chart.result.and_then(|r| r.into_iter().next()) {
+            // No events in the range is normal, not an error
+            let events = result.events.unwrap_or_default();
+            let mut splits: Vec<Split> = events.splits.into_values()
+                .map(|s| Split {
+                    date: s.date * 1000,
+                    numerator: s.numerator,
+                    denominator: s.denominator,
+                    ratio: if s.split_ratio.is_empty() { format!("{}:{}", s.numerator, s.denominator) } else { s.split_ratio },
+                })
+                .collect();
+            let mut dividends: Vec<Dividend> = events.dividends.into_values()
+                .map(|d| Dividend { date: d.date * 1000, amount: d.amount })
+                .collect();
+            splits.sort_by_key(|s| s.date);
+            dividends.sort_by_key(|d| d.date);
+            return Ok(CorporateActions { symbol, splits, dividends });
+        }
+
+        last_error = format!("No chart data in response from {}", host);
+    }
+
+    Err(last_error)
+}
+
+// DexScreener response structures
+#[derive(Debug, Deserialize, Serialize)]
+struct DexScreenerResponse {
+    pairs: Option<Vec<DexPair>>,
+    pair: Option<DexPair>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexPair {
+    #[serde(rename = "chainId")]
+    chain_id: Option<String>,
+    #[serde(rename = "dexId")]
+    dex_id: Option<String>,
+    #[serde(rename = "pairAddress")]
+    pair_address: Option<String>,
+    #[serde(rename = "baseToken")]
+    base_token: Option<DexToken>,
+    #[serde(rename = "quoteToken")]
+    quote_token: Option<DexToken>,
+    #[serde(rename = "priceUsd")]
+    price_usd: Option<String>,
+    // Price in the quote token; the only price on some thinly listed pairs
+    #[serde(rename = "priceNative")]
+    price_native: Option<String>,
+    volume: Option<DexVolume>,
+    #[serde(rename = "priceChange")]
+    price_change: Option<DexPriceChange>,
+    liquidity: Option<DexLiquidity>,
+    txns: Option<DexTxns>,
+    info: Option<DexPairProfile>,
+}
+
+impl DexScreenerResponse {
+    // Endpoints answer with either a `pairs` list or a single `pair`; treat both alike
+    fn all_pairs(&self) -> impl Iterator<Item = &DexPair> {
+        self.pairs.iter().flatten().chain(self.pair.iter())
+    }
+}
+
+// Most liquid pair on `chain_id`, or the first pair when none is on that chain
+fn best_pair<'a>(response: &'a DexScreenerResponse, chain_id: &str) -> Option<&'a DexPair> {
+    response.all_pairs()
+        .filter(|p| p.is_on_chain(chain_id))
+        .max_by(|a, b| DexPair::cmp_liquidity(a, b))
+        .or_else(|| response.all_pairs().next())
+}
+
+// Most liquid pair on `chain_id` traded on `dex_id`; the error lists the DEXes
+// that do have a pair so the caller can pick one
+fn best_pair_on_dex<'a>(response: &'a DexScreenerResponse, chain_id: &str, dex_id: &str) -> Result<&'a DexPair, String> {
+    let on_chain = || response.all_pairs().filter(|p| p.is_on_chain(chain_id));
+    if let Some(best) = on_chain()
+        .filter(|p| p.dex_id.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(dex_id)))
+        .max_by(|a, b| DexPair::cmp_liquidity(a, b))
+    {
+        return Ok(best);
+    }
+    let mut available: Vec<&str> = on_chain().filter_map(|p| p.dex_id.as_deref()).collect();
+    available.sort_unstable();
+    available.dedup();
+    if available.is_empty() {
+        return Err(format!("DexScreener: no pairs on {}", chain_id));
+    }
+    Err(format!(
+        "DexScreener: no {} pair on {}; available DEXes: {}",
+        dex_id, chain_id, available.join(", ")
+    ))
+}
+
+impl DexPair {
+    fn liquidity_usd(&self) -> Option<f64> {
+        self.liquidity.as_ref().and_then(|l| l.usd)
+    }
+
+    fn is_on_chain(&self, chain_id: &str) -> bool {
+        self.chain_id.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(chain_id))
+    }
+
+    fn cmp_liquidity(a: &DexPair, b: &DexPair) -> std::cmp::Ordering {
+        let la = a.liquidity_usd().unwrap_or(0.0);
+        let lb = b.liquidity_usd().unwrap_or(0.0);
+        la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
+    }
+
+    fn quote_symbol(&self) -> Option<String> {
+        self.quote_token.as_ref().and_then(|t| t.symbol.clone())
+    }
+
+    fn is_base_token(&self, address: &str) -> bool {
+        self.base_token.as_ref()
+            .and_then(|t| t.address.as_deref())
+            .is_some_and(|a| a.eq_ignore_ascii_case(address))
+    }
+}
+
+// USD price of a pair. DexScreener omits priceUsd on some pairs quoted in a
+// non-USD token; those are converted from priceNative using the quote token's
+// own USD price, which costs a second lookup.
+async fn pair_price_usd(client: &reqwest::Client, breakers: &SourceBreakers, base_url: &str, user_agent: &str, pair: &DexPair) -> Result<f64, String> {
+    if let Some(raw) = pair.price_usd.as_deref() {
+        return parse_price("DexScreener", raw);
+    }
+    let quote = pair.quote_symbol().unwrap_or_else(|| "an unknown token".to_string());
+    let native = pair.price_native.as_deref()
+        .ok_or_else(|| format!("DexScreener: pair quoted in {} has neither a USD nor a native price", quote))?;
+    let native = parse_price("DexScreener", native)?;
+    let quote_address = pair.quote_token.as_ref().and_then(|t| t.address.clone())
+        .ok_or_else(|| format!("DexScreener: pair is only priced in {}, whose address is unknown", quote))?;
+
+    breakers.check_retry_after("dexscreener")?;
+    let url = format!("{}/latest/dex/tokens/{}", base_url, quote_address);
+    let response = client.get(&url)
+        .header("User-Agent", user_agent)
+        .send().await.map_err(|e| format!("DexScreener request: {}", e))?;
+    breakers.note_retry_after("dexscreener", &response);
+    let data: DexScreenerResponse = read_json("DexScreener", response).await
+        .map_err(|e| format!("DexScreener parse: {}", e))?;
+    // Only pairs with the quote token as their base carry its USD price
+    let chain_id = pair.chain_id.as_deref().unwrap_or_default();
+    let quote_usd = data.all_pairs()
+        .filter(|p| p.is_on_chain(chain_id) && p.is_base_token(&quote_address) && p.price_usd.is_some())
+        .max_by(|a, b| DexPair::cmp_liquidity(a, b))
+        .and_then(|p| p.price_usd.as_deref())
+        .ok_or_else(|| format!("DexScreener: pair is only priced in {}, which has no USD price", quote))?;
+    let price = native * parse_price("DexScreener", quote_usd)?;
+    log::info!("[price] DexScreener: converted {} {} to ${}", native, quote, price);
+    checked_price("DexScreener", price)
+}
+
+// Unknown liquidity isn't flagged; only a reported figure under the threshold is
+fn is_low_liquidity(liquidity_usd: Option<f64>, threshold: f64) -> bool {
+    liquidity_usd.is_some_and(|usd| usd < threshold)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexPairProfile {
+    #[serde(rename = "imageUrl")]
+    image_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexToken {
+    address: Option<String>,
+    name: Option<String>,
+    symbol: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexVolume {
+    m5: Option<f64>,
+    h1: Option<f64>,
+    h6: Option<f64>,
+    h24: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexTxns {
+    m5: Option<DexTxnCount>,
+    h1: Option<DexTxnCount>,
+    h6: Option<DexTxnCount>,
+    h24: Option<DexTxnCount>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+struct DexTxnCount {
+    #[serde(default)]
+    buys: u64,
+    #[serde(default)]
+    sells: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexPriceChange {
+    h24: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct DexLiquidity {
+    usd: Option<f64>,
+}
+
+// Jupiter Lite API v3 response — top-level is HashMap<mint, data>, no "data" wrapper
+#[derive(Debug, Deserialize, Serialize)]
+struct JupiterV3PriceData {
+    #[serde(rename = "usdPrice")]
+    usd_price: Option<f64>,
+    #[serde(rename = "priceChange24h")]
+    price_change_24h: Option<f64>,
+}
+
+// Upstream prices must be finite and positive. Rust's f64 parser accepts
+// "inf" and "NaN", which would otherwise poison downstream math (and
+// serialize as null), so a bad value fails the source and the chain moves on.
+fn checked_price(source: &str, price: f64) -> Result<f64, String> {
+    if price.is_finite() && price > 0.0 {
+        Ok(price)
+    } else {
+        Err(format!("{}: invalid price {}", source, price))
+    }
+}
+
+fn parse_price(source: &str, raw: &str) -> Result<f64, String> {
+    let price: f64 = raw.trim().parse().map_err(|_| format!("{}: invalid price '{}'", source, raw))?;
+    checked_price(source, price)
+}
+
+// Jupiter caps the number of ids per price request
+const JUPITER_BATCH_SIZE: usize = 50;
+
+// One Jupiter v3 request for up to JUPITER_BATCH_SIZE mints
+async fn fetch_jupiter_prices(client: &reqwest::Client, api: &ApiConfig, breakers: &SourceBreakers, addresses: &[String]) -> Result<HashMap<String, JupiterV3PriceData>, String> {
+    breakers.check_retry_after("jupiter")?;
+    let url = format!("{}/price/v3?ids={}", api.jupiter_base_url, addresses.join(","));
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Jupiter request: {}", e))?;
+    breakers.note_retry_after("jupiter", &response);
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Jupiter status {}", status));
+    }
+    let entries: HashMap<String, serde_json::Value> = read_json("Jupiter", response).await
+        .map_err(|e| format!("Jupiter parse: {}", e))?;
+    Ok(parse_jupiter_prices(entries))
+}
+
+// Unindexed mints are omitted or null; parse per mint so one malformed entry
+// drops only that mint instead of the whole batch
+fn parse_jupiter_prices(entries: HashMap<String, serde_json::Value>) -> HashMap<String, JupiterV3PriceData> {
+    let mut prices = HashMap::new();
+    for (mint, value) in entries {
+        match serde_json::from_value::<Option<JupiterV3PriceData>>(value) {
+            Ok(Some(data)) => {
+                prices.insert(mint, data);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("[price] Ignoring Jupiter entry for {}: {}", mint, e),
+        }
+    }
+    prices
+}
+
+// A request's share of the batched Jupiter lookup: Ok((price, change_24h))
+// when the batch priced it, otherwise the sources already settled for its
+// per-token fallback. A mint that a successful batch (`asked`) came back
+// without is unindexed, so Jupiter isn't asked about it again.
+fn jupiter_batch_outcome(
+    jupiter: &HashMap<String, JupiterV3PriceData>,
+    asked: &std::collections::HashSet<String>,
+    request: &DexPriceRequest,
+) -> Result<(f64, Option<f64>), Vec<SourceAttempt>> {
+    let batchable = request.chain_id.eq_ignore_ascii_case("solana") && request.dex_id.is_none();
+    let priced = jupiter.get(&request.address)
+        .filter(|_| batchable)
+        .and_then(|token| token.usd_price.and_then(|p| checked_price("Jupiter", p).ok()).map(|price| (price, token.price_change_24h)));
+    match priced {
+        Some(priced) => Ok(priced),
+        None if batchable && asked.contains(&request.address) => {
+            Err(vec![SourceAttempt::new("jupiter", "no usable price in batch response".to_string())])
+        }
+        None => Err(Vec::new()),
+    }
+}
+
+// GeckoTerminal simple token price response
+#[derive(Debug, Deserialize)]
+struct GeckoTokenPriceResponse {
+    data: Option<GeckoTokenPriceData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeckoTokenPriceData {
+    attributes: Option<GeckoTokenPriceAttributes>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeckoTokenPriceAttributes {
+    token_prices: Option<std::collections::HashMap<String, Option<String>>>,
+}
+
+// Raydium price API response
+#[derive(Debug, Deserialize)]
+struct RaydiumPriceResponse {
+    data: Option<std::collections::HashMap<String, String>>,
+}
+
+// CoinGecko simple price response — top-level is HashMap<id, data>
+#[derive(Debug, Deserialize)]
+struct CoinGeckoPriceData {
+    usd: Option<f64>,
+    usd_24h_change: Option<f64>,
+    usd_24h_vol: Option<f64>,
+    // Epoch seconds, present with include_last_updated_at=true
+    last_updated_at: Option<i64>,
+}
+
+// Chains the DEX price commands understand. chain_to_gecko_network and
+// supported_chains both read from this table so they can't drift apart.
+struct ChainSpec {
+    chain_id: &'static str,
+    display_name: &'static str,
+    gecko_network: &'static str,
+    // Numeric EVM chain id used by token lists; None for non-EVM chains
+    evm_chain_id: Option<u64>,
+}
+
+const SUPPORTED_CHAINS: &[ChainSpec] = &[
+    ChainSpec { chain_id: "solana", display_name: "Solana", gecko_network: "solana", evm_chain_id: None },
+    ChainSpec { chain_id: "ethereum", display_name: "Ethereum", gecko_network: "eth", evm_chain_id: Some(1) },
+    ChainSpec { chain_id: "bsc", display_name: "BNB Chain", gecko_network: "bsc", evm_chain_id: Some(56) },
+    ChainSpec { chain_id: "base", display_name: "Base", gecko_network: "base", evm_chain_id: Some(8453) },
+    ChainSpec { chain_id: "arbitrum", display_name: "Arbitrum", gecko_network: "arbitrum", evm_chain_id: Some(42161) },
+    ChainSpec { chain_id: "polygon", display_name: "Polygon", gecko_network: "polygon_pos", evm_chain_id: Some(137) },
+    ChainSpec { chain_id: "avalanche", display_name: "Avalanche", gecko_network: "avax", evm_chain_id: Some(43114) },
+    ChainSpec { chain_id: "optimism", display_name: "Optimism", gecko_network: "optimism", evm_chain_id: Some(10) },
+];
+
+fn chain_to_gecko_network(chain_id: &str) -> Option<&'static str> {
+    let chain_id = chain_id.to_lowercase();
+    SUPPORTED_CHAINS
+        .iter()
+        .find(|chain| chain.chain_id == chain_id)
+        .map(|chain| chain.gecko_network)
+}
+
+#[derive(Debug, Serialize)]
+struct ChainInfo {
+    chain_id: String,
+    display_name: String,
+    gecko_network: String,
+    dexscreener_supported: bool,
+    // Jupiter/Raydium give near real-time prices; other chains rely on slower aggregators
+    solana_realtime: bool,
+}
+
+#[tauri::command]
+fn supported_chains() -> Vec<ChainInfo> {
+    SUPPORTED_CHAINS
+        .iter()
+        .map(|chain| ChainInfo {
+            chain_id: chain.chain_id.to_string(),
+            display_name: chain.display_name.to_string(),
+            gecko_network: chain.gecko_network.to_string(),
+            dexscreener_supported: true,
+            solana_realtime: chain.chain_id == "solana",
+        })
+        .collect()
+}
+
+// Every source name fetch_dex_price understands, in the default fallback order
+const DEX_PRICE_SOURCES: &[&str] = &["jupiter", "raydium", "gecko", "dexscreener", "coingecko"];
+
+// One failed source inside fetch_dex_price
+#[derive(Debug, Clone, Serialize)]
+struct SourceAttempt {
+    source: String,
+    error: String,
+}
+
+impl SourceAttempt {
+    fn new(source: &str, error: String) -> Self {
+        SourceAttempt {
+            source: source.to_string(),
+            error,
+        }
+    }
+}
+
+// Returned when every source failed, listing each attempt so a wrong
+// chain_id or unindexed token is easy to spot
+#[derive(Debug, Clone, Serialize)]
+struct DexPriceError {
+    message: String,
+    attempts: Vec<SourceAttempt>,
+}
+
+impl DexPriceError {
+    fn from_attempts(attempts: Vec<SourceAttempt>) -> Self {
+        let summary: Vec<String> = attempts.iter()
+            .map(|a| format!("{}: {}", a.source, a.error))
+            .collect();
+        DexPriceError {
+            message: format!("All price sources failed ({})", summary.join("; ")),
+            attempts,
+        }
+    }
+}
+
+impl std::fmt::Display for DexPriceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<String> for DexPriceError {
+    fn from(message: String) -> Self {
+        DexPriceError {
+            message,
+            attempts: Vec::new(),
+        }
+    }
+}
+
+// Per-source circuit breaker for fetch_dex_price: after repeated outage-like
+// failures a source is skipped for a cooldown, then a single request probes it
+const BREAKER_FAILURE_THRESHOLD: u32 = 3;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+// Upstream health shared through DataSourceState: the DEX circuit breakers
+// and the Retry-After back-offs of every host, Yahoo included
+struct SourceBreakers {
+    breakers: Mutex<HashMap<String, Breaker>>,
+    // Latest lookups across all sources, newest last, for export_diagnostics
+    recent: Mutex<std::collections::VecDeque<SourceOutcome>>,
+    // Per-source "don't call before" times (epoch ms) taken from 429 responses;
+    // keyed by source name ("yahoo", "jupiter", "dexscreener", ...)
+    retry_after: Mutex<HashMap<String, i64>>,
+}
+
+const RECENT_OUTCOMES: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+struct SourceOutcome {
+    source: String,
+    ok: bool,
+    error: Option<String>,
+    at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BreakerStatus {
+    source: String,
+    state: String, // "closed", "open", "half_open"
+    consecutive_failures: u32,
+    // Seconds until the next probe is allowed while open
+    retry_in_secs: Option<u64>,
+}
+
+impl SourceBreakers {
+    fn new() -> Self {
+        SourceBreakers {
+            breakers: Mutex::new(HashMap::new()),
+            recent: Mutex::new(std::collections::VecDeque::new()),
+            retry_after: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Remembers a 429's Retry-After so `source` is skipped until it passes
+    fn note_retry_after(&self, source: &str, response: &reqwest::Response) {
+        if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return;
+        }
+        let now = now_millis();
+        let until = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, now));
+        if let Some(until) = until.filter(|until| *until > now) {
+            log::warn!("[rate-limit] {} asked for a {}s back-off", source, (until - now + 999) / 1000);
+            self.retry_after.lock_or_recover().insert(source.to_string(), until);
+        }
+    }
+
+    // Seconds until `source` may be called again, while it is backing off
+    fn retry_after_secs(&self, source: &str) -> Option<u64> {
+        let now = now_millis();
+        let mut until = self.retry_after.lock_or_recover();
+        match until.get(source) {
+            Some(&t) if t > now => Some(((t - now + 999) / 1000) as u64),
+            Some(_) => {
+                until.remove(source);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // For request paths outside the price fallback chain: fail fast instead
+    // of calling a source that is still backing off
+    fn check_retry_after(&self, source: &str) -> Result<(), String> {
+        match self.retry_after_secs(source) {
+            Some(wait) => Err(format!("{} skipped, rate limited for another {}s", source, wait)),
+            None => Ok(()),
+        }
+    }
+
+    // Once the cooldown has passed, the first caller probes and restarts the
+    // cooldown, so concurrent lookups keep skipping until the probe settles
+    fn allow(&self, source: &str) -> bool {
+        let mut breakers = self.breakers.lock_or_recover();
+        let Some(breaker) = breakers.get_mut(source) else { return true };
+        match breaker.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() >= BREAKER_COOLDOWN => {
+                breaker.opened_at = Some(Instant::now());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    // `error` is the lookup's failure, if any; only outages count against the source
+    fn record(&self, source: &str, error: Option<&str>) {
+        {
+            let mut recent = self.recent.lock_or_recover();
+            if recent.len() == RECENT_OUTCOMES {
+                recent.pop_front();
+            }
+            recent.push_back(SourceOutcome {
+                source: source.to_string(),
+                ok: error.is_none(),
+                error: error.map(str::to_string),
+                at: now_millis(),
+            });
+        }
+
+        let outage = error.is_some_and(is_source_outage);
+        let mut breakers = self.breakers.lock_or_recover();
+        let breaker = breakers.entry(source.to_string()).or_default();
+        if !outage {
+            *breaker = Breaker::default();
+            return;
+        }
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= BREAKER_FAILURE_THRESHOLD {
+            if breaker.opened_at.is_none() {
+                log::warn!("[price] {} failed {} times in a row, skipping it for {}s", source, breaker.consecutive_failures, BREAKER_COOLDOWN.as_secs());
+            }
+            breaker.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn status(&self) -> Vec<BreakerStatus> {
+        let breakers = self.breakers.lock_or_recover();
+        DEX_PRICE_SOURCES.iter()
+            .map(|source| {
+                let breaker = breakers.get(*source);
+                let remaining = breaker
+                    .and_then(|b| b.opened_at)
+                    .map(|opened_at| BREAKER_COOLDOWN.saturating_sub(opened_at.elapsed()));
+                let state = match remaining {
+                    None => "closed",
+                    Some(remaining) if remaining.is_zero() => "half_open",
+                    Some(_) => "open",
+                };
+                BreakerStatus {
+                    source: source.to_string(),
+                    state: state.to_string(),
+                    consecutive_failures: breaker.map(|b| b.consecutive_failures).unwrap_or(0),
+                    retry_in_secs: remaining.filter(|r| !r.is_zero()).map(|r| r.as_secs()),
+                }
+            })
+            .collect()
+    }
+}
+
+// Only transport errors, timeouts, rate limits and 5xx count against a source;
+// "token not found" says nothing about the provider's health
+fn is_source_outage(error: &str) -> bool {
+    if error.contains(" request: ") {
+        return true;
+    }
+    error.split(" status ")
+        .nth(1)
+        .and_then(|rest| rest.get(..3))
+        .and_then(|code| code.parse::<u16>().ok())
+        .is_some_and(|code| code == 429 || code >= 500)
+}
+
+#[tauri::command]
+fn source_diagnostics(data_source: tauri::State<'_, DataSourceState>) -> Vec<BreakerStatus> {
+    data_source.breakers.status()
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostics {
+    version: String,
+    generated_at: i64,
+    data_source: String,
+    sim_clock: SimClock,
+    api_config: ApiConfig,
+    breakers: Vec<BreakerStatus>,
+    recent_source_outcomes: Vec<SourceOutcome>,
+    cache_entries: HashMap<&'static str, usize>,
+    in_flight: HashMap<&'static str, usize>,
+    ticker_running: bool,
+    ticker_subscriptions: usize,
+    alerts: usize,
+    resting_orders: usize,
+    solana_stream_running: bool,
+}
+
+// Drops the query string, where stream providers put api keys
+fn redact_url(url: &str) -> String {
+    match url.split_once('?') {
+        Some((base, _)) => format!("{}?<redacted>", base),
+        None => url.to_string(),
+    }
+}
+
+// Keeps scheme, host and port but drops any user:password
+fn redact_proxy(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if !parsed.username().is_empty() || parsed.password().is_some() => {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            format!("{} (credentials redacted)", parsed)
+        }
+        Ok(parsed) => parsed.to_string(),
+        Err(_) => "<invalid>".to_string(),
+    }
+}
+
+// Everything a maintainer needs to reproduce "prices are wrong", as a JSON blob
+// users can attach to an issue. Secrets (stream api keys, proxy credentials)
+// are redacted.
+#[tauri::command]
+fn export_diagnostics(app: tauri::AppHandle) -> Result<String, String> {
+    let data_source = app.state::<DataSourceState>();
+    let in_flight = app.state::<InFlightRequests>();
+    let ticker = app.state::<TickerState>();
+
+    let mut api_config = app.state::<ApiConfigState>().get();
+    api_config.solana_stream_url = redact_url(&api_config.solana_stream_url);
+    api_config.proxy_url = configured_proxy(&api_config).map(redact_proxy);
+
+    let cache_entries = HashMap::from([
+        ("dex_stats", app.state::<DexStatsCache>().entries.lock_or_recover().len()),
+        ("token_metadata", app.state::<TokenMetadataCache>().entries.lock_or_recover().len()),
+    ]);
+    let in_flight = HashMap::from([
+        ("candles", in_flight.candles.requests.lock_or_recover().len()),
+        ("quotes", in_flight.quotes.requests.lock_or_recover().len()),
+        ("dex_prices", in_flight.dex_prices.requests.lock_or_recover().len()),
+        ("dex_stats", in_flight.dex_stats.requests.lock_or_recover().len()),
+    ]);
+
+    let diagnostics = Diagnostics {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        generated_at: now_millis(),
+        data_source: data_source.get().name().to_string(),
+        sim_clock: *app.state::<ClockState>().clock.lock_or_recover(),
+        api_config,
+        breakers: data_source.breakers.status(),
+        recent_source_outcomes: data_source.breakers.recent.lock_or_recover().iter().cloned().collect(),
+        cache_entries,
+        in_flight,
+        ticker_running: ticker.task.lock_or_recover().is_some(),
+        ticker_subscriptions: ticker.subscriptions.lock_or_recover().len(),
+        alerts: app.state::<AlertState>().alerts.lock_or_recover().len(),
+        resting_orders: app.state::<OrderBook>().orders.lock_or_recover().len(),
+        solana_stream_running: app.state::<SolanaStreamState>().task.lock_or_recover().is_some(),
+    };
+    serde_json::to_string_pretty(&diagnostics).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DexPriceResult {
+    price: f64,
+    // Percent change over 24h; None (null) when the source doesn't report one
+    change_24h: Option<f64>,
+    volume_24h: f64,
+    pair_address: String,
+    source: String,
+    // Epoch ms when this result was produced
+    fetched_at: i64,
+    // Epoch ms of the source's own last update, when it reports one (CoinGecko);
+    // Jupiter, Raydium, GeckoTerminal and DexScreener don't expose it
+    source_updated_at: Option<i64>,
+    // From the chosen DexScreener pair; other sources don't report liquidity
+    liquidity_usd: Option<f64>,
+    // Fills against a pool this thin are meaningless in a simulator
+    low_liquidity: bool,
+    // Display hints for micro-cap prices: decimals to show, and the exact value
+    // as text since JSON numbers like 1.234e-7 tend to get rounded naively
+    suggested_decimals: u8,
+    price_text: String,
+    // Quote token of the DexScreener pair the price came from (SOL, WETH...);
+    // the price itself is always USD. None for sources that quote USD directly.
+    quote_symbol: Option<String>,
+}
+
+impl DexPriceResult {
+    // A bare price from `source`; callers add what else their source reports
+    // with struct update syntax. `price_text` is the source's own text where it
+    // sends prices as strings, otherwise the shortest round-trip form.
+    fn new(source: &str, price: f64, price_text: String, pair_address: Option<String>) -> Self {
+        DexPriceResult {
+            price,
+            change_24h: None,
+            volume_24h: 0.0,
+            pair_address: pair_address.unwrap_or_default(),
+            source: source.to_string(),
+            fetched_at: now_millis(),
+            source_updated_at: None,
+            liquidity_usd: None,
+            low_liquidity: false,
+            suggested_decimals: suggested_decimals(price),
+            price_text,
+            quote_symbol: None,
+        }
+    }
+
+    // A DexScreener pair priced at `price`, which pair_price_usd may have
+    // converted from priceNative; then there's no priceUsd text to keep
+    fn from_pair(pair: &DexPair, price: f64, min_liquidity: f64) -> Self {
+        let price_text = pair.price_usd.as_deref()
+            .map(|raw| raw.trim().to_string())
+            .unwrap_or_else(|| price.to_string());
+        DexPriceResult {
+            change_24h: pair.price_change.as_ref().and_then(|p| p.h24),
+            volume_24h: pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0),
+            liquidity_usd: pair.liquidity_usd(),
+            low_liquidity: is_low_liquidity(pair.liquidity_usd(), min_liquidity),
+            quote_symbol: pair.quote_symbol(),
+            ..DexPriceResult::new("dexscreener", price, price_text, pair.pair_address.clone())
+        }
+    }
+}
+
+// Two decimals for large prices, otherwise four significant digits past the
+// leading zeros, e.g. 0.0000001234 -> 10
+fn suggested_decimals(price: f64) -> u8 {
+    if !price.is_finite() || price <= 0.0 {
+        return 2;
+    }
+    if price >= 1000.0 {
+        return 2;
+    }
+    if price >= 1.0 {
+        return 4;
+    }
+    let leading_zeros = (-price.log10()).ceil() as i32 - 1;
+    (leading_zeros + 4).clamp(4, 18) as u8
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn fetch_dex_price(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    prefetched: tauri::State<'_, PrefetchCache>,
+    chain_id: String,
+    address: String,
+    pair_address: Option<String>,
+    preferred_source: Option<String>,
+    coingecko_id: Option<String>,
+    source_order: Option<Vec<String>>,
+    dex_id: Option<String>,
+    race: Option<bool>,
+) -> Result<DexPriceResult, DexPriceError> {
+    let request = DexPriceRequest {
+        chain_id,
+        address,
+        pair_address,
+        preferred_source,
+        coingecko_id,
+        source_order,
+        dex_id,
+        race: race.unwrap_or(false),
+    };
+    let key = request.key();
+    if let Some(result) = take_prefetched(&prefetched.dex_prices, &key) {
+        return Ok(result);
+    }
+    let fetch = data_source.get().dex_price(api_config.get(), request);
+    in_flight.dex_prices.run(key, fetch).await
+}
+
+// One source's pending price request
+type SourceFetch = BoxFuture<'static, Result<DexPriceResult, String>>;
+
+// Price lookups for one token: the returned closure starts a source's request,
+// bounded by that source's timeout, or gives None when the source can't price
+// this token (Jupiter and Raydium off Solana, CoinGecko without an id)
+fn dex_source_lookup<'a>(api: &'a ApiConfig, breakers: &'a std::sync::Arc<SourceBreakers>, request: &DexPriceRequest) -> Result<impl Fn(&str) -> Option<SourceFetch> + 'a, String> {
+    let DexPriceRequest { chain_id, address, pair_address, coingecko_id, dex_id, .. } = request.clone();
+    let client = http_client(api, Duration::from_secs(5))?;
+    let is_solana = chain_id.to_lowercase() == "solana";
+    let ua = api.user_agent.as_str();
+
+    // Helper closures for each source
+    let try_jupiter = move |client: &reqwest::Client, address: &str, pair_address: &Option<String>| {
+        let client = client.clone();
+        let address = address.to_string();
+        let pa = pair_address.clone();
+        let ua = ua.to_string();
+        let base_url = api.jupiter_base_url.clone();
+        let breakers = breakers.clone();
+        async move {
+            // Jupiter Lite API v3 — free, no API key, 60 req/min
+            // Response is top-level HashMap<mint, {usdPrice, priceChange24h, ...}>
+            let url = format!("{}/price/v3?ids={}", base_url, address);
+            let response = client.get(&url)
+                .header("User-Agent", &ua)
+                .header("Accept", "application/json")
+                .send()
+                .await.map_err(|e| format!("Jupiter request: {}", e))?;
+            breakers.note_retry_after("jupiter", &response);
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!("Jupiter status {}", status));
+            }
+            let data: std::collections::HashMap<String, JupiterV3PriceData> = read_json("Jupiter", response).await
+                .map_err(|e| format!("Jupiter parse: {}", e))?;
+            let token = data.get(&address).ok_or("Jupiter: token not found")?;
+            let price = checked_price("Jupiter", token.usd_price.ok_or("Jupiter: no usdPrice")?)?;
+            let change_24h = token.price_change_24h;
+            log::info!("[price] Jupiter v3 OK: ${} (24h: {:?}%)", price, change_24h);
+            Ok(DexPriceResult { change_24h, ..DexPriceResult::new("jupiter", price, price.to_string(), pa) })
+        }
+    };
+
+    let try_raydium = move |client: &reqwest::Client, address: &str, pair_address: &Option<String>| {
+        let client = client.clone();
+        let address = address.to_string();
+        let pa = pair_address.clone();
+        let ua = ua.to_string();
+        let base_url = api.raydium_base_url.clone();
+        let breakers = breakers.clone();
+        async move {
+            // Raydium API v3 — free, no API key
+            let url = format!("{}/mint/price?mints={}", base_url, address);
+            let response = client.get(&url)
+                .header("User-Agent", &ua)
+                .header("Accept", "application/json")
+                .send()
+                .await.map_err(|e| format!("Raydium request: {}", e))?;
+            breakers.note_retry_after("raydium", &response);
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!("Raydium status {}", status));
+            }
+            let data: RaydiumPriceResponse = response.json().await
+                .map_err(|e| format!("Raydium parse: {}", e))?;
+            let prices = data.data.ok_or("Raydium: no data")?;
+            let price_str = prices.get(&address).ok_or("Raydium: token not found")?;
+            let price = parse_price("Raydium", price_str)?;
+            log::info!("[price] Raydium OK: ${}", price);
+            Ok(DexPriceResult::new("raydium", price, price_str.trim().to_string(), pa))
+        }
+    };
+
+    let try_gecko = move |client: &reqwest::Client, chain_id: &str, address: &str, pair_address: &Option<String>| {
+        let client = client.clone();
+        let address = address.to_string();
+        let pa = pair_address.clone();
+        let network = chain_to_gecko_network(chain_id).unwrap_or("").to_string();
+        let ua = ua.to_string();
+        let base_url = api.gecko_terminal_base_url.clone();
+        let breakers = breakers.clone();
+        async move {
+            if network.is_empty() { return Err("Gecko: unsupported chain".to_string()); }
+            let url = format!(
+                "{}/simple/networks/{}/token_price/{}",
+                base_url, network, address
+            );
+            let response = client.get(&url)
+                .header("User-Agent", &ua)
+                .header("Accept", "application/json")
+                .send()
+                .await.map_err(|e| format!("Gecko request: {}", e))?;
+            breakers.note_retry_after("gecko", &response);
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!("Gecko status {}", status));
+            }
+            let data: GeckoTokenPriceResponse = response.json().await
+                .map_err(|e| format!("Gecko parse: {}", e))?;
+            let price_data = data.data.ok_or("Gecko: no data")?;
+            let attrs = price_data.attributes.ok_or("Gecko: no attributes")?;
+            let prices = attrs.token_prices.ok_or("Gecko: no token_prices")?;
+            let price_opt = prices.get(&address).or_else(|| prices.get(&address.to_lowercase()));
+            let price_str = price_opt
+                .and_then(|v| v.as_ref())
+                .ok_or("Gecko: token not in results")?;
+            let price = parse_price("Gecko", price_str)?;
+            log::info!("[price] GeckoTerminal OK: ${}", price);
+            Ok(DexPriceResult::new("gecko", price, price_str.trim().to_string(), pa))
+        }
+    };
+
+    let try_dexscreener = move |client: &reqwest::Client, chain_id: &str, address: &str, pair_address: &Option<String>, dex_id: &Option<String>| {
+        let client = client.clone();
+        let chain_id = chain_id.to_string();
+        let address = address.to_string();
+        let pa = pair_address.clone();
+        let dex_id = dex_id.clone();
+        let ua = ua.to_string();
+        let base_url = api.dexscreener_base_url.clone();
+        let breakers = breakers.clone();
+        let min_liquidity = api.low_liquidity_usd;
+        async move {
+            // Try pairs endpoint first
+            if let Some(ref pa_str) = pa {
+                let url = format!("{}/latest/dex/pairs/{}/{}", base_url, chain_id, pa_str);
+                if let Ok(response) = client.get(&url)
+                    .header("User-Agent", &ua)
+                    .send().await
+                {
+                    breakers.note_retry_after("dexscreener", &response);
+                    if let Ok(data) = read_json::<DexScreenerResponse>("DexScreener", response).await {
+                        // A pair on another venue than the requested one falls through to the tokens endpoint
+                        let pair = data.pairs.as_ref().and_then(|p| p.first()).or(data.pair.as_ref())
+                            .filter(|p| dex_id.as_deref().map_or(true, |d| p.dex_id.as_deref().is_some_and(|pd| pd.eq_ignore_ascii_case(d))));
+                        if let Some(pair) = pair {
+                            if let Ok(price) = pair_price_usd(&client, &breakers, &base_url, &ua, pair).await {
+                                log::info!("[price] DexScreener OK: ${}", price);
+                                return Ok(DexPriceResult::from_pair(pair, price, min_liquidity));
+                            }
+                        }
+                    }
+                }
+            }
+            // Fall back to tokens endpoint
+            let url = format!("{}/latest/dex/tokens/{}", base_url, address);
+            let response = client.get(&url)
+                .header("User-Agent", &ua)
+                .send().await.map_err(|e| format!("DexScreener request: {}", e))?;
+            breakers.note_retry_after("dexscreener", &response);
+            // Counted as outages by the breaker; a 5xx body isn't worth parsing
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                return Err(format!("DexScreener status {}", status));
+            }
+            let data: DexScreenerResponse = read_json("DexScreener", response).await
+                .map_err(|e| format!("DexScreener parse: {}", e))?;
+            let best = match dex_id {
+                Some(ref dex_id) => best_pair_on_dex(&data, &chain_id, dex_id)?,
+                None => best_pair(&data, &chain_id).ok_or("DexScreener: no pairs")?,
+            };
+            let price = pair_price_usd(&client, &breakers, &base_url, &ua, best).await?;
+            log::info!("[price] DexScreener OK: ${}", price);
+            Ok(DexPriceResult::from_pair(best, price, min_liquidity))
+        }
+    };
+
+    let try_coingecko = move |client: &reqwest::Client, id: &str, pair_address: &Option<String>| {
+        let client = client.clone();
+        let id = id.to_string();
+        let pa = pair_address.clone();
+        let ua = ua.to_string();
+        let base_url = api.coingecko_base_url.clone();
+        let breakers = breakers.clone();
+        async move {
+            // CoinGecko keys on its own asset ids (bitcoin, ethereum) rather than addresses
+            let url = format!(
+                "{}/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true&include_24hr_vol=true&include_last_updated_at=true",
+                base_url, id
+            );
+            let response = client.get(&url)
+                .header("User-Agent", &ua)
+                .header("Accept", "application/json")
+                .send()
+                .await.map_err(|e| format!("CoinGecko request: {}", e))?;
+            breakers.note_retry_after("coingecko", &response);
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!("CoinGecko status {}", status));
+            }
+            let data: std::collections::HashMap<String, CoinGeckoPriceData> = response.json().await
+                .map_err(|e| format!("CoinGecko parse: {}", e))?;
+            let coin = data.get(&id).ok_or("CoinGecko: id not found")?;
+            let price = checked_price("CoinGecko", coin.usd.ok_or("CoinGecko: no usd price")?)?;
+            log::info!("[price] CoinGecko OK: ${}", price);
+            Ok(DexPriceResult {
+                change_24h: coin.usd_24h_change,
+                volume_24h: coin.usd_24h_vol.unwrap_or(0.0),
+                source_updated_at: coin.last_updated_at.map(|secs| secs * 1000),
+                ..DexPriceResult::new("coingecko", price, price.to_string(), pa)
+            })
+        }
+    };
+
+    Ok(move |source: &str| -> Option<SourceFetch> {
+        let fetch = match source {
+            // Jupiter and Raydium only price Solana mints
+            "jupiter" if is_solana => try_jupiter(&client, &address, &pair_address).boxed(),
+            "raydium" if is_solana => try_raydium(&client, &address, &pair_address).boxed(),
+            "gecko" => try_gecko(&client, &chain_id, &address, &pair_address).boxed(),
+            "dexscreener" => try_dexscreener(&client, &chain_id, &address, &pair_address, &dex_id).boxed(),
+            // Last resort for major assets without a good DEX pair
+            "coingecko" => try_coingecko(&client, coingecko_id.as_deref()?, &pair_address).boxed(),
+            _ => return None,
+        };
+        // load_dex_price checks this itself via skip_reason; this covers the comparison
+        if let Some(wait) = breakers.retry_after_secs(source) {
+            return Some(futures::future::ready(Err(format!("{} skipped, rate limited for another {}s", source, wait))).boxed());
+        }
+        let timeout = api.dex_source_timeouts.for_source(source);
+        let source = source.to_string();
+        Some(async move {
+            match tokio::time::timeout(timeout, fetch).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("{} request: timed out after {}ms", source, timeout.as_millis())),
+            }
+        }.boxed())
+    })
+}
+
+// Why `source` must be skipped right now, if it must. Skips are checked before
+// the lookup and never recorded, so a back-off can't trip the breaker.
+fn skip_reason(breakers: &SourceBreakers, source: &str) -> Option<String> {
+    if let Some(wait) = breakers.retry_after_secs(source) {
+        return Some(format!("skipped, rate limited for another {}s", wait));
+    }
+    (!breakers.allow(source)).then(|| "skipped, circuit open after repeated failures".to_string())
+}
+
+// `settled` holds sources the caller already tried for this token (e.g. a
+// batched Jupiter request); they are skipped and reported with the rest
+async fn load_dex_price(
+    api: ApiConfig,
+    breakers: std::sync::Arc<SourceBreakers>,
+    request: DexPriceRequest,
+    settled: Vec<SourceAttempt>,
+) -> Result<DexPriceResult, DexPriceError> {
+    let lookup = dex_source_lookup(&api, &breakers, &request)?;
+    let DexPriceRequest { chain_id, address, preferred_source, coingecko_id, source_order, dex_id, race, .. } = request;
+    let pref = preferred_source.as_deref().unwrap_or("");
+
+    let mut attempts = settled;
+
+    // No on-chain address to look up — CoinGecko is the only option
+    if address.trim().is_empty() {
+        let fetch = coingecko_id.as_ref().and_then(|_| lookup("coingecko"))
+            .ok_or("No address or CoinGecko id given".to_string())?;
+        return match fetch.await {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                attempts.push(SourceAttempt::new("coingecko", e));
+                Err(DexPriceError::from_attempts(attempts))
+            }
+        };
+    }
+
+    let is_solana = chain_id.to_lowercase() == "solana";
+
+    // Only DexScreener reports which venue a price came from, so a pinned DEX
+    // bypasses the aggregators entirely
+    if let Some(ref dex) = dex_id {
+        if let Some(reason) = skip_reason(&breakers, "dexscreener") {
+            return Err(format!("DexScreener {}; cannot price {} on {}", reason, address, dex).into());
+        }
+        let result = lookup("dexscreener").expect("DexScreener prices any token").await;
+        breakers.record("dexscreener", result.as_ref().err().map(String::as_str));
+        return result.map_err(|e| e.into());
+    }
+
+    // For Solana: ALWAYS try Jupiter first — it's real-time, and gecko/dexscreener
+    // are too slow to lead with, whatever the caller prefers
+    let mut order: Vec<String> = if is_solana { vec!["jupiter".to_string()] } else { Vec::new() };
+    match source_order {
+        Some(source_order) => {
+            if let Some(unknown) = source_order.iter().find(|s| !DEX_PRICE_SOURCES.contains(&s.as_str())) {
+                return Err(format!(
+                    "Unknown price source '{}', expected one of: {}",
+                    unknown,
+                    DEX_PRICE_SOURCES.join(", ")
+                ).into());
+            }
+            for source in source_order {
+                if !order.contains(&source) {
+                    order.push(source);
+                }
+            }
+        }
+        None => {
+            // Default: Raydium on Solana, then the preferred aggregator, then the other, then CoinGecko
+            if is_solana {
+                order.push("raydium".to_string());
+            }
+            if pref == "gecko" || pref == "dexscreener" {
+                order.push(pref.to_string());
+            }
+            for source in ["gecko", "dexscreener", "coingecko"] {
+                if source != pref {
+                    order.push(source.to_string());
+                }
+            }
+        }
+    }
+    order.retain(|source| !attempts.iter().any(|a| a.source == *source));
+
+    // Race mode: the first two sources in order (by default the chain's two
+    // fast ones) run concurrently and the first valid price wins; dropping the
+    // rest cancels their requests. If both fail, the remaining sources are
+    // tried in order as usual.
+    if race {
+        let racers: Vec<String> = order.drain(..order.len().min(2)).collect();
+        let mut pending = futures::stream::FuturesUnordered::new();
+        for source in racers.iter().map(String::as_str) {
+            if let Some(reason) = skip_reason(&breakers, source) {
+                log::info!("[price] {} {}", source, reason);
+                attempts.push(SourceAttempt::new(source, reason));
+                continue;
+            }
+            if let Some(fetch) = lookup(source) {
+                pending.push(fetch.map(move |result| (source, result)));
+            }
+        }
+        while let Some((source, result)) = pending.next().await {
+            breakers.record(source, result.as_ref().err().map(String::as_str));
+            match result {
+                Ok(result) => {
+                    log::info!("[price] {} won the race", source);
+                    return Ok(result);
+                }
+                Err(e) => {
+                    log::warn!("[price] {} failed: {}", source, e);
+                    attempts.push(SourceAttempt::new(source, e));
+                }
+            }
+        }
+    }
+
+    for source in &order {
+        if let Some(reason) = skip_reason(&breakers, source) {
+            log::info!("[price] {} {}", source, reason);
+            attempts.push(SourceAttempt::new(source, reason));
+            continue;
+        }
+        let Some(fetch) = lookup(source) else { continue };
+        let result = fetch.await;
+        breakers.record(source, result.as_ref().err().map(String::as_str));
+        match result {
+            Ok(result) => return Ok(result),
+            Err(e) => {
+                log::warn!("[price] {} failed: {}", source, e);
+                attempts.push(SourceAttempt::new(source, e));
+            }
+        }
+    }
+
+    Err(DexPriceError::from_attempts(attempts))
+}
+
+#[derive(Debug, Serialize)]
+struct SourcePrice {
+    source: String,
+    price: Option<f64>,
+    error: Option<String>,
+    latency_ms: u64,
+    // Against the median of the sources that answered; large values point at a
+    // stale or manipulated feed
+    deviation_percent: Option<f64>,
+}
+
+// Asks every source that can price the token, rather than stopping at the
+// first success, so their prices can be compared. Circuit breakers are
+// neither consulted nor updated: this is a diagnostic.
+#[tauri::command]
+async fn compare_dex_sources(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    chain_id: String,
+    address: String,
+    pair_address: Option<String>,
+    coingecko_id: Option<String>,
+) -> Result<Vec<SourcePrice>, String> {
+    let api = api_config.get();
+    let request = DexPriceRequest {
+        chain_id,
+        address,
+        pair_address,
+        preferred_source: None,
+        coingecko_id,
+        source_order: None,
+        dex_id: None,
+        race: false,
+    };
+    let lookup = dex_source_lookup(&api, &data_source.breakers, &request)?;
+    let timed = DEX_PRICE_SOURCES.iter()
+        .filter(|source| !request.address.trim().is_empty() || **source == "coingecko")
+        .filter_map(|source| {
+            let fetch = lookup(source)?;
+            Some(async move {
+                let started = Instant::now();
+                let result = fetch.await;
+                (source.to_string(), result, started.elapsed().as_millis() as u64)
+            })
+        });
+    let results = futures::future::join_all(timed).await;
+
+    let mut prices: Vec<f64> = results.iter().filter_map(|(_, r, _)| r.as_ref().ok().map(|r| r.price)).collect();
+    prices.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = match prices.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(prices[n / 2]),
+        n => Some((prices[n / 2 - 1] + prices[n / 2]) / 2.0),
+    };
+
+    Ok(results.into_iter()
+        .map(|(source, result, latency_ms)| match result {
+            Ok(result) => SourcePrice {
+                source,
+                price: Some(result.price),
+                error: None,
+                latency_ms,
+                deviation_percent: median.and_then(|m| percent_change(m, result.price)),
+            },
+            Err(e) => SourcePrice { source, price: None, error: Some(e), latency_ms, deviation_percent: None },
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize)]
+struct SourceHealth {
+    name: String,
+    reachable: bool,
+    latency_ms: u64,
+    error: Option<String>,
+}
+
+async fn probe_source(client: reqwest::Client, ua: String, name: &str, url: String) -> SourceHealth {
+    let started = Instant::now();
+    let result = client.get(&url)
+        .header("User-Agent", &ua)
+        .header("Accept", "application/json")
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let error = match result {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("HTTP {}", response.status())),
+        Err(e) if e.is_timeout() => Some("Timed out".to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+
+    SourceHealth {
+        name: name.to_string(),
+        reachable: error.is_none(),
+        latency_ms,
+        error,
+    }
+}
+
+// Batched fetch_dex_price: Solana tokens share one Jupiter request per
+// JUPITER_BATCH_SIZE mints; anything Jupiter misses, and every other chain,
+// goes through the normal per-token fallback chain concurrently
+async fn load_dex_prices(api: ApiConfig, breakers: std::sync::Arc<SourceBreakers>, requests: Vec<DexPriceRequest>) -> Vec<Result<DexPriceResult, DexPriceError>> {
+    let mut solana: Vec<String> = requests.iter()
+        .filter(|r| r.chain_id.eq_ignore_ascii_case("solana") && !r.address.is_empty() && r.dex_id.is_none())
+        .map(|r| r.address.clone())
+        .collect();
+    solana.sort();
+    solana.dedup();
+
+    let mut jupiter = HashMap::new();
+    // Mints sent in a batch that succeeded. One it came back without is
+    // unindexed on Jupiter, so its fallback skips asking Jupiter again.
+    let mut asked: std::collections::HashSet<String> = std::collections::HashSet::new();
+    // While Jupiter is backing off, each token falls through to its other sources;
+    // without an HTTP client they fail there with the reason
+    let client = (!solana.is_empty() && breakers.retry_after_secs("jupiter").is_none())
+        .then(|| http_client(&api, Duration::from_secs(5)).ok())
+        .flatten();
+    if let Some(client) = client {
+        for chunk in solana.chunks(JUPITER_BATCH_SIZE) {
+            match fetch_jupiter_prices(&client, &api, &breakers, chunk).await {
+                Ok(prices) => {
+                    jupiter.extend(prices);
+                    asked.extend(chunk.iter().cloned());
+                }
+                Err(e) => log::warn!("[price] Jupiter batch failed: {}", e),
+            }
+        }
+    }
+
+    let lookups = requests.into_iter().map(|request| {
+        let batched = jupiter_batch_outcome(&jupiter, &asked, &request);
+        let api = api.clone();
+        let breakers = breakers.clone();
+        async move {
+            match batched {
+                Ok((price, change_24h)) => Ok(DexPriceResult {
+                    change_24h,
+                    ..DexPriceResult::new("jupiter", price, price.to_string(), request.pair_address)
+                }),
+                Err(settled) => load_dex_price(api, breakers, request, settled).await,
+            }
+        }
+    });
+    futures::future::join_all(lookups).await
+}
+
+#[tauri::command]
+async fn fetch_dex_prices(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    requests: Vec<DexPriceRequest>,
+) -> Result<Vec<Result<DexPriceResult, String>>, String> {
+    let results = data_source.get().dex_prices(api_config.get(), requests).await;
+    Ok(results.into_iter().map(|r| r.map_err(|e| e.to_string())).collect())
+}
+
+// Prefetched results are handed to the first matching fetch within this window
+const PREFETCH_TTL: Duration = Duration::from_secs(30);
+// Lookups in flight at once during a prefetch, to stay under the sources' rate limits
+const PREFETCH_CONCURRENCY: usize = 4;
+
+// Warm results from `prefetch`, each served once so regular polling stays live
+struct PrefetchCache {
+    quotes: Mutex<HashMap<String, (Instant, StockQuote)>>,
+    dex_prices: Mutex<HashMap<String, (Instant, DexPriceResult)>>,
+}
+
+fn take_prefetched<T>(entries: &Mutex<HashMap<String, (Instant, T)>>, key: &str) -> Option<T> {
+    let mut entries = entries.lock_or_recover();
+    entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < PREFETCH_TTL);
+    entries.remove(key).map(|(_, value)| value)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PrefetchComplete {
+    quotes: usize,
+    dex_prices: usize,
+    // Symbols and token addresses whose lookup failed
+    failed: Vec<String>,
+}
+
+// Warms quotes and DEX prices during the splash screen so the watchlist renders
+// from cache. Returns immediately; "prefetch-complete" is emitted when done.
+#[tauri::command]
+fn prefetch(app: tauri::AppHandle, symbols: Vec<String>, dex: Vec<DexPriceRequest>) {
+    tauri::async_runtime::spawn(run_prefetch(app, symbols, dex));
+}
+
+async fn run_prefetch(app: tauri::AppHandle, symbols: Vec<String>, dex: Vec<DexPriceRequest>) {
+    let in_flight = app.state::<InFlightRequests>();
+    let cache = app.state::<PrefetchCache>();
+    let api = app.state::<ApiConfigState>().get();
+    let source = app.state::<DataSourceState>().get();
+    let now_ms = app.state::<ClockState>().now_millis();
+
+    // Through the in-flight maps, so fetches the UI starts meanwhile join these
+    let quotes: Vec<_> = futures::stream::iter(symbols)
+        .map(|symbol| {
+            let fetch = source.stock_quote(api.clone(), symbol.clone(), false, now_ms);
+            let lookup = in_flight.quotes.run(quote_key(&symbol, false, now_ms), fetch);
+            async move { (symbol, lookup.await) }
+        })
+        .buffer_unordered(PREFETCH_CONCURRENCY)
+        .collect()
+        .await;
+    let prices: Vec<_> = futures::stream::iter(dex)
+        .map(|request| {
+            let key = request.key();
+            let address = request.address.clone();
+            let lookup = in_flight.dex_prices.run(key.clone(), source.dex_price(api.clone(), request));
+            async move { (key, address, lookup.await) }
+        })
+        .buffer_unordered(PREFETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut complete = PrefetchComplete { quotes: 0, dex_prices: 0, failed: Vec::new() };
+    {
+        let mut cached = cache.quotes.lock_or_recover();
+        for (symbol, result) in quotes {
+            match result {
+                Ok(quote) => {
+                    cached.insert(symbol, (Instant::now(), quote));
+                    complete.quotes += 1;
+                }
+                Err(e) => {
+                    log::warn!("[prefetch] {}: {}", symbol, e);
+                    complete.failed.push(symbol);
+                }
+            }
+        }
+    }
+    {
+        let mut cached = cache.dex_prices.lock_or_recover();
+        for (key, address, result) in prices {
+            match result {
+                Ok(price) => {
+                    cached.insert(key, (Instant::now(), price));
+                    complete.dex_prices += 1;
+                }
+                Err(e) => {
+                    log::warn!("[prefetch] {}: {}", address, e);
+                    complete.failed.push(address);
+                }
+            }
+        }
+    }
+    log::info!("[prefetch] Warmed {} quotes and {} DEX prices", complete.quotes, complete.dex_prices);
+    let _ = app.emit("prefetch-complete", complete);
+}
+
+// Probe every price source in parallel so one slow source can't hold up the rest
+#[tauri::command]
+async fn check_sources(api_config: tauri::State<'_, ApiConfigState>) -> Result<Vec<SourceHealth>, String> {
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(3))?;
+
+    let sol_mint = "So11111111111111111111111111111111111111112";
+    let yahoo_host = api.yahoo_hosts.first().cloned().unwrap_or_default();
+    let probes = vec![
+        ("yahoo", format!("https://{}/v8/finance/chart/SPY?interval=1d&range=1d", yahoo_host)),
+        ("jupiter", format!("{}/price/v3?ids={}", api.jupiter_base_url, sol_mint)),
+        ("raydium", format!("{}/mint/price?mints={}", api.raydium_base_url, sol_mint)),
+        ("gecko", format!("{}/networks?page=1", api.gecko_terminal_base_url)),
+        ("dexscreener", format!("{}/latest/dex/tokens/{}", api.dexscreener_base_url, sol_mint)),
+    ];
+
+    let checks = probes.into_iter()
+        .map(|(name, url)| probe_source(client.clone(), api.user_agent.clone(), name, url));
+    Ok(futures::future::join_all(checks).await)
+}
+
+#[derive(Debug, Serialize)]
+struct DexPairInfo {
+    chain_id: String,
+    pair_address: String,
+    base_symbol: String,
+    quote_symbol: String,
+    dex_id: String,
+    liquidity_usd: f64,
+}
+
+// Find the highest-liquidity pair for a raw token address on a chain
+#[tauri::command]
+async fn resolve_dex_pair(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    chain_id: String,
+    address: String,
+) -> Result<DexPairInfo, String> {
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(10))?;
+
+    data_source.breakers.check_retry_after("dexscreener")?;
+    let url = format!("{}/latest/dex/tokens/{}", api.dexscreener_base_url, address);
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .send().await.map_err(|e| format!("DexScreener: {}", e))?;
+    data_source.breakers.note_retry_after("dexscreener", &response);
+    let data: DexScreenerResponse = response.json().await
+        .map_err(|e| format!("DexScreener parse: {}", e))?;
+    let pairs = data.pairs.unwrap_or_default();
+    if pairs.is_empty() {
+        return Err(format!("No pairs found for token {}", address));
+    }
+
+    let best = pairs.iter()
+        .filter(|p| p.chain_id.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(&chain_id)))
+        .max_by(|a, b| {
+            let la = a.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+            let lb = b.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
+            la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+    let Some(best) = best else {
+        let mut chains: Vec<String> = pairs.iter().filter_map(|p| p.chain_id.clone()).collect();
+        chains.sort();
+        chains.dedup();
+        return Err(format!(
+            "Token {} not found on {}; available on: {}",
+            address, chain_id, chains.join(", ")
+        ));
+    };
+
+    Ok(DexPairInfo {
+        chain_id: best.chain_id.clone().unwrap_or(chain_id),
+        pair_address: best.pair_address.clone().ok_or("DexScreener: pair has no address")?,
+        base_symbol: best.base_token.as_ref().and_then(|t| t.symbol.clone()).unwrap_or_default(),
+        quote_symbol: best.quote_token.as_ref().and_then(|t| t.symbol.clone()).unwrap_or_default(),
+        dex_id: best.dex_id.clone().unwrap_or_default(),
+        liquidity_usd: best.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0),
+    })
+}
+
+// Repeat stats requests inside this window reuse the last result, which keeps
+// a chatty UI off DexScreener's rate limit
+const DEX_STATS_MIN_INTERVAL: Duration = Duration::from_secs(15);
+
+struct DexStatsCache {
+    entries: Mutex<HashMap<String, (Instant, DexPriceResult)>>,
+}
+
+// EVM addresses are hex and case-insensitive (mixed case is only a checksum);
+// anything else, e.g. a base58 Solana mint, is kept as given
+fn address_key(address: &str) -> String {
+    if address.starts_with("0x") || address.starts_with("0X") {
+        address.to_lowercase()
+    } else {
+        address.to_string()
+    }
+}
+
+// Separate command for 24h stats (called less frequently)
+#[tauri::command]
+async fn fetch_dex_stats(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    cache: tauri::State<'_, DexStatsCache>,
+    chain_id: String,
+    address: String,
+    pair_address: Option<String>,
+) -> Result<DexPriceResult, String> {
+    let key = format!(
+        "{}|{}|{}",
+        chain_id.to_lowercase(),
+        address_key(&address),
+        pair_address.as_deref().map(address_key).unwrap_or_default()
+    );
+    if let Some((fetched_at, stats)) = cache.entries.lock_or_recover().get(&key) {
+        if fetched_at.elapsed() < DEX_STATS_MIN_INTERVAL {
+            return Ok(stats.clone());
+        }
+    }
+
+    // Misses that arrive together share one request
+    let (api, breakers) = (api_config.get(), data_source.breakers.clone());
+    let fetch = async move { load_dex_stats(&api, &breakers, chain_id, address, pair_address).await };
+    let stats = in_flight.dex_stats.run(key.clone(), fetch).await?;
+    let mut entries = cache.entries.lock_or_recover();
+    entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < DEX_STATS_MIN_INTERVAL);
+    entries.insert(key, (Instant::now(), stats.clone()));
+    Ok(stats)
+}
+
+async fn load_dex_stats(api: &ApiConfig, breakers: &SourceBreakers, chain_id: String, address: String, pair_address: Option<String>) -> Result<DexPriceResult, String> {
+    breakers.check_retry_after("dexscreener")?;
+    let min_liquidity = api.low_liquidity_usd;
+    let client = http_client(api, Duration::from_secs(10))?;
+
+    // Always use DexScreener for stats (24h change, volume)
+    if let Some(ref pa) = pair_address {
+        let url = format!(
+            "{}/latest/dex/pairs/{}/{}",
+            api.dexscreener_base_url, chain_id, pa
+        );
+        if let Ok(response) = client.get(&url).send().await {
+            breakers.note_retry_after("dexscreener", &response);
+            if let Ok(data) = response.json::<DexScreenerResponse>().await {
+                let pair = data.pairs.as_ref().and_then(|p| p.first()).or(data.pair.as_ref());
+                if let Some(pair) = pair {
+                    // A pair with a bad price falls through to the tokens endpoint
+                    if let Ok(price) = pair_price_usd(&client, breakers, &api.dexscreener_base_url, &api.user_agent, pair).await {
+                        return Ok(DexPriceResult::from_pair(pair, price, min_liquidity));
+                    }
+                }
+            }
+        }
+    }
+
+    // Fall back to tokens endpoint, unless the pair request was rate limited
+    breakers.check_retry_after("dexscreener")?;
+    let url = format!("{}/latest/dex/tokens/{}", api.dexscreener_base_url, address);
+    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
+    breakers.note_retry_after("dexscreener", &response);
+    let data: DexScreenerResponse = response.json().await.map_err(|e| e.to_string())?;
+    let best = best_pair(&data, &chain_id).ok_or("No pairs found")?;
+
+    let price = pair_price_usd(&client, breakers, &api.dexscreener_base_url, &api.user_agent, best).await?;
+    Ok(DexPriceResult::from_pair(best, price, min_liquidity))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChainPrice {
+    chain_id: String,
+    #[serde(flatten)]
+    result: DexPriceResult,
+}
+
+// The same address can be deployed on several chains; price it on each chain
+// where it is the base token of a liquid pair, most liquid chain first
+#[tauri::command]
+async fn fetch_dex_price_all_chains(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    address: String,
+) -> Result<Vec<ChainPrice>, String> {
+    let api = api_config.get();
+    let breakers = data_source.breakers.as_ref();
+    let client = http_client(&api, Duration::from_secs(10))?;
+
+    breakers.check_retry_after("dexscreener")?;
+    let url = format!("{}/latest/dex/tokens/{}", api.dexscreener_base_url, address);
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .send().await.map_err(|e| format!("DexScreener request: {}", e))?;
+    breakers.note_retry_after("dexscreener", &response);
+    let data: DexScreenerResponse = response.json().await.map_err(|e| format!("DexScreener parse: {}", e))?;
+
+    // Most liquid pair per chain, ignoring pairs that only quote the token
+    let mut best: HashMap<String, &DexPair> = HashMap::new();
+    for pair in data.pairs.iter().flatten() {
+        let is_base = pair.is_base_token(&address);
+        let liquid = pair.liquidity_usd().is_some_and(|usd| usd >= api.low_liquidity_usd);
+        let Some(chain_id) = pair.chain_id.as_ref().filter(|_| is_base && liquid) else { continue };
+        let entry = best.entry(chain_id.to_lowercase()).or_insert(pair);
+        if pair.liquidity_usd() > entry.liquidity_usd() {
+            *entry = pair;
+        }
+    }
+
+    // Pairs without priceUsd are converted from priceNative; a chain whose
+    // pair can't be priced is left out
+    let priced = best.into_iter().map(|(chain_id, pair)| {
+        let (client, api) = (&client, &api);
+        async move {
+            match pair_price_usd(client, breakers, &api.dexscreener_base_url, &api.user_agent, pair).await {
+                Ok(price) => Some(ChainPrice {
+                    chain_id,
+                    // Every pair here already cleared the liquidity threshold
+                    result: DexPriceResult::from_pair(pair, price, api.low_liquidity_usd),
+                }),
+                Err(e) => {
+                    log::warn!("[price] No price on {}: {}", chain_id, e);
+                    None
+                }
+            }
+        }
+    });
+    let mut prices: Vec<ChainPrice> = futures::future::join_all(priced).await.into_iter().flatten().collect();
+    prices.sort_by(|a, b| {
+        let la = a.result.liquidity_usd.unwrap_or(0.0);
+        let lb = b.result.liquidity_usd.unwrap_or(0.0);
+        lb.partial_cmp(&la).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(prices)
+}
+
+// Buy/sell counts and USD volume for one DexScreener time window
+#[derive(Debug, Serialize)]
+struct DexActivityWindow {
+    buys: u64,
+    sells: u64,
+    volume_usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct DexActivity {
+    chain_id: String,
+    pair_address: String,
+    m5: DexActivityWindow,
+    h1: DexActivityWindow,
+    h6: DexActivityWindow,
+    h24: DexActivityWindow,
+}
+
+fn activity_window(txns: Option<&DexTxnCount>, volume: Option<f64>) -> DexActivityWindow {
+    let txns = txns.cloned().unwrap_or_default();
+    DexActivityWindow {
+        buys: txns.buys,
+        sells: txns.sells,
+        volume_usd: volume.unwrap_or(0.0),
+    }
+}
+
+// Recent trading momentum for a pair, from DexScreener's txns/volume breakdowns
+#[tauri::command]
+async fn fetch_dex_activity(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    chain_id: String,
+    pair_address: String,
+) -> Result<DexActivity, String> {
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(10))?;
+    data_source.breakers.check_retry_after("dexscreener")?;
+
+    let url = format!(
+        "{}/latest/dex/pairs/{}/{}",
+        api.dexscreener_base_url, chain_id, pair_address
+    );
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    data_source.breakers.note_retry_after("dexscreener", &response);
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("DexScreener status {}", status));
+    }
+    let data: DexScreenerResponse = response.json().await.map_err(|e| e.to_string())?;
+    let pair = data.pairs.and_then(|p| p.into_iter().next()).or(data.pair)
+        .ok_or_else(|| format!("Pair {} not found on {}", pair_address, chain_id))?;
+
+    let txns = pair.txns.as_ref();
+    let volume = pair.volume.as_ref();
+    Ok(DexActivity {
+        chain_id: pair.chain_id.clone().unwrap_or(chain_id),
+        pair_address: pair.pair_address.clone().unwrap_or(pair_address),
+        m5: activity_window(txns.and_then(|t| t.m5.as_ref()), volume.and_then(|v| v.m5)),
+        h1: activity_window(txns.and_then(|t| t.h1.as_ref()), volume.and_then(|v| v.h1)),
+        h6: activity_window(txns.and_then(|t| t.h6.as_ref()), volume.and_then(|v| v.h6)),
+        h24: activity_window(txns.and_then(|t| t.h24.as_ref()), volume.and_then(|v| v.h24)),
+    })
+}
+
+// GeckoTerminal token info response
+#[derive(Debug, Deserialize)]
+struct GeckoTokenInfoResponse {
+    data: Option<GeckoTokenInfoData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeckoTokenInfoData {
+    attributes: Option<GeckoTokenInfoAttributes>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeckoTokenInfoAttributes {
+    name: Option<String>,
+    symbol: Option<String>,
+    decimals: Option<u8>,
+    image_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TokenMetadata {
+    chain_id: String,
+    address: String,
+    name: String,
+    symbol: String,
+    decimals: Option<u8>, // GeckoTerminal only
+    image_url: Option<String>,
+    source: String,
+}
+
+// Token metadata effectively never changes, so keep it for the whole day
+const TOKEN_METADATA_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct TokenMetadataCache {
+    entries: Mutex<HashMap<String, (Instant, TokenMetadata)>>,
+}
+
+async fn gecko_token_metadata(client: &reqwest::Client, api: &ApiConfig, breakers: &SourceBreakers, chain_id: &str, address: &str) -> Result<TokenMetadata, String> {
+    let network = chain_to_gecko_network(chain_id).ok_or("Gecko: unsupported chain")?;
+    breakers.check_retry_after("gecko")?;
+    let url = format!("{}/networks/{}/tokens/{}", api.gecko_terminal_base_url, network, address);
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Gecko request: {}", e))?;
+    breakers.note_retry_after("gecko", &response);
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Gecko status {}", status));
+    }
+    let data: GeckoTokenInfoResponse = response.json().await.map_err(|e| format!("Gecko parse: {}", e))?;
+    let attrs = data.data.and_then(|d| d.attributes).ok_or("Gecko: no attributes")?;
+    Ok(TokenMetadata {
+        chain_id: chain_id.to_string(),
+        address: address.to_string(),
+        name: attrs.name.ok_or("Gecko: no name")?,
+        symbol: attrs.symbol.unwrap_or_default(),
+        decimals: attrs.decimals,
+        // Gecko uses "missing.png" placeholders for tokens without a logo
+        image_url: attrs.image_url.filter(|u| !u.contains("missing")),
+        source: "gecko".to_string(),
+    })
+}
+
+async fn dexscreener_token_metadata(client: &reqwest::Client, api: &ApiConfig, breakers: &SourceBreakers, chain_id: &str, address: &str) -> Result<TokenMetadata, String> {
+    breakers.check_retry_after("dexscreener")?;
+    let url = format!("{}/latest/dex/tokens/{}", api.dexscreener_base_url, address);
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .send()
+        .await
+        .map_err(|e| format!("DexScreener request: {}", e))?;
+    breakers.note_retry_after("dexscreener", &response);
+    let data: DexScreenerResponse = response.json().await.map_err(|e| format!("DexScreener parse: {}", e))?;
+    let pairs = data.pairs.ok_or("DexScreener: no pairs")?;
+    // The pair's image belongs to its base token, so a quote-side match gets none
+    let (token, image_url) = pairs.iter()
+        .filter(|p| p.chain_id.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(chain_id)))
+        .find_map(|p| {
+            let matches = |t: &&DexToken| t.address.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(address));
+            if let Some(base) = p.base_token.as_ref().filter(matches) {
+                return Some((base, p.info.as_ref().and_then(|i| i.image_url.clone())));
+            }
+            p.quote_token.as_ref().filter(matches).map(|quote| (quote, None))
+        })
+        .ok_or("DexScreener: token not in any pair")?;
+    Ok(TokenMetadata {
+        chain_id: chain_id.to_string(),
+        address: address.to_string(),
+        name: token.name.clone().ok_or("DexScreener: no name")?,
+        symbol: token.symbol.clone().unwrap_or_default(),
+        decimals: None,
+        image_url,
+        source: "dexscreener".to_string(),
+    })
+}
+
+// Human-readable name, symbol and logo for a token address
+#[tauri::command]
+async fn fetch_token_metadata(
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    cache: tauri::State<'_, TokenMetadataCache>,
+    chain_id: String,
+    address: String,
+) -> Result<TokenMetadata, String> {
+    let key = format!("{}|{}", chain_id.to_lowercase(), address_key(&address));
+    if let Some((cached_at, metadata)) = cache.entries.lock_or_recover().get(&key) {
+        if cached_at.elapsed() < TOKEN_METADATA_TTL {
+            return Ok(metadata.clone());
+        }
+    }
+
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(10))?;
+
+    let metadata = match gecko_token_metadata(&client, &api, &data_source.breakers, &chain_id, &address).await {
+        Ok(metadata) => metadata,
+        Err(gecko_error) => {
+            log::warn!("[metadata] {}", gecko_error);
+            dexscreener_token_metadata(&client, &api, &data_source.breakers, &chain_id, &address).await
+                .map_err(|e| format!("{}; {}", gecko_error, e))?
+        }
+    };
+
+    cache.entries.lock_or_recover().insert(key, (Instant::now(), metadata.clone()));
+    Ok(metadata)
+}
+
+// Tradeable token lists for the crypto picker, cached on disk for a day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TokenListEntry {
+    symbol: String,
+    name: String,
+    address: String,
+    decimals: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct JupiterToken {
+    id: String,
+    name: String,
+    symbol: String,
+    decimals: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct EvmTokenList {
+    tokens: Vec<EvmToken>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EvmToken {
+    #[serde(rename = "chainId")]
+    chain_id: u64,
+    address: String,
+    name: String,
+    symbol: String,
+    decimals: u8,
+}
+
+const TOKEN_LIST_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+async fn download_token_list(client: &reqwest::Client, api: &ApiConfig, chain: &ChainSpec) -> Result<Vec<TokenListEntry>, String> {
+    // Jupiter's verified list for Solana, the Uniswap list filtered by chain for EVM
+    let url = match chain.evm_chain_id {
+        None => format!("{}/tokens/v2/tag?query=verified", api.jupiter_base_url),
+        Some(_) => api.evm_token_list_url.clone(),
+    };
+    let response = client.get(&url)
+        .header("User-Agent", &api.user_agent)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Token list request: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Token list status {}", status));
+    }
+
+    let entries = match chain.evm_chain_id {
+        None => response.json::<Vec<JupiterToken>>().await
+            .map_err(|e| format!("Token list parse: {}", e))?
+            .into_iter()
+            .map(|t| TokenListEntry { symbol: t.symbol, name: t.name, address: t.id, decimals: t.decimals })
+            .collect(),
+        Some(evm_chain_id) => response.json::<EvmTokenList>().await
+            .map_err(|e| format!("Token list parse: {}", e))?
+            .tokens
+            .into_iter()
+            .filter(|t| t.chain_id == evm_chain_id)
+            .map(|t| TokenListEntry { symbol: t.symbol, name: t.name, address: t.address, decimals: t.decimals })
+            .collect(),
+    };
+    Ok(entries)
+}
+
+fn read_token_list(path: &std::path::Path) -> Option<(Duration, Vec<TokenListEntry>)> {
+    let age = std::fs::metadata(path).ok()?.modified().ok()?.elapsed().unwrap_or_default();
+    let entries = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    Some((age, entries))
+}
+
+#[tauri::command]
+async fn fetch_token_list(app: tauri::AppHandle, api_config: tauri::State<'_, ApiConfigState>, chain_id: String) -> Result<Vec<TokenListEntry>, String> {
+    let chain_id = chain_id.to_lowercase();
+    let chain = SUPPORTED_CHAINS.iter()
+        .find(|chain| chain.chain_id == chain_id)
+        .ok_or_else(|| format!("Unsupported chain '{}'", chain_id))?;
+
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?.join("token_lists");
+    let path = dir.join(format!("{}.json", chain.chain_id));
+    let cached = read_token_list(&path);
+    if let Some((age, entries)) = &cached {
+        if *age < TOKEN_LIST_TTL {
+            return Ok(entries.clone());
+        }
+    }
+
+    let api = api_config.get();
+    let client = http_client(&api, Duration::from_secs(30))?;
+
+    match download_token_list(&client, &api, chain).await {
+        Ok(entries) => {
+            let written = std::fs::create_dir_all(&dir)
+                .and_then(|_| std::fs::write(&path, serde_json::to_string(&entries).unwrap_or_default()));
+            if let Err(e) = written {
+                log::warn!("[tokens] Couldn't cache {}: {}", path.display(), e);
+            }
+            log::info!("[tokens] {} tokens for {}", entries.len(), chain.chain_id);
+            Ok(entries)
+        }
+        // A stale list still beats an empty picker
+        Err(e) => match cached {
+            Some((_, entries)) => {
+                log::warn!("[tokens] {}; serving cached list for {}", e, chain.chain_id);
+                Ok(entries)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+// Portfolio valuation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AssetKind {
+    Stock,
+    Crypto,
+}
+
+// Stocks are priced by ticker; crypto by token address on chain_id.
+// A negative quantity is a short position.
+#[derive(Debug, Clone, Deserialize)]
+struct Position {
+    kind: AssetKind,
+    symbol: String,
+    #[serde(default)]
+    chain_id: Option<String>,
+    quantity: f64,
+    avg_entry_price: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Portfolio {
+    #[serde(default)]
+    cash: f64,
+    positions: Vec<Position>,
+}
+
+#[derive(Debug, Serialize)]
+struct PositionValuation {
+    kind: AssetKind,
+    symbol: String,
+    quantity: f64,
+    price: Option<f64>,
+    market_value: f64,
+    cost_basis: f64,
+    unrealized_pnl: f64,
+    unrealized_pnl_percent: f64,
+    // None when the price source reports no previous close or 24h change
+    day_change: Option<f64>,
+    // Price lookup failed; the position is carried at cost with no P/L
+    stale: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PortfolioValuation {
+    cash: f64,
+    market_value: f64,
+    total_equity: f64,
+    unrealized_pnl: f64,
+    day_change: f64,
+    positions: Vec<PositionValuation>,
+}
+
+// Current price and absolute per-unit change since the previous close / 24h
+// ago; the change is None when the source can't say
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    price: f64,
+    change: Option<f64>,
+}
+
+// Absolute change implied by `percent` measured from the earlier price. A move
+// of -100% or worse has no earlier price to recover, so it is unknown.
+fn change_from_percent(price: f64, percent: f64) -> Option<f64> {
+    let factor = 1.0 + percent / 100.0;
+    (factor.is_finite() && factor > 0.0).then(|| price - price / factor)
+}
+
+// A quote's price is the extended-hours one outside regular hours while its
+// change is regular-session only; add the session move so the change runs
+// from the previous close to that price
+fn stock_mark(quote: &StockQuote) -> Mark {
+    Mark { price: quote.price, change: Some(quote.change + quote.session_change.unwrap_or(0.0)) }
+}
+
+fn position_key(position: &Position) -> String {
+    match position.kind {
+        AssetKind::Stock => format!("stock|{}", position.symbol.to_uppercase()),
+        AssetKind::Crypto => format!("crypto|{}|{}", position.chain_id.as_deref().unwrap_or("").to_lowercase(), position.symbol),
+    }
+}
+
+async fn mark_position(api: ApiConfig, source: std::sync::Arc<dyn DataSource>, in_flight: &InFlightRequests, position: Position, now_ms: i64) -> Result<Mark, String> {
+    match position.kind {
+        AssetKind::Stock => {
+            let symbol = required_symbol(&position.symbol)?;
+            let quote = in_flight.quotes.run(quote_key(&symbol, false, now_ms), source.stock_quote(api, symbol, false, now_ms)).await.map_err(|e| e.to_string())?;
+            Ok(stock_mark(&quote))
+        }
+        AssetKind::Crypto => {
+            let chain_id = position.chain_id.ok_or_else(|| format!("Crypto position {} has no chain_id", position.symbol))?;
+            let request = DexPriceRequest {
+                chain_id,
+                address: position.symbol,
+                pair_address: None,
+                preferred_source: None,
+                coingecko_id: None,
+                source_order: None,
+                dex_id: None,
+                race: false,
+            };
+            let key = request.key();
+            let result = in_flight.dex_prices.run(key, source.dex_price(api, request)).await.map_err(|e| e.to_string())?;
+            let change = result.change_24h.and_then(|percent| change_from_percent(result.price, percent));
+            Ok(Mark { price: result.price, change })
+        }
+    }
+}
+
+#[tauri::command]
+async fn value_portfolio(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    clock: tauri::State<'_, ClockState>,
+    portfolio: Portfolio,
+) -> Result<PortfolioValuation, String> {
+    let api = api_config.get();
+    let source = data_source.get();
+    let now_ms = clock.now_millis();
+
+    // Price each distinct holding once, concurrently
+    let mut distinct: HashMap<String, Position> = HashMap::new();
+    for position in &portfolio.positions {
+        distinct.entry(position_key(position)).or_insert_with(|| position.clone());
+    }
+    let lookups = distinct.into_iter().map(|(key, position)| {
+        let fetch = mark_position(api.clone(), source.clone(), &in_flight, position, now_ms);
+        async move { (key, fetch.await) }
+    });
+    let marks: HashMap<String, Result<Mark, String>> = futures::future::join_all(lookups).await.into_iter().collect();
+
+    let mut valuation = PortfolioValuation {
+        cash: portfolio.cash,
+        market_value: 0.0,
+        total_equity: portfolio.cash,
+        unrealized_pnl: 0.0,
+        day_change: 0.0,
+        positions: Vec::with_capacity(portfolio.positions.len()),
+    };
+
+    for position in portfolio.positions {
+        let cost_basis = position.quantity * position.avg_entry_price;
+        let entry = match &marks[&position_key(&position)] {
+            Ok(mark) => {
+                let market_value = position.quantity * mark.price;
+                let unrealized_pnl = market_value - cost_basis;
+                PositionValuation {
+                    kind: position.kind,
+                    symbol: position.symbol,
+                    quantity: position.quantity,
+                    price: Some(mark.price),
+                    market_value,
+                    cost_basis,
+                    unrealized_pnl,
+                    unrealized_pnl_percent: if cost_basis != 0.0 { unrealized_pnl / cost_basis.abs() * 100.0 } else { 0.0 },
+                    day_change: mark.change.map(|change| position.quantity * change),
+                    stale: false,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                log::warn!("[portfolio] Could not price {}: {}", position.symbol, e);
+                PositionValuation {
+                    kind: position.kind,
+                    symbol: position.symbol,
+                    quantity: position.quantity,
+                    price: None,
+                    market_value: cost_basis,
+                    cost_basis,
+                    unrealized_pnl: 0.0,
+                    unrealized_pnl_percent: 0.0,
+                    day_change: None,
+                    stale: true,
+                    error: Some(e.clone()),
+                }
+            }
+        };
+        valuation.market_value += entry.market_value;
+        valuation.unrealized_pnl += entry.unrealized_pnl;
+        valuation.day_change += entry.day_change.unwrap_or(0.0);
+        valuation.positions.push(entry);
+    }
+    valuation.total_equity = valuation.cash + valuation.market_value;
+
+    Ok(valuation)
+}
+
+#[derive(Debug, Serialize)]
+struct AffordableResult {
+    price: f64,
+    // Whole shares/tokens only
+    units: f64,
+    cost: f64,
+    leftover_cash: f64,
+}
+
+// How many whole units `cash` buys at the current price: a stock ticker, or a
+// token address when chain_id is given. There is no commission model, so the
+// cost is just units * price.
+#[tauri::command]
+async fn max_affordable(
+    api_config: tauri::State<'_, ApiConfigState>,
+    in_flight: tauri::State<'_, InFlightRequests>,
+    data_source: tauri::State<'_, DataSourceState>,
+    clock: tauri::State<'_, ClockState>,
+    symbol_or_address: String,
+    cash: f64,
+    chain_id: Option<String>,
+) -> Result<AffordableResult, String> {
+    if !(cash.is_finite() && cash >= 0.0) {
+        return Err(format!("Invalid cash amount: {}", cash));
+    }
+    let position = Position {
+        kind: if chain_id.is_some() { AssetKind::Crypto } else { AssetKind::Stock },
+        symbol: symbol_or_address.trim().to_string(),
+        chain_id,
+        quantity: 0.0,
+        avg_entry_price: 0.0,
+    };
+    let mark = mark_position(api_config.get(), data_source.get(), &in_flight, position, clock.now_millis()).await?;
+    if !(mark.price.is_finite() && mark.price > 0.0) {
+        return Err(format!("No usable price for {}", symbol_or_address));
+    }
+
+    let units = (cash / mark.price).floor();
+    let cost = units * mark.price;
+    Ok(AffordableResult {
+        price: mark.price,
+        units,
+        cost,
+        leftover_cash: cash - cost,
+    })
+}
+
+// FIFO realized/unrealized P&L over a trade history
+#[tauri::command]
+fn compute_pnl(trades: Vec<positions::Trade>) -> positions::PnlReport {
+    positions::compute(&trades)
+}
+
+// Quantity that risks `risk_percent` of equity between entry and stop
+#[tauri::command]
+fn compute_position_size(
+    account_equity: f64,
+    risk_percent: f64,
+    entry_price: f64,
+    stop_price: f64,
+    side: Option<positions::TradeSide>,
+) -> Result<positions::PositionSize, String> {
+    positions::size_position(account_equity, risk_percent, entry_price, stop_price, side)
+}
+
+// Exit price that nets zero for an open position after the fees already paid
+// on it; the closing trade's own fee is not included
+#[tauri::command]
+fn compute_break_even(avg_cost: f64, quantity: f64, fees_paid: Option<f64>, side: positions::TradeSide) -> Result<f64, String> {
+    positions::break_even(avg_cost, quantity, fees_paid.unwrap_or(0.0), side)
+}
+
+// Return, drawdown, volatility and Sharpe for [timestamp ms, equity] points;
+// risk_free_rate is annual, as a fraction (0.04 = 4%)
+#[tauri::command]
+fn compute_performance_stats(curve: Vec<(i64, f64)>, risk_free_rate: Option<f64>) -> Result<performance::PerformanceStats, String> {
+    performance::compute(&curve, risk_free_rate.unwrap_or(0.0))
+}
+
+// Append-only trade journal (one JSON trade per line) in the app data dir;
+// a crash can at worst leave a torn final line, which load_trades skips
+struct TradeJournal {
+    write_lock: Mutex<()>,
+}
+
+fn trade_journal_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("trades.jsonl"))
+}
+
+#[tauri::command]
+fn record_trade(app: tauri::AppHandle, journal: tauri::State<'_, TradeJournal>, trade: positions::Trade) -> Result<(), String> {
+    if !(trade.quantity.is_finite() && trade.quantity > 0.0) {
+        return Err(format!("Invalid trade quantity: {}", trade.quantity));
+    }
+    if !(trade.price.is_finite() && trade.price > 0.0) {
+        return Err(format!("Invalid trade price: {}", trade.price));
+    }
+
+    let path = trade_journal_path(&app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let line = serde_json::to_string(&trade).map_err(|e| e.to_string())?;
+
+    let _guard = journal.write_lock.lock_or_recover();
+    append_line(&path, &line).map_err(|e| e.to_string())
+}
+
+// Appends `line` and a newline, first terminating a torn final line left by a
+// crash so the new record doesn't get glued onto it
+fn append_line(path: &std::path::Path, line: &str) -> std::io::Result<()> {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+    let mut record = String::with_capacity(line.len() + 2);
+    if file.seek(SeekFrom::End(0))? > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            record.push('\n');
+        }
+    }
+    record.push_str(line);
+    record.push('\n');
+    file.write_all(record.as_bytes())?;
+    file.sync_data()
+}
+
+// Trades at or after `since` (epoch ms), in journal order
+#[tauri::command]
+fn load_trades(app: tauri::AppHandle, since: Option<i64>) -> Result<Vec<positions::Trade>, String> {
+    let path = trade_journal_path(&app)?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let trades = contents.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str::<positions::Trade>(line) {
+            Ok(trade) => Some(trade),
+            Err(e) => {
+                log::warn!("[journal] Skipping line {} of {}: {}", i + 1, path.display(), e);
+                None
+            }
+        })
+        .filter(|trade| !since.is_some_and(|since| trade.timestamp < since))
+        .collect();
+    Ok(trades)
+}
+
+// Writes through a temp file and a rename so a crash mid-write leaves the old
+// file intact rather than a truncated one
+fn write_atomic(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+// Named watchlists, saved to watchlists.json in the app data dir. Every command
+// returns the full map so the UI can redraw its tabs.
+type Watchlists = std::collections::BTreeMap<String, Vec<String>>;
+
+struct WatchlistStore {
+    write_lock: Mutex<()>,
+}
+
+fn watchlists_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("watchlists.json"))
+}
+
+fn read_watchlists(app: &tauri::AppHandle) -> Result<Watchlists, String> {
+    let path = watchlists_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Watchlists::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn write_watchlists(app: &tauri::AppHandle, watchlists: &Watchlists) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(watchlists).map_err(|e| e.to_string())?;
+    write_atomic(&watchlists_path(app)?, &json)
+}
+
+#[tauri::command]
+fn load_watchlists(app: tauri::AppHandle) -> Result<Watchlists, String> {
+    read_watchlists(&app)
+}
+
+// Creates or replaces a watchlist. Symbols keep their case (DEX addresses are
+// case-sensitive) and order; blanks and repeats are dropped.
+#[tauri::command]
+fn save_watchlist(app: tauri::AppHandle, store: tauri::State<'_, WatchlistStore>, name: String, symbols: Vec<String>) -> Result<Watchlists, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Watchlist name is required".to_string());
+    }
+    let mut cleaned: Vec<String> = Vec::with_capacity(symbols.len());
+    for symbol in symbols {
+        let symbol = symbol.trim();
+        if !symbol.is_empty() && !cleaned.iter().any(|s| s == symbol) {
+            cleaned.push(symbol.to_string());
+        }
+    }
+
+    let _guard = store.write_lock.lock_or_recover();
+    let mut watchlists = read_watchlists(&app)?;
+    watchlists.insert(name, cleaned);
+    write_watchlists(&app, &watchlists)?;
+    Ok(watchlists)
+}
+
+#[tauri::command]
+fn delete_watchlist(app: tauri::AppHandle, store: tauri::State<'_, WatchlistStore>, name: String) -> Result<Watchlists, String> {
+    let _guard = store.write_lock.lock_or_recover();
+    let mut watchlists = read_watchlists(&app)?;
+    if watchlists.remove(name.trim()).is_none() {
+        return Err(format!("No watchlist named '{}'", name.trim()));
+    }
+    write_watchlists(&app, &watchlists)?;
+    Ok(watchlists)
+}
+
+// Local state the user can inspect and clear from settings. Holdings live in
+// the frontend store; the trade journal is the backend's record of them.
+const STORAGE_KINDS: [&str; 4] = ["trades", "orders", "watchlists", "cache"];
+
+#[derive(Debug, Clone, Serialize)]
+struct StorageEntry {
+    kind: String,
+    path: String,
+    exists: bool,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StorageInfo {
+    data_dir: String,
+    cache_dir: String,
+    entries: Vec<StorageEntry>,
+    total_bytes: u64,
+}
+
+fn storage_path(app: &tauri::AppHandle, kind: &str) -> Result<PathBuf, String> {
+    match kind {
+        "trades" => trade_journal_path(app),
+        "orders" => order_book_path(app),
+        "watchlists" => watchlists_path(app),
+        "cache" => Ok(app.path().app_cache_dir().map_err(|e| e.to_string())?.join("token_lists")),
+        other => Err(format!("Unknown storage kind '{}' (expected one of {})", other, STORAGE_KINDS.join(", "))),
+    }
+}
+
+// Size of a file, or of everything under a directory
+fn disk_usage(path: &std::path::Path) -> u64 {
+    let Ok(meta) = std::fs::metadata(path) else { return 0 };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| disk_usage(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+fn get_storage_info(app: tauri::AppHandle) -> Result<StorageInfo, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let cache_dir = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    let mut entries = Vec::with_capacity(STORAGE_KINDS.len());
+    for kind in STORAGE_KINDS {
+        let path = storage_path(&app, kind)?;
+        entries.push(StorageEntry {
+            kind: kind.to_string(),
+            exists: path.exists(),
+            bytes: disk_usage(&path),
+            path: path.display().to_string(),
+        });
+    }
+    Ok(StorageInfo {
+        data_dir: data_dir.display().to_string(),
+        cache_dir: cache_dir.display().to_string(),
+        total_bytes: entries.iter().map(|e| e.bytes).sum(),
+        entries,
+    })
+}
+
+// Deletes the named kinds of local state and returns what is left. Every kind
+// is checked before anything is removed, so a typo deletes nothing.
+#[tauri::command]
+fn clear_storage(
+    app: tauri::AppHandle,
+    journal: tauri::State<'_, TradeJournal>,
+    watchlist_store: tauri::State<'_, WatchlistStore>,
+    order_book: tauri::State<'_, OrderBook>,
+    kinds: Vec<String>,
+) -> Result<StorageInfo, String> {
+    let targets = kinds.iter()
+        .map(|kind| {
+            let kind = kind.trim().to_lowercase();
+            storage_path(&app, &kind).map(|path| (kind, path))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    for (kind, path) in targets {
+        let removed = match kind.as_str() {
+            "trades" => {
+                let _guard = journal.write_lock.lock_or_recover();
+                std::fs::remove_file(&path)
+            }
+            "orders" => {
+                // Clear the live book too, or the next change would write it back
+                let mut orders = order_book.orders.lock_or_recover();
+                orders.clear();
+                order_book.pending_fills.lock_or_recover().clear();
+                std::fs::remove_file(&path)
+            }
+            "watchlists" => {
+                let _guard = watchlist_store.write_lock.lock_or_recover();
+                std::fs::remove_file(&path)
+            }
+            _ => std::fs::remove_dir_all(&path),
+        };
+        match removed {
+            Ok(()) => log::info!("[storage] Cleared {} ({})", kind, path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Couldn't clear {}: {}", kind, e)),
+        }
+    }
+    get_storage_info(app)
+}
+
+// Simulated clock for replays: quote session status (and the extended-hours
+// price it selects) is evaluated as of this instant, while data is still
+// fetched live. Cache TTLs keep measuring real elapsed time.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+enum SimClock {
+    Real,
+    // Frozen at epoch_ms
+    Fixed { epoch_ms: i64 },
+    // Started at an earlier instant and running at wall-clock speed
+    Offset { offset_ms: i64 },
+}
+
+impl SimClock {
+    fn now_millis(&self) -> i64 {
+        match *self {
+            SimClock::Real => now_millis(),
+            SimClock::Fixed { epoch_ms } => epoch_ms,
+            SimClock::Offset { offset_ms } => now_millis() + offset_ms,
+        }
+    }
+}
+
+struct ClockState {
+    clock: Mutex<SimClock>,
+}
+
+impl ClockState {
+    fn now_millis(&self) -> i64 {
+        self.clock.lock_or_recover().now_millis()
+    }
+}
+
+// `epoch` in ms; None restores wall time. With `running` the clock starts at
+// `epoch` and keeps ticking, e.g. to replay Monday's open in real time.
+#[tauri::command]
+fn set_sim_clock(clock: tauri::State<'_, ClockState>, epoch: Option<i64>, running: Option<bool>) -> SimClock {
+    let next = match (epoch, running.unwrap_or(false)) {
+        (None, _) => SimClock::Real,
+        (Some(epoch_ms), false) => SimClock::Fixed { epoch_ms },
+        (Some(epoch_ms), true) => SimClock::Offset { offset_ms: epoch_ms - now_millis() },
+    };
+    log::info!("[clock] {:?}", next);
+    *clock.clock.lock_or_recover() = next;
+    next
+}
+
+#[tauri::command]
+fn get_sim_clock(clock: tauri::State<'_, ClockState>) -> SimClock {
+    *clock.clock.lock_or_recover()
+}
+
+// Solana price streaming
+struct SolanaStreamState {
+    task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DexPriceTick {
+    address: String,
+    price: f64,
+    source: String, // "stream" or "jupiter" while the socket is down
+    timestamp: i64,
+}
+
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const STREAM_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+// Runs until the socket closes or errors. Returns whether any tick was received,
+// so the caller can reset its reconnect backoff.
+async fn stream_solana_prices(app: &tauri::AppHandle, url: &str, addresses: &[String]) -> Result<bool, String> {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await
+        .map_err(|e| format!("connect: {}", e))?;
+
+    for address in addresses {
+        let subscribe = serde_json::json!({
+            "type": "SUBSCRIBE_PRICE",
+            "data": { "queryType": "simple", "chartType": "1m", "address": address, "currency": "usd" }
+        });
+        socket.send(Message::Text(subscribe.to_string())).await
+            .map_err(|e| format!("subscribe: {}", e))?;
+    }
+    log::info!("[stream] Connected, subscribed to {} tokens", addresses.len());
+
+    let mut received = false;
+    while let Some(message) = socket.next().await {
+        let text = match message.map_err(|e| format!("read: {}", e))? {
+            Message::Text(text) => text,
+            Message::Ping(payload) => {
+                let _ = socket.send(Message::Pong(payload)).await;
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+        if value["type"] != "PRICE_DATA" {
+            continue;
+        }
+        let data = &value["data"];
+        let (Some(address), Some(price)) = (data["address"].as_str(), data["c"].as_f64()) else { continue };
+        if checked_price("stream", price).is_err() {
+            continue;
+        }
+
+        received = true;
+        let _ = app.emit("dex-price-tick", DexPriceTick {
+            address: address.to_string(),
+            price,
+            source: "stream".to_string(),
+            timestamp: now_millis(),
+        });
+    }
+
+    log::info!("[stream] Socket closed");
+    Ok(received)
+}
+
+// Fallback while the socket is down: one batched Jupiter request for all addresses
+async fn poll_solana_prices(app: &tauri::AppHandle, client: &reqwest::Client, api: &ApiConfig, breakers: &SourceBreakers, addresses: &[String]) {
+    // Quietly sit out Jupiter's back-off; the 429 was already logged
+    if breakers.retry_after_secs("jupiter").is_some() {
+        return;
+    }
+    let mut data = HashMap::new();
+    for chunk in addresses.chunks(JUPITER_BATCH_SIZE) {
+        match fetch_jupiter_prices(client, api, breakers, chunk).await {
+            Ok(prices) => data.extend(prices),
+            Err(e) => log::warn!("[stream] Jupiter poll failed: {}", e),
+        }
+    }
+
+    for address in addresses {
+        if let Some(price) = data.get(address).and_then(|t| t.usd_price).and_then(|p| checked_price("Jupiter", p).ok()) {
+            let _ = app.emit("dex-price-tick", DexPriceTick {
+                address: address.clone(),
+                price,
+                source: "jupiter".to_string(),
+                timestamp: now_millis(),
+            });
+        }
+    }
+}
+
+async fn run_solana_stream(app: tauri::AppHandle, api: ApiConfig, breakers: std::sync::Arc<SourceBreakers>, addresses: Vec<String>) {
+    let client = match http_client(&api, Duration::from_secs(5)) {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("[stream] Not starting: {}", e);
+            return;
+        }
+    };
+
+    // No push endpoint configured — this is just a batched polling loop
+    if api.solana_stream_url.is_empty() {
+        loop {
+            poll_solana_prices(&app, &client, &api, &breakers, &addresses).await;
+            tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+        }
+    }
+
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match stream_solana_prices(&app, &api.solana_stream_url, &addresses).await {
+            Ok(true) => backoff = Duration::from_secs(1),
+            Ok(false) => {}
+            Err(e) => log::warn!("[stream] {}", e),
+        }
+
+        // Keep prices flowing by polling until the next reconnect attempt
+        let reconnect_at = Instant::now() + backoff;
+        while Instant::now() < reconnect_at {
+            poll_solana_prices(&app, &client, &api, &breakers, &addresses).await;
+            tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+        }
+        backoff = (backoff * 2).min(STREAM_MAX_BACKOFF);
+    }
+}
+
+#[tauri::command]
+fn start_solana_stream(
+    app: tauri::AppHandle,
+    api_config: tauri::State<'_, ApiConfigState>,
+    data_source: tauri::State<'_, DataSourceState>,
+    stream: tauri::State<'_, SolanaStreamState>,
+    addresses: Vec<String>,
+) -> Result<(), String> {
+    if addresses.is_empty() {
+        return Err("No addresses to stream".to_string());
+    }
+
+    let mut task = stream.task.lock_or_recover();
+    if let Some(previous) = task.take() {
+        previous.abort();
+    }
+    *task = Some(tauri::async_runtime::spawn(run_solana_stream(app.clone(), api_config.get(), data_source.breakers.clone(), addresses)));
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_solana_stream(stream: tauri::State<'_, SolanaStreamState>) {
+    if let Some(task) = stream.task.lock_or_recover().take() {
+        task.abort();
+    }
+}
+
+// Background quote ticker: one loop polls every subscribed symbol plus any
+// symbol with a live alert or resting order, and exits once none is left
+struct TickerState {
+    subscriptions: Mutex<Vec<String>>,
+    task: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TickerStatus {
+    running: bool,
+    subscribed: usize,
+    alert_symbols: usize,
+    order_symbols: usize,
+}
+
+const TICKER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+fn ticker_symbols(subscriptions: &[String], alerts: &[PriceAlert], orders: &[orders::RestingOrder]) -> Vec<String> {
+    let mut symbols = subscriptions.to_vec();
+    symbols.extend(watched_symbols(alerts));
+    symbols.extend(orders.iter().map(|o| o.symbol.clone()));
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+async fn run_ticker(app: tauri::AppHandle) {
+    let ticker = app.state::<TickerState>();
+    let alert_state = app.state::<AlertState>();
+    let order_book = app.state::<OrderBook>();
+    let in_flight = app.state::<InFlightRequests>();
+
+    loop {
+        let (symbols, subscribed) = {
+            // Decide whether to exit while holding the task slot, so a concurrent
+            // subscribe either sees this loop or an empty slot, never a dying loop
+            let mut task = ticker.task.lock_or_recover();
+            let subscribed = ticker.subscriptions.lock_or_recover().clone();
+            let symbols = ticker_symbols(
+                &subscribed,
+                &alert_state.alerts.lock_or_recover(),
+                &order_book.orders.lock_or_recover(),
+            );
+            if symbols.is_empty() {
+                task.take();
+                return;
+            }
+            (symbols, subscribed)
+        };
+
+        let api = app.state::<ApiConfigState>().get();
+        let source = app.state::<DataSourceState>().get();
+        let now_ms = app.state::<ClockState>().now_millis();
+        let quotes = futures::future::join_all(symbols.into_iter().map(|symbol| {
+            let fetch = source.stock_quote(api.clone(), symbol.clone(), false, now_ms);
+            in_flight.quotes.run(quote_key(&symbol, false, now_ms), fetch)
+        })).await;
+
+        let now = now_millis();
+        let mut wait = TICKER_POLL_INTERVAL;
+        let mut fired = Vec::new();
+        {
+            let mut alerts = alert_state.alerts.lock_or_recover();
+            for quote in &quotes {
+                match quote {
+                    Ok(quote) => {
+                        fired.extend(check_alerts(&mut alerts, quote, now));
+                        wait = wait.max(Duration::from_secs(quote.suggested_refresh_secs));
+                    }
+                    Err(e) => log::warn!("[ticker] Quote failed: {}", e),
+                }
+            }
+        }
+        let mut fills = Vec::new();
+        {
+            let mut book = order_book.orders.lock_or_recover();
+            let was_triggered: Vec<u64> = book.iter().filter(|o| o.triggered).map(|o| o.id).collect();
+            for quote in quotes.iter().flatten() {
+                fills.extend(orders::match_orders(&mut book, &quote.symbol, quote.price, now));
+            }
+            // A stop-limit whose stop was touched must stay triggered across a restart
+            let newly_triggered = book.iter().any(|o| o.triggered && !was_triggered.contains(&o.id));
+            if !fills.is_empty() {
+                order_book.pending_fills.lock_or_recover().extend(fills.iter().cloned());
+            }
+            if !fills.is_empty() || newly_triggered {
+                if let Err(e) = order_book.save(&app, &book) {
+                    log::warn!("[orders] Failed to save order book: {}", e);
+                }
+            }
+        }
+        for quote in quotes.into_iter().flatten() {
+            if subscribed.contains(&quote.symbol) {
+                let _ = app.emit("ticker-update", quote);
+            }
+        }
+        for event in fired {
+            log::info!("[alerts] {} crossed {} at {}", event.alert.symbol, event.alert.threshold, event.price);
+            let _ = app.emit("alert-triggered", event);
+        }
+        for fill in fills {
+            log::info!("[orders] Order {} on {} filled at {}", fill.order.id, fill.order.symbol, fill.price);
+            let _ = app.emit("order-filled", fill);
+        }
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Idempotent: spawns the loop only when none is running
+fn ensure_ticker(app: &tauri::AppHandle, ticker: &TickerState) {
+    let mut task = ticker.task.lock_or_recover();
+    if task.is_none() {
+        *task = Some(tauri::async_runtime::spawn(run_ticker(app.clone())));
+    }
+}
+
+#[tauri::command]
+fn subscribe_ticker(app: tauri::AppHandle, ticker: tauri::State<'_, TickerState>, symbol: String) -> Result<(), String> {
+    let symbol = required_symbol(&symbol)?;
+    {
+        let mut subscriptions = ticker.subscriptions.lock_or_recover();
+        if !subscriptions.contains(&symbol) {
+            subscriptions.push(symbol);
+        }
+    }
+    ensure_ticker(&app, &ticker);
+    Ok(())
+}
 
-    let is_solana = chain_id.to_lowercase() == "solana";
+// The loop stops by itself once nothing is subscribed or alerted
+#[tauri::command]
+fn unsubscribe_ticker(ticker: tauri::State<'_, TickerState>, symbol: String) {
+    let symbol = normalize_symbol(&symbol);
+    ticker.subscriptions.lock_or_recover().retain(|s| *s != symbol);
+}
 
-    // For Solana: ALWAYS try Jupiter first, then Raydium — both are real-time.
-    // Don't let preferred_source skip them, because gecko/dexscreener are too slow.
-    if is_solana {
-        match try_jupiter(&client, &address, &pair_address).await {
-            Ok(result) => return Ok(result),
-            Err(e) => eprintln!("[price] Jupiter failed: {}", e),
-        }
-        match try_raydium(&client, &address, &pair_address).await {
-            Ok(result) => return Ok(result),
-            Err(e) => eprintln!("[price] Raydium failed: {}", e),
+#[tauri::command]
+fn ticker_status(
+    ticker: tauri::State<'_, TickerState>,
+    alert_state: tauri::State<'_, AlertState>,
+    order_book: tauri::State<'_, OrderBook>,
+) -> TickerStatus {
+    let running = ticker.task.lock_or_recover().is_some();
+    let mut order_symbols: Vec<String> = order_book.orders.lock_or_recover().iter().map(|o| o.symbol.clone()).collect();
+    order_symbols.sort();
+    order_symbols.dedup();
+    TickerStatus {
+        running,
+        subscribed: ticker.subscriptions.lock_or_recover().len(),
+        alert_symbols: watched_symbols(&alert_state.alerts.lock_or_recover()).len(),
+        order_symbols: order_symbols.len(),
+    }
+}
+
+// Price alerts, checked on each ticker poll while any alert can still fire
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AlertDirection {
+    Above,
+    Below,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PriceAlert {
+    id: u64,
+    symbol: String,
+    direction: AlertDirection,
+    threshold: f64,
+    // One-shot alerts stay disarmed after firing; others re-arm once the
+    // price moves back across the threshold
+    once: bool,
+    armed: bool,
+    created_at: i64,
+    triggered_at: Option<i64>,
+}
+
+impl PriceAlert {
+    fn crossed(&self, price: f64) -> bool {
+        match self.direction {
+            AlertDirection::Above => price >= self.threshold,
+            AlertDirection::Below => price <= self.threshold,
         }
     }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AlertTriggered {
+    alert: PriceAlert,
+    price: f64,
+    timestamp: i64,
+}
+
+struct AlertState {
+    alerts: Mutex<Vec<PriceAlert>>,
+    next_id: AtomicU64,
+}
 
-    // For non-Solana (or Solana fallback): use preferred source if we have one
-    if pref == "gecko" {
-        if let Ok(result) = try_gecko(&client, &chain_id, &address, &pair_address).await {
-            return Ok(result);
+// Updates arming state for a fresh quote and returns the alerts that fired
+fn check_alerts(alerts: &mut [PriceAlert], quote: &StockQuote, now: i64) -> Vec<AlertTriggered> {
+    let mut fired = Vec::new();
+    for alert in alerts.iter_mut().filter(|a| a.symbol == quote.symbol) {
+        if !alert.crossed(quote.price) {
+            if !alert.once {
+                alert.armed = true;
+            }
+            continue;
         }
-    } else if pref == "dexscreener" {
-        if let Ok(result) = try_dexscreener(&client, &chain_id, &address, &pair_address).await {
-            return Ok(result);
+        if alert.armed {
+            alert.armed = false;
+            alert.triggered_at = Some(now);
+            fired.push(AlertTriggered {
+                alert: alert.clone(),
+                price: quote.price,
+                timestamp: now,
+            });
         }
     }
+    fired
+}
 
-    // Try remaining sources in order
-    if let Ok(result) = try_gecko(&client, &chain_id, &address, &pair_address).await {
-        return Ok(result);
+// Symbols that still have an alert able to fire (armed, or re-armable)
+fn watched_symbols(alerts: &[PriceAlert]) -> Vec<String> {
+    let mut symbols: Vec<String> = alerts.iter()
+        .filter(|a| a.armed || !a.once)
+        .map(|a| a.symbol.clone())
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+#[tauri::command]
+fn set_price_alert(
+    app: tauri::AppHandle,
+    ticker: tauri::State<'_, TickerState>,
+    alert_state: tauri::State<'_, AlertState>,
+    symbol: String,
+    direction: AlertDirection,
+    threshold: f64,
+    once: Option<bool>,
+) -> Result<PriceAlert, String> {
+    let symbol = required_symbol(&symbol)?;
+    if !threshold.is_finite() || threshold <= 0.0 {
+        return Err(format!("Invalid alert threshold: {}", threshold));
     }
-    try_dexscreener(&client, &chain_id, &address, &pair_address).await
+
+    let alert = PriceAlert {
+        id: alert_state.next_id.fetch_add(1, Ordering::Relaxed),
+        symbol,
+        direction,
+        threshold,
+        once: once.unwrap_or(true),
+        armed: true,
+        created_at: now_millis(),
+        triggered_at: None,
+    };
+    alert_state.alerts.lock_or_recover().push(alert.clone());
+    ensure_ticker(&app, &ticker);
+    Ok(alert)
 }
 
-// Separate command for 24h stats (called less frequently)
 #[tauri::command]
-async fn fetch_dex_stats(chain_id: String, address: String, pair_address: Option<String>) -> Result<DexPriceResult, String> {
-    let client = reqwest::Client::builder()
-        .no_proxy()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+fn list_alerts(alert_state: tauri::State<'_, AlertState>) -> Vec<PriceAlert> {
+    alert_state.alerts.lock_or_recover().clone()
+}
 
-    // Always use DexScreener for stats (24h change, volume)
-    if let Some(ref pa) = pair_address {
-        let url = format!(
-            "https://api.dexscreener.com/latest/dex/pairs/{}/{}",
-            chain_id, pa
-        );
-        if let Ok(response) = client.get(&url).send().await {
-            if let Ok(data) = response.json::<DexScreenerResponse>().await {
-                let pair = data.pairs.as_ref().and_then(|p| p.first()).or(data.pair.as_ref());
-                if let Some(pair) = pair {
-                    let price = pair.price_usd.as_ref()
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(0.0);
-                    return Ok(DexPriceResult {
-                        price,
-                        change_24h: pair.price_change.as_ref().and_then(|p| p.h24).unwrap_or(0.0),
-                        volume_24h: pair.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0),
-                        pair_address: pair.pair_address.clone().unwrap_or_default(),
-                        source: "dexscreener".to_string(),
-                    });
-                }
-            }
-        }
+#[tauri::command]
+fn clear_alert(alert_state: tauri::State<'_, AlertState>, id: u64) -> Result<(), String> {
+    let mut alerts = alert_state.alerts.lock_or_recover();
+    let before = alerts.len();
+    alerts.retain(|a| a.id != id);
+    if alerts.len() == before {
+        return Err(format!("No alert with id {}", id));
     }
+    Ok(())
+}
 
-    // Fall back to tokens endpoint
-    let url = format!("https://api.dexscreener.com/latest/dex/tokens/{}", address);
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
-    let data: DexScreenerResponse = response.json().await.map_err(|e| e.to_string())?;
-    let pairs = data.pairs.ok_or("No pairs found")?;
+// Resting stop, limit and bracket orders. The book is saved to orders.json in
+// the app data dir on every change and matched on each ticker poll. A fill
+// moves from the book to pending_fills in the same write and is emitted as
+// "order-filled"; it stays pending until the portfolio acknowledges it, so a
+// fill while no window is listening is applied on the next launch.
+struct OrderBook {
+    orders: Mutex<Vec<orders::RestingOrder>>,
+    pending_fills: Mutex<Vec<orders::OrderFill>>,
+    next_id: AtomicU64,
+}
 
-    let best = pairs.iter()
-        .filter(|p| p.chain_id.as_ref().map(|c| c.to_lowercase()) == Some(chain_id.to_lowercase()))
-        .max_by(|a, b| {
-            let la = a.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
-            let lb = b.liquidity.as_ref().and_then(|l| l.usd).unwrap_or(0.0);
-            la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedOrderBook {
+    orders: Vec<orders::RestingOrder>,
+    #[serde(default)]
+    pending_fills: Vec<orders::OrderFill>,
+}
+
+impl OrderBook {
+    // Saves `orders` (held locked by the caller) with the pending fills
+    fn save(&self, app: &tauri::AppHandle, orders: &[orders::RestingOrder]) -> Result<(), String> {
+        let saved = SavedOrderBook {
+            orders: orders.to_vec(),
+            pending_fills: self.pending_fills.lock_or_recover().clone(),
+        };
+        let json = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+        write_atomic(&order_book_path(app)?, &json)
+    }
+}
+
+fn order_book_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("orders.json"))
+}
+
+fn load_order_book(app: &tauri::AppHandle) -> SavedOrderBook {
+    let Ok(path) = order_book_path(app) else { return SavedOrderBook::default() };
+    let Ok(contents) = std::fs::read_to_string(&path) else { return SavedOrderBook::default() };
+    // Older versions saved just the array of resting orders
+    serde_json::from_str(&contents)
+        .or_else(|_| serde_json::from_str(&contents).map(|orders| SavedOrderBook { orders, pending_fills: Vec::new() }))
+        .unwrap_or_else(|e| {
+            log::warn!("[orders] Ignoring invalid {}: {}", path.display(), e);
+            SavedOrderBook::default()
         })
-        .or_else(|| pairs.first())
-        .ok_or("No pair found")?;
-
-    let price = best.price_usd.as_ref().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-    Ok(DexPriceResult {
-        price,
-        change_24h: best.price_change.as_ref().and_then(|p| p.h24).unwrap_or(0.0),
-        volume_24h: best.volume.as_ref().and_then(|v| v.h24).unwrap_or(0.0),
-        pair_address: best.pair_address.clone().unwrap_or_default(),
-        source: "dexscreener".to_string(),
-    })
+}
+
+fn validate_order_input(symbol: &str, quantity: f64) -> Result<String, String> {
+    let symbol = required_symbol(symbol)?;
+    if !(quantity.is_finite() && quantity > 0.0) {
+        return Err(format!("Invalid order quantity: {}", quantity));
+    }
+    Ok(symbol)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+fn place_order(
+    app: tauri::AppHandle,
+    ticker: tauri::State<'_, TickerState>,
+    order_book: tauri::State<'_, OrderBook>,
+    symbol: String,
+    side: positions::TradeSide,
+    quantity: f64,
+    kind: orders::OrderKind,
+    reduce_only: Option<bool>,
+) -> Result<orders::RestingOrder, String> {
+    let symbol = validate_order_input(&symbol, quantity)?;
+    kind.validate()?;
+
+    let order = orders::RestingOrder {
+        id: order_book.next_id.fetch_add(1, Ordering::Relaxed),
+        symbol,
+        side,
+        quantity,
+        kind,
+        oco_group: None,
+        triggered: false,
+        reduce_only: reduce_only.unwrap_or(false),
+        created_at: now_millis(),
+    };
+    {
+        let mut book = order_book.orders.lock_or_recover();
+        book.push(order.clone());
+        order_book.save(&app, &book)?;
+    }
+    ensure_ticker(&app, &ticker);
+    Ok(order)
+}
+
+// OCO exits for a position opened on `side`: a stop-market stop-loss and a
+// limit take-profit; whichever fills first cancels the other
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+fn place_bracket_order(
+    app: tauri::AppHandle,
+    ticker: tauri::State<'_, TickerState>,
+    order_book: tauri::State<'_, OrderBook>,
+    symbol: String,
+    side: positions::TradeSide,
+    quantity: f64,
+    stop_loss: f64,
+    take_profit: f64,
+) -> Result<Vec<orders::RestingOrder>, String> {
+    let symbol = validate_order_input(&symbol, quantity)?;
+    let (exit_side, stop, target) = orders::bracket_legs(side, stop_loss, take_profit)?;
+
+    let created_at = now_millis();
+    let stop_id = order_book.next_id.fetch_add(1, Ordering::Relaxed);
+    let target_id = order_book.next_id.fetch_add(1, Ordering::Relaxed);
+    let legs: Vec<orders::RestingOrder> = [(stop_id, stop), (target_id, target)]
+        .into_iter()
+        .map(|(id, kind)| orders::RestingOrder {
+            id,
+            symbol: symbol.clone(),
+            side: exit_side,
+            quantity,
+            kind,
+            oco_group: Some(stop_id),
+            triggered: false,
+            // Exits only: after a manual close they must not open the other side
+            reduce_only: true,
+            created_at,
+        })
+        .collect();
+    {
+        let mut book = order_book.orders.lock_or_recover();
+        book.extend(legs.iter().cloned());
+        order_book.save(&app, &book)?;
+    }
+    ensure_ticker(&app, &ticker);
+    Ok(legs)
+}
+
+#[tauri::command]
+fn list_orders(order_book: tauri::State<'_, OrderBook>) -> Vec<orders::RestingOrder> {
+    order_book.orders.lock_or_recover().clone()
+}
+
+#[tauri::command]
+fn cancel_order(app: tauri::AppHandle, order_book: tauri::State<'_, OrderBook>, id: u64) -> Result<(), String> {
+    let mut book = order_book.orders.lock_or_recover();
+    let before = book.len();
+    book.retain(|o| o.id != id);
+    if book.len() == before {
+        return Err(format!("No resting order with id {}", id));
+    }
+    order_book.save(&app, &book)
+}
+
+// Fills the portfolio hasn't acknowledged yet, oldest first; the frontend
+// applies these on startup in case they filled while it wasn't listening
+#[tauri::command]
+fn pending_order_fills(order_book: tauri::State<'_, OrderBook>) -> Vec<orders::OrderFill> {
+    order_book.pending_fills.lock_or_recover().clone()
+}
+
+// Called once the portfolio has booked the fill of resting order `id`
+#[tauri::command]
+fn ack_order_fill(app: tauri::AppHandle, order_book: tauri::State<'_, OrderBook>, id: u64) -> Result<(), String> {
+    let book = order_book.orders.lock_or_recover();
+    {
+        let mut pending = order_book.pending_fills.lock_or_recover();
+        let before = pending.len();
+        pending.retain(|fill| fill.order.id != id);
+        if pending.len() == before {
+            return Ok(());
+        }
+    }
+    order_book.save(&app, &book)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let breakers = std::sync::Arc::new(SourceBreakers::new());
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .manage(UpdateState {
             update_available: Mutex::new(None),
+            checked_at: Mutex::new(None),
+            pending_update: Mutex::new(None),
+            cancel_requested: AtomicBool::new(false),
+            restart_pending: AtomicBool::new(false),
+        })
+        .manage(InFlightRequests {
+            candles: InFlight::new(),
+            quotes: InFlight::new(),
+            supersede: SupersedeRequests::new(),
+            dex_prices: InFlight::new(),
+            dex_stats: InFlight::new(),
+        })
+        .manage(SolanaStreamState {
+            task: Mutex::new(None),
+        })
+        .manage(AlertState {
+            alerts: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        })
+        .manage(TradeJournal {
+            write_lock: Mutex::new(()),
+        })
+        .manage(WatchlistStore {
+            write_lock: Mutex::new(()),
+        })
+        .manage(ClockState {
+            clock: Mutex::new(SimClock::Real),
+        })
+        .manage(TickerState {
+            subscriptions: Mutex::new(Vec::new()),
+            task: Mutex::new(None),
+        })
+        .manage(DexStatsCache {
+            entries: Mutex::new(HashMap::new()),
+        })
+        .manage(TokenMetadataCache {
+            entries: Mutex::new(HashMap::new()),
+        })
+        .manage(PrefetchCache {
+            quotes: Mutex::new(HashMap::new()),
+            dex_prices: Mutex::new(HashMap::new()),
+        })
+        .manage(DataSourceState {
+            // TRADING_SIM_DATA_SOURCE=mock starts in offline mode
+            source: Mutex::new(
+                std::env::var("TRADING_SIM_DATA_SOURCE").ok()
+                    .and_then(|name| data_source_for(&name, &breakers).ok())
+                    .unwrap_or_else(|| std::sync::Arc::new(LiveDataSource { breakers: breakers.clone() })),
+            ),
+            breakers,
         })
         .invoke_handler(tauri::generate_handler![
             check_for_update,
+            get_pending_update,
             install_update,
+            cancel_update,
+            confirm_restart,
             get_current_version,
+            is_version_newer,
             get_changelog,
+            get_changelog_structured,
+            get_api_config,
+            set_api_config,
+            reset_api_config,
+            set_data_source,
+            get_data_source,
+            set_sandbox,
+            value_portfolio,
+            compute_pnl,
+            compute_position_size,
+            compute_break_even,
+            load_watchlists,
+            save_watchlist,
+            delete_watchlist,
+            get_storage_info,
+            clear_storage,
+            compute_performance_stats,
+            max_affordable,
+            supported_chains,
+            export_candles,
+            fetch_dex_prices,
+            compare_dex_sources,
+            prefetch,
+            fetch_dex_activity,
+            fetch_corporate_actions,
+            fetch_token_metadata,
+            fetch_daily_closes,
+            compute_correlation,
+            fetch_market_overview,
+            fetch_vwap,
+            fetch_52w_range,
+            source_diagnostics,
+            export_diagnostics,
+            fetch_dex_price_all_chains,
+            validate_symbol,
+            set_sim_clock,
+            get_sim_clock,
+            fetch_ratio_candles,
+            record_trade,
+            load_trades,
+            fetch_token_list,
+            set_price_alert,
+            list_alerts,
+            clear_alert,
+            subscribe_ticker,
+            unsubscribe_ticker,
+            ticker_status,
+            place_order,
+            place_bracket_order,
+            list_orders,
+            cancel_order,
+            pending_order_fills,
+            ack_order_fill,
             fetch_stock_candles,
             fetch_stock_quote,
+            fetch_stock_summary,
             fetch_dex_price,
-            fetch_dex_stats
+            fetch_dex_stats,
+            resolve_dex_pair,
+            check_sources,
+            start_solana_stream,
+            stop_solana_stream
         ])
         .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
+            // Release builds keep warnings and errors in the plugin's log file
+            app.handle().plugin(
+                tauri_plugin_log::Builder::default()
+                    .level(if cfg!(debug_assertions) { log::LevelFilter::Info } else { log::LevelFilter::Warn })
+                    .build(),
+            )?;
+
+            let api_config = load_api_config(app.handle());
+            app.manage(ApiConfigState {
+                config: Mutex::new(api_config),
+            });
+
+            // Resting orders survive restarts; resume matching them right away
+            let saved = load_order_book(app.handle());
+            let next_id = saved.orders.iter().map(|o| o.id)
+                .chain(saved.pending_fills.iter().map(|f| f.order.id))
+                .max()
+                .unwrap_or(0) + 1;
+            let has_resting = !saved.orders.is_empty();
+            app.manage(OrderBook {
+                orders: Mutex::new(saved.orders),
+                pending_fills: Mutex::new(saved.pending_fills),
+                next_id: AtomicU64::new(next_id),
+            });
+            if has_resting {
+                ensure_ticker(app.handle(), &app.state::<TickerState>());
             }
 
             // Set window icon (works in both dev and production)
@@ -891,3 +6847,720 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn sandbox_prices(source: &SandboxDataSource, symbol: &str, count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|_| block_on(source.stock_quote(ApiConfig::default(), symbol.to_string(), false, 0)).unwrap().price)
+            .collect()
+    }
+
+    #[test]
+    fn sandbox_replays_the_same_walk_for_a_seed() {
+        let config = SandboxConfig { seed: 7, ..SANDBOX_DEFAULTS };
+        let a = sandbox_prices(&SandboxDataSource::new(config).unwrap(), "AAPL", 5);
+        let b = sandbox_prices(&SandboxDataSource::new(config).unwrap(), "AAPL", 5);
+        assert_eq!(a, b);
+        let other = sandbox_prices(&SandboxDataSource::new(SandboxConfig { seed: 8, ..config }).unwrap(), "AAPL", 5);
+        assert_ne!(a, other);
+    }
+
+    #[test]
+    fn sandbox_steps_only_when_polled() {
+        let source = SandboxDataSource::new(SANDBOX_DEFAULTS).unwrap();
+        let dropped = source.stock_quote(ApiConfig::default(), "AAPL".to_string(), false, 0);
+        drop(dropped);
+        let fresh = SandboxDataSource::new(SANDBOX_DEFAULTS).unwrap();
+        assert_eq!(sandbox_prices(&source, "AAPL", 3), sandbox_prices(&fresh, "AAPL", 3));
+    }
+
+    fn candle_request(symbol: &str, interval: &str, range: &str) -> CandleRequest {
+        CandleRequest {
+            symbol: symbol.to_string(),
+            interval: interval.to_string(),
+            range: range.to_string(),
+            adjusted: false,
+            fill_gaps: false,
+            include_extended: false,
+            bypass_cache: false,
+        }
+    }
+
+    #[test]
+    fn mock_source_is_deterministic_per_symbol() {
+        let mock = MockDataSource;
+        let prices = |symbol: &str| -> Vec<f64> {
+            let chart = block_on(mock.stock_candles(ApiConfig::default(), candle_request(symbol, "1d", "1mo"))).unwrap();
+            chart.candles.iter().map(|c| c.close).collect()
+        };
+        assert_eq!(prices("AAPL"), prices("AAPL"));
+        assert_ne!(prices("AAPL"), prices("MSFT"));
+
+        let quote = |symbol: &str| block_on(mock.stock_quote(ApiConfig::default(), symbol.to_string(), false, 0)).unwrap().price;
+        assert_eq!(quote("AAPL"), quote("AAPL"));
+    }
+
+    #[test]
+    fn mock_candles_are_well_formed() {
+        let candles = mock_candles("AAPL", "1h", "5d");
+        assert_eq!(candles.len(), 5 * 24);
+        assert!(candles.windows(2).all(|w| w[1].time - w[0].time == 3_600_000));
+        assert!(candles.iter().all(|c| c.low <= c.open.min(c.close) && c.high >= c.open.max(c.close)));
+        assert_eq!(mock_candles("AAPL", "1m", "max").len() as i64, MOCK_MAX_CANDLES);
+    }
+
+    fn solana_request(address: &str) -> DexPriceRequest {
+        DexPriceRequest {
+            chain_id: "solana".to_string(),
+            address: address.to_string(),
+            pair_address: None,
+            preferred_source: None,
+            coingecko_id: None,
+            source_order: None,
+            dex_id: None,
+            race: false,
+        }
+    }
+
+    #[test]
+    fn jupiter_batch_missing_mint_falls_through_without_jupiter() {
+        // Asked for three mints: one priced, one omitted, one malformed
+        let body = r#"{
+            "PRICED": { "usdPrice": 1.5, "priceChange24h": 2.0 },
+            "BROKEN": { "usdPrice": "not a number" }
+        }"#;
+        let jupiter = parse_jupiter_prices(serde_json::from_str(body).unwrap());
+        assert_eq!(jupiter.len(), 1);
+        let asked: std::collections::HashSet<String> =
+            ["PRICED", "OMITTED", "BROKEN"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(jupiter_batch_outcome(&jupiter, &asked, &solana_request("PRICED")).unwrap(), (1.5, Some(2.0)));
+        for mint in ["OMITTED", "BROKEN"] {
+            let settled = jupiter_batch_outcome(&jupiter, &asked, &solana_request(mint)).unwrap_err();
+            assert_eq!(settled.len(), 1);
+            assert_eq!(settled[0].source, "jupiter");
+        }
+    }
+
+    #[test]
+    fn jupiter_batch_failure_leaves_jupiter_in_the_fallback() {
+        // A mint whose batch failed outright was never answered
+        let jupiter = HashMap::new();
+        let asked = std::collections::HashSet::new();
+        assert!(jupiter_batch_outcome(&jupiter, &asked, &solana_request("MINT")).unwrap_err().is_empty());
+    }
+
+    const CHART_FIXTURE: &str = r#"{"chart":{"result":[{
+        "meta":{"symbol":"AAPL","regularMarketPrice":190.5,"previousClose":188.0},
+        "timestamp":[1700000000,1700000060],
+        "indicators":{"quote":[{"open":[189.0,190.0],"high":[190.0,191.0],"low":[188.5,189.5],"close":[190.0,190.5],"volume":[100,200]}]}
+    }],"error":null}}"#;
+
+    // Answers one request on a local socket with `head` (status line and
+    // headers) and `body`; the server task returns the request it read
+    fn serve_once(head: &str, body: Vec<u8>) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/v8/finance/chart/AAPL", listener.local_addr().unwrap());
+        let head = format!("{}\r\nContent-Length: {}\r\n\r\n", head, body.len());
+        let server = tokio::spawn(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let n = socket.read(&mut request).await.unwrap();
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+            String::from_utf8_lossy(&request[..n]).to_lowercase()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn gzip_chart_response_round_trips() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(CHART_FIXTURE.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (request, data) = runtime.block_on(async {
+            let (url, server) = serve_once("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip", gzipped);
+            let response = http_client(&ApiConfig::default(), Duration::from_secs(5))
+                .unwrap()
+                .get(&url)
+                .header("Accept-Encoding", "gzip, deflate, br")
+                .send()
+                .await
+                .unwrap();
+            let data: YahooChartResponse = read_json("Yahoo chart", response).await.unwrap();
+            (server.await.unwrap(), data)
+        });
+
+        assert!(request.contains("accept-encoding: gzip, deflate, br"));
+        let results = data.chart.result.unwrap();
+        let chart = chart_to_response(&results[0], false, false, false, 1_700_000_100).unwrap();
+        assert_eq!(chart.candles.len(), 2);
+        assert_eq!(chart.current_price, 190.5);
+        assert_eq!(chart.candles[1].volume, 200);
+    }
+
+    #[test]
+    fn yahoo_429_is_a_typed_rate_limit() {
+        let breakers = SourceBreakers::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let error = runtime.block_on(async {
+            let (url, _server) = serve_once("HTTP/1.1 429 Too Many Requests\r\nContent-Type: text/html\r\nRetry-After: 30", b"<html>Too Many Requests</html>".to_vec());
+            let response = http_client(&ApiConfig::default(), Duration::from_secs(5)).unwrap().get(&url).send().await.unwrap();
+            rate_limit_error(&breakers, "query1.finance.yahoo.com", &response)
+        });
+
+        match error {
+            Some(TradingError::RateLimited { message, retry_after }) => {
+                assert_eq!(message, "Rate limited by query1.finance.yahoo.com");
+                assert_eq!(retry_after.as_deref(), Some("30"));
+            }
+            other => panic!("expected a rate limit, got {:?}", other),
+        }
+        // The Retry-After holds further Yahoo calls back
+        assert!(matches!(yahoo_backoff(&breakers), Err(TradingError::RateLimited { .. })));
+    }
+
+    #[test]
+    fn jupiter_retry_after_holds_the_next_poll_back() {
+        let breakers = SourceBreakers::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (first, second) = runtime.block_on(async {
+            let (url, _server) = serve_once("HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30", Vec::new());
+            let api = ApiConfig { jupiter_base_url: url.replace("/v8/finance/chart/AAPL", ""), ..ApiConfig::default() };
+            let client = http_client(&api, Duration::from_secs(5)).unwrap();
+            let mints = ["MintA".to_string()];
+            let first = fetch_jupiter_prices(&client, &api, &breakers, &mints).await.unwrap_err();
+            // Would fail to connect to the spent one-shot server if it went out
+            let second = fetch_jupiter_prices(&client, &api, &breakers, &mints).await.unwrap_err();
+            (first, second)
+        });
+        assert_eq!(first, "Jupiter status 429 Too Many Requests");
+        assert!(second.starts_with("jupiter skipped, rate limited for another "), "{}", second);
+        assert!(!is_source_outage(&second));
+    }
+
+    #[test]
+    fn dexscreener_5xx_counts_as_an_outage() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let error = runtime.block_on(async {
+            let (url, _server) = serve_once("HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/html", b"<html>Down</html>".to_vec());
+            let api = ApiConfig { dexscreener_base_url: url.replace("/v8/finance/chart/AAPL", ""), ..ApiConfig::default() };
+            let breakers = std::sync::Arc::new(SourceBreakers::new());
+            let lookup = dex_source_lookup(&api, &breakers, &solana_request("MintA")).unwrap();
+            lookup("dexscreener").unwrap().await.unwrap_err()
+        });
+        assert_eq!(error, "DexScreener status 503 Service Unavailable");
+        assert!(is_source_outage(&error));
+    }
+
+    #[test]
+    fn delisted_symbol_is_not_found() {
+        let body = r#"{"chart":{"result":null,"error":{"code":"Not Found","description":"No data found, symbol may be delisted"}}}"#;
+        match yahoo_error_from_body(body).and_then(|e| e.not_found()) {
+            Some(TradingError::NotFound { message }) => assert_eq!(message, "No data found, symbol may be delisted"),
+            other => panic!("expected not found, got {:?}", other),
+        }
+        let outage = r#"{"chart":{"result":null,"error":{"code":"Internal Server Error","description":"Try later"}}}"#;
+        assert!(yahoo_error_from_body(outage).unwrap().not_found().is_none());
+        assert!(yahoo_error_from_body(CHART_FIXTURE).is_none());
+    }
+
+    #[test]
+    fn non_object_error_envelopes_still_parse() {
+        let text = yahoo_error_from_body(r#"{"chart":{"result":null,"error":"Not Found"}}"#).unwrap();
+        assert!(matches!(text.not_found(), Some(TradingError::NotFound { message }) if message == "Not Found"));
+        assert_eq!(text.to_string(), "Not Found");
+        let number = yahoo_error_from_body(r#"{"chart":{"result":null,"error":500}}"#).unwrap();
+        assert_eq!(number.to_string(), "500");
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_dates() {
+        let now = 1_445_412_000_000; // Wed, 21 Oct 2015 07:20:00 GMT
+        assert_eq!(parse_retry_after("120", now), Some(now + 120_000));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now), Some(now + 480_000));
+        assert_eq!(parse_retry_after("999999", now), Some(now + MAX_RETRY_AFTER_SECS * 1000));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn non_finite_prices_are_rejected() {
+        for raw in ["inf", "-inf", "NaN", "infinity", "0", "-1.5", "", "abc"] {
+            assert!(parse_price("test", raw).is_err(), "{} should be rejected", raw);
+        }
+        assert_eq!(parse_price("test", " 1.25 "), Ok(1.25));
+        assert!(checked_price("test", f64::NAN).is_err());
+        assert!(checked_price("test", f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn future_bars_are_skipped_for_the_last_close() {
+        let now = 1_700_000_000;
+        // A placeholder 2 minutes ahead carries a close; the bar before it has none
+        let timestamps = [now - 120, now - 60, now, now + 120];
+        let closes = [Some(10.0), Some(11.0), None, Some(99.0)];
+        assert_eq!(last_valid_bar(&timestamps, &closes, now), Some((now - 60, 11.0)));
+        // Within the tolerance a slightly-ahead bar still counts
+        assert_eq!(last_valid_bar(&[now - 60, now + 30], &[Some(11.0), Some(12.0)], now), Some((now + 30, 12.0)));
+        assert_eq!(last_valid_bar(&[now + 300], &[Some(12.0)], now), None);
+    }
+
+    fn meta(value: serde_json::Value) -> YahooChartMeta {
+        serde_json::from_value(value).unwrap()
+    }
+
+    // Epoch seconds for a New York wall-clock time in January (EST, UTC-5)
+    fn new_york_winter(day: i64, hour: i64, minute: i64) -> i64 {
+        days_from_civil(2024, 1, day) * 86_400 + (hour + 5) * 3_600 + minute * 60
+    }
+
+    #[test]
+    fn asset_class_follows_the_symbol_suffix() {
+        assert_eq!(asset_class("ES=F"), "future");
+        assert_eq!(asset_class("gc=f"), "future");
+        assert_eq!(asset_class("EURUSD=X"), "forex");
+        assert_eq!(asset_class("AAPL"), "equity");
+        assert_eq!(asset_class("VOD.L"), "equity");
+        assert_eq!(asset_class("^GSPC"), "equity");
+    }
+
+    #[test]
+    fn futures_and_forex_trade_around_the_clock() {
+        // Tuesday 9 Jan 2024, then Saturday 13th and Sunday 14th
+        let tuesday_night = new_york_winter(9, 23, 0);
+        let halt = new_york_winter(9, 17, 30);
+        let saturday = new_york_winter(13, 12, 0);
+        let sunday_evening = new_york_winter(14, 17, 30);
+        let friday_evening = new_york_winter(12, 17, 30);
+
+        let futures = meta(serde_json::json!({ "symbol": "ES=F" }));
+        assert_eq!(market_status(&futures, tuesday_night, None), MarketStatus::Regular);
+        assert_eq!(market_status(&futures, halt, None), MarketStatus::Closed);
+        assert_eq!(market_status(&futures, saturday, None), MarketStatus::Closed);
+        assert_eq!(market_status(&futures, sunday_evening, None), MarketStatus::Closed);
+        assert_eq!(market_status(&futures, new_york_winter(14, 18, 0), None), MarketStatus::Regular);
+
+        let forex = meta(serde_json::json!({ "symbol": "EURUSD=X" }));
+        assert_eq!(market_status(&forex, tuesday_night, None), MarketStatus::Regular);
+        assert_eq!(market_status(&forex, halt, None), MarketStatus::Regular);
+        assert_eq!(market_status(&forex, saturday, None), MarketStatus::Closed);
+        assert_eq!(market_status(&forex, sunday_evening, None), MarketStatus::Regular);
+        assert_eq!(market_status(&forex, friday_evening, None), MarketStatus::Closed);
+    }
+
+    #[test]
+    fn daily_closes_key_by_local_date() {
+        // Tokyo opens at 00:00 UTC, Sydney at 23:00 UTC the evening before
+        let tokyo = [candle(10 * 86_400_000, 1.0, 1.0, 10.0, 0)];
+        let sydney = [candle(10 * 86_400_000 - 3_600_000, 1.0, 1.0, 20.0, 0)];
+        let tokyo = closes_by_date(&tokyo, 9 * 3_600);
+        let sydney = closes_by_date(&sydney, 10 * 3_600);
+        assert_eq!(tokyo.keys().collect::<Vec<_>>(), sydney.keys().collect::<Vec<_>>());
+        assert_eq!(tokyo.get(&(10 * 86_400_000)), Some(&10.0));
+        // West of UTC the date matches the UTC one
+        let new_york = closes_by_date(&[candle(new_york_winter(9, 9, 30) * 1000, 1.0, 1.0, 1.0, 0)], NEW_YORK_WINTER_OFFSET);
+        assert_eq!(new_york.keys().next(), Some(&(days_from_civil(2024, 1, 9) * 86_400_000)));
+    }
+
+    #[test]
+    fn gaps_follow_the_page() {
+        const DAY_MS: i64 = 86_400_000;
+        // Bars on days 0, 1, 3, 4 and 6, opening 14:30 UTC; days 2 and 5 are gaps
+        let candles: Vec<StockCandle> = [0, 1, 3, 4, 6].iter()
+            .map(|&day| candle(day * DAY_MS + 52_200_000, 1.0, 1.0, 1.0, 0))
+            .collect();
+        let gaps = [2 * DAY_MS, 5 * DAY_MS];
+        assert_eq!(page_gaps(&gaps, &candles, &(0..2), 0), vec![2 * DAY_MS]);
+        assert_eq!(page_gaps(&gaps, &candles, &(2..5), 0), vec![5 * DAY_MS]);
+        assert_eq!(page_gaps(&gaps, &candles, &(3..4), 0), vec![5 * DAY_MS]);
+        assert!(page_gaps(&gaps, &candles, &(2..3), 0).is_empty());
+        assert!(page_gaps(&gaps, &candles, &(5..5), 0).is_empty());
+    }
+
+    #[test]
+    fn new_york_switches_on_the_sundays() {
+        // 2024: DST from Sunday 10 March 07:00 UTC to Sunday 3 November 06:00 UTC
+        let dst_start = days_from_civil(2024, 3, 10) * 86_400 + 7 * 3_600;
+        let dst_end = days_from_civil(2024, 11, 3) * 86_400 + 6 * 3_600;
+        assert_eq!(new_york_offset_secs(dst_start - 1), -5 * 3_600);
+        assert_eq!(new_york_offset_secs(dst_start), -4 * 3_600);
+        assert_eq!(new_york_offset_secs(dst_end - 1), -4 * 3_600);
+        assert_eq!(new_york_offset_secs(dst_end), -5 * 3_600);
+        assert!(is_local_weekend(new_york_winter(13, 12, 0), NEW_YORK_WINTER_OFFSET));
+        assert!(!is_local_weekend(new_york_winter(12, 12, 0), NEW_YORK_WINTER_OFFSET));
+    }
+
+    fn candle(time: i64, high: f64, low: f64, close: f64, volume: i64) -> StockCandle {
+        StockCandle { time, open: close, high, low, close, volume, filled: Vec::new(), session: None, time_iso: None }
+    }
+
+    const NEW_YORK_WINTER_OFFSET: i64 = -5 * 3_600;
+
+    #[test]
+    fn weekly_resample_starts_on_the_local_monday() {
+        // Sunday 20:00 in New York is already Monday in UTC
+        let bars = [
+            candle(new_york_winter(5, 9, 30) * 1000, 11.0, 9.0, 10.0, 100),
+            candle(new_york_winter(7, 20, 0) * 1000, 12.0, 10.0, 11.0, 50),
+            candle(new_york_winter(8, 9, 30) * 1000, 13.0, 11.0, 12.0, 70),
+        ];
+        let weeks = resample_candles(&bars, Bucket::for_target("1wk", 7 * 86_400), NEW_YORK_WINTER_OFFSET);
+        let found: Vec<(i64, f64, i64)> = weeks.iter().map(|c| (c.time, c.high, c.volume)).collect();
+        assert_eq!(found, vec![
+            (new_york_winter(1, 0, 0) * 1000, 12.0, 150),
+            (new_york_winter(8, 0, 0) * 1000, 13.0, 70),
+        ]);
+    }
+
+    #[test]
+    fn monthly_resample_follows_calendar_months() {
+        let day = |month, day| (days_from_civil(2024, month, day) * 86_400 + 14 * 3_600) * 1000;
+        let bars = [
+            candle(day(1, 2), 10.0, 9.0, 9.5, 1),
+            candle(day(1, 31), 11.0, 9.0, 10.5, 1),
+            candle(day(2, 1), 12.0, 10.0, 11.5, 1),
+            candle(day(2, 29), 13.0, 11.0, 12.5, 1),
+            candle(day(3, 1), 14.0, 12.0, 13.5, 1),
+        ];
+        let months = resample_candles(&bars, Bucket::for_target("1mo", 30 * 86_400), NEW_YORK_WINTER_OFFSET);
+        let midnight = |month| (days_from_civil(2024, month, 1) * 86_400 + 5 * 3_600) * 1000;
+        let found: Vec<(i64, f64)> = months.iter().map(|c| (c.time, c.close)).collect();
+        assert_eq!(found, vec![(midnight(1), 10.5), (midnight(2), 12.5), (midnight(3), 13.5)]);
+
+        let quarters = resample_candles(&bars, Bucket::for_target("3mo", 90 * 86_400), NEW_YORK_WINTER_OFFSET);
+        assert_eq!(quarters.len(), 1);
+        assert_eq!(Bucket::Months(1).span(day(2, 10), NEW_YORK_WINTER_OFFSET), (midnight(2), midnight(3)));
+    }
+
+    #[test]
+    fn intraday_resample_stays_epoch_aligned() {
+        let bars = [
+            candle(new_york_winter(2, 9, 30) * 1000, 10.0, 9.0, 9.5, 1),
+            candle(new_york_winter(2, 10, 55) * 1000, 11.0, 9.0, 10.5, 1),
+            candle(new_york_winter(2, 11, 0) * 1000, 12.0, 10.0, 11.5, 1),
+        ];
+        assert_eq!(Bucket::for_target("1h", 3_600), Bucket::Secs(3_600));
+        let hours: Vec<i64> = resample_candles(&bars, Bucket::Secs(3_600), NEW_YORK_WINTER_OFFSET).iter().map(|c| c.time).collect();
+        assert_eq!(hours, vec![
+            new_york_winter(2, 9, 0) * 1000,
+            new_york_winter(2, 10, 0) * 1000,
+            new_york_winter(2, 11, 0) * 1000,
+        ]);
+    }
+
+    #[test]
+    fn vwap_weights_typical_prices_by_volume() {
+        // Typical prices 10 and 20 with volumes 100 and 300: (1000 + 6000) / 400
+        let candles = [
+            candle(0, 11.0, 9.0, 10.0, 100),
+            candle(60, 21.0, 19.0, 20.0, 300),
+            candle(120, 50.0, 40.0, 45.0, 0),
+        ];
+        assert_eq!(vwap(&candles), Some(17.5));
+        assert_eq!(vwap(&[candle(0, 11.0, 9.0, 10.0, 0)]), None);
+        assert_eq!(vwap(&[]), None);
+    }
+
+    #[test]
+    fn percent_change_is_unknown_without_a_positive_base() {
+        assert_eq!(percent_change(100.0, 110.0), Some(10.0));
+        assert_eq!(percent_change(0.0, 110.0), None);
+        assert_eq!(percent_change(-5.0, 110.0), None);
+        assert_eq!(percent_change(f64::NAN, 110.0), None);
+        assert_eq!(percent_change(100.0, f64::INFINITY), None);
+    }
+
+    #[test]
+    fn change_from_percent_guards_a_total_loss() {
+        // Up 25% to 125 means 100 a day ago
+        assert_eq!(change_from_percent(125.0, 25.0), Some(25.0));
+        assert_eq!(change_from_percent(50.0, -50.0), Some(-50.0));
+        assert_eq!(change_from_percent(1.0, -100.0), None);
+        assert_eq!(change_from_percent(1.0, -150.0), None);
+    }
+
+    #[test]
+    fn best_pair_reads_a_lone_pair() {
+        let single: DexScreenerResponse = serde_json::from_str(r#"{
+            "pairs": null,
+            "pair": { "chainId": "solana", "pairAddress": "ONLY", "priceUsd": "1.5", "liquidity": { "usd": 5000 } }
+        }"#).unwrap();
+        assert_eq!(best_pair(&single, "solana").and_then(|p| p.pair_address.as_deref()), Some("ONLY"));
+        // Off-chain pairs are still the fallback
+        assert_eq!(best_pair(&single, "ethereum").and_then(|p| p.pair_address.as_deref()), Some("ONLY"));
+    }
+
+    #[test]
+    fn pair_results_keep_the_source_price_text() {
+        let response: DexScreenerResponse = serde_json::from_str(r#"{ "pairs": [
+            { "chainId": "solana", "pairAddress": "A", "priceUsd": "0.00000012340", "quoteToken": { "symbol": "SOL" } },
+            { "chainId": "solana", "pairAddress": "B", "priceNative": "2" }
+        ] }"#).unwrap();
+        let pairs: Vec<&DexPair> = response.all_pairs().collect();
+        let quoted = DexPriceResult::from_pair(pairs[0], 0.0000001234, 0.0);
+        assert_eq!(quoted.price_text, "0.00000012340");
+        assert_eq!((quoted.source.as_str(), quoted.pair_address.as_str()), ("dexscreener", "A"));
+        assert_eq!(quoted.quote_symbol.as_deref(), Some("SOL"));
+        // Converted from priceNative, so there is no source text
+        assert_eq!(DexPriceResult::from_pair(pairs[1], 300.5, 0.0).price_text, "300.5");
+    }
+
+    #[test]
+    fn best_pair_picks_the_most_liquid_on_chain() {
+        let response: DexScreenerResponse = serde_json::from_str(r#"{ "pairs": [
+            { "chainId": "ethereum", "pairAddress": "ETH", "liquidity": { "usd": 9000000 } },
+            { "chainId": "Solana", "pairAddress": "THIN", "liquidity": { "usd": 100 } },
+            { "chainId": "solana", "pairAddress": "DEEP", "liquidity": { "usd": 50000 } },
+            { "chainId": "solana", "pairAddress": "UNKNOWN" }
+        ] }"#).unwrap();
+        assert_eq!(best_pair(&response, "solana").and_then(|p| p.pair_address.as_deref()), Some("DEEP"));
+        let empty: DexScreenerResponse = serde_json::from_str(r#"{ "pairs": [] }"#).unwrap();
+        assert!(best_pair(&empty, "solana").is_none());
+    }
+
+    #[test]
+    fn ratio_candles_align_on_shared_times() {
+        let ohlc = |time, open, high, low, close| StockCandle { time, open, high, low, close, ..candle(time, 0.0, 0.0, 0.0, 0) };
+        let a = [ohlc(1, 10.0, 12.0, 9.0, 11.0), ohlc(2, 11.0, 11.0, 11.0, 11.0), ohlc(3, 20.0, 24.0, 18.0, 22.0)];
+        let b = [ohlc(1, 5.0, 6.0, 4.0, 5.5), ohlc(3, 10.0, 12.0, 8.0, 11.0), ohlc(4, 1.0, 1.0, 1.0, 1.0)];
+        let ratio = ratio_candles(&a, &b);
+
+        // Time 2 and 4 are in only one series
+        assert_eq!(ratio.iter().map(|r| r.time).collect::<Vec<_>>(), vec![1, 3]);
+        let first = &ratio[0];
+        assert_eq!((first.open, first.high, first.low, first.close), (2.0, 3.0, 1.5, 2.0));
+        let last = &ratio[1];
+        assert_eq!((last.open, last.high, last.low, last.close), (2.0, 3.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn ratio_candles_skip_non_positive_denominators() {
+        let zero = StockCandle { low: 0.0, ..candle(1, 1.0, 1.0, 1.0, 0) };
+        assert!(ratio_candles(&[candle(1, 1.0, 1.0, 1.0, 0)], &[zero]).is_empty());
+    }
+
+    // Pre 1000-2000, regular 2000-3000, post 3000-4000; closed at 95, regular 100
+    fn session_chart(extended: serde_json::Value) -> YahooChartData {
+        let mut meta = serde_json::json!({
+            "symbol": "AAPL",
+            "regularMarketPrice": 100.0,
+            "previousClose": 95.0,
+            "currentTradingPeriod": {
+                "pre": { "start": 1000, "end": 2000 },
+                "regular": { "start": 2000, "end": 3000 },
+                "post": { "start": 3000, "end": 4000 }
+            }
+        });
+        meta.as_object_mut().unwrap().extend(extended.as_object().unwrap().clone());
+        serde_json::from_value(serde_json::json!({ "meta": meta, "indicators": { "quote": [] } })).unwrap()
+    }
+
+    #[test]
+    fn pre_market_change_is_kept_apart_from_the_day_change() {
+        let chart = session_chart(serde_json::json!({ "preMarketPrice": 102.0, "preMarketChange": 2.0 }));
+        let quote = chart_to_quote(&chart, "AAPL", 1500, &QuoteRefresh::default());
+        assert_eq!(quote.market_status, MarketStatus::Pre);
+        assert_eq!(quote.price, 102.0);
+        assert_eq!(quote.regular_price, 100.0);
+        assert_eq!(quote.change, 5.0);
+        assert_eq!(quote.session_change, Some(2.0));
+        assert_eq!(quote.session_change_percent, Some(2.0));
+        // Marked at the pre-market price, 7 above the previous close
+        let mark = stock_mark(&quote);
+        assert_eq!((mark.price, mark.change), (102.0, Some(7.0)));
+    }
+
+    #[test]
+    fn post_market_change_falls_back_to_the_price_difference() {
+        let chart = session_chart(serde_json::json!({ "postMarketPrice": 99.0 }));
+        let quote = chart_to_quote(&chart, "AAPL", 3500, &QuoteRefresh::default());
+        assert_eq!(quote.market_status, MarketStatus::Post);
+        assert_eq!(quote.price, 99.0);
+        assert_eq!(quote.change, 5.0);
+        assert_eq!(quote.session_change, Some(-1.0));
+        assert_eq!(quote.session_change_percent, Some(-1.0));
+        assert_eq!(stock_mark(&quote).change, Some(4.0));
+    }
+
+    #[test]
+    fn regular_session_has_no_session_change() {
+        let chart = session_chart(serde_json::json!({ "preMarketPrice": 102.0 }));
+        let quote = chart_to_quote(&chart, "AAPL", 2500, &QuoteRefresh::default());
+        assert_eq!(quote.market_status, MarketStatus::Regular);
+        assert_eq!(quote.price, 100.0);
+        assert_eq!((quote.session_change, quote.extended_price), (None, None));
+        assert_eq!(stock_mark(&quote).change, Some(5.0));
+    }
+
+    #[test]
+    fn symbols_are_trimmed_and_uppercased() {
+        assert_eq!(normalize_symbol(" aapl "), "AAPL");
+        assert_eq!(normalize_symbol("\tMsft\n"), "MSFT");
+        assert_eq!(normalize_symbol("Aapl"), "AAPL");
+        // Suffixes and index prefixes survive, uppercased the way Yahoo lists them
+        assert_eq!(normalize_symbol("eurusd=x"), "EURUSD=X");
+        assert_eq!(normalize_symbol(" vod.l"), "VOD.L");
+        assert_eq!(normalize_symbol("es=F"), "ES=F");
+        assert_eq!(normalize_symbol("^gspc"), "^GSPC");
+        assert_eq!(normalize_symbol("BRK-B"), "BRK-B");
+    }
+
+    #[test]
+    fn blank_symbols_are_rejected() {
+        assert_eq!(required_symbol(" aapl ").unwrap(), "AAPL");
+        assert!(required_symbol("").is_err());
+        assert!(matches!(required_stock_symbol(" \t"), Err(TradingError::InvalidSymbol { .. })));
+    }
+
+    #[test]
+    fn day_range_falls_back_to_regular_bars() {
+        // Null meta high/low; the pre-market bar at 1500 spikes and must not count
+        let mut chart = session_chart(serde_json::json!({ "regularMarketDayHigh": null, "regularMarketDayLow": 0.0 }));
+        chart.timestamp = Some(vec![1500, 2000, 2060, 2120]);
+        chart.indicators = serde_json::from_value(serde_json::json!({ "quote": [{
+            "high": [150.0, 101.0, 103.0, null],
+            "low": [50.0, 99.0, 98.5, 97.0]
+        }] })).unwrap();
+        assert_eq!(day_range(&chart), (103.0, 98.5));
+
+        // A valid meta side is kept as-is
+        chart.meta.regular_market_day_high = Some(104.0);
+        assert_eq!(day_range(&chart), (104.0, 98.5));
+    }
+
+    #[test]
+    fn day_range_skips_bars_without_trading_periods() {
+        let mut chart = session_chart(serde_json::json!({}));
+        chart.meta.current_trading_period = None;
+        chart.timestamp = Some(vec![1500, 2000]);
+        chart.indicators = serde_json::from_value(serde_json::json!({ "quote": [{ "high": [150.0, 101.0], "low": [50.0, 99.0] }] })).unwrap();
+        assert_eq!(day_range(&chart), (0.0, 0.0));
+    }
+
+    #[test]
+    fn market_status_covers_every_session() {
+        let chart = session_chart(serde_json::json!({}));
+        let status = |now| market_status(&chart.meta, now, None);
+        assert_eq!(status(999), MarketStatus::Closed);
+        assert_eq!(status(1000), MarketStatus::Pre);
+        assert_eq!(status(1999), MarketStatus::Pre);
+        assert_eq!(status(2000), MarketStatus::Regular);
+        assert_eq!(status(2999), MarketStatus::Regular);
+        assert_eq!(status(3000), MarketStatus::Post);
+        assert_eq!(status(3999), MarketStatus::Post);
+        assert_eq!(status(4000), MarketStatus::Closed);
+        // The last bar doesn't override windows Yahoo reported
+        assert_eq!(market_status(&chart.meta, 2500, Some(0)), MarketStatus::Regular);
+    }
+
+    #[test]
+    fn market_status_without_windows_uses_the_last_bar() {
+        let tuesday_noon = new_york_winter(9, 12, 0);
+        let saturday_noon = new_york_winter(13, 12, 0);
+        let plain = meta(serde_json::json!({ "symbol": "AAPL", "gmtoffset": -18_000 }));
+        assert_eq!(market_status(&plain, tuesday_noon, None), MarketStatus::Regular);
+        assert_eq!(market_status(&plain, tuesday_noon, Some(tuesday_noon - 120)), MarketStatus::Regular);
+        assert_eq!(market_status(&plain, tuesday_noon, Some(tuesday_noon - LIVE_BAR_GAP_SECS - 1)), MarketStatus::Closed);
+        assert_eq!(market_status(&plain, saturday_noon, Some(saturday_noon - 60)), MarketStatus::Closed);
+        // No offset to find the local weekend with
+        let bare = meta(serde_json::json!({ "symbol": "AAPL" }));
+        assert_eq!(market_status(&bare, saturday_noon, None), MarketStatus::Regular);
+    }
+
+    #[test]
+    fn market_status_reads_past_days_from_trading_periods() {
+        let period = |start, end| serde_json::json!([[{ "start": start, "end": end }], [{ "start": start + 86_400, "end": end + 86_400 }]]);
+        let chart = session_chart(serde_json::json!({
+            "currentTradingPeriod": {
+                "pre": { "start": 87_400, "end": 88_400 },
+                "regular": { "start": 88_400, "end": 89_400 },
+                "post": { "start": 89_400, "end": 90_400 }
+            },
+            "tradingPeriods": { "pre": period(1000, 2000), "regular": period(2000, 3000), "post": period(3000, 4000) }
+        }));
+        let status = |now| market_status(&chart.meta, now, None);
+        assert_eq!(status(1500), MarketStatus::Pre);
+        assert_eq!(status(2500), MarketStatus::Regular);
+        assert_eq!(status(3500), MarketStatus::Post);
+        assert_eq!(status(50_000), MarketStatus::Closed);
+        assert_eq!(status(88_500), MarketStatus::Regular);
+    }
+
+    #[test]
+    fn past_quotes_fetch_the_days_around_them() {
+        assert_eq!(quote_window(1_000_000, 1_000_000), "range=1d");
+        assert_eq!(quote_window(1_000_000 - 60, 1_000_000), "range=1d");
+        assert_eq!(quote_window(500_000, 1_000_000), "period1=413600&period2=586400");
+    }
+
+    #[test]
+    fn market_status_serializes_lowercase() {
+        for (status, text) in [
+            (MarketStatus::Pre, "\"pre\""),
+            (MarketStatus::Regular, "\"regular\""),
+            (MarketStatus::Post, "\"post\""),
+            (MarketStatus::Closed, "\"closed\""),
+        ] {
+            assert_eq!(serde_json::to_string(&status).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn iso_timestamps_are_utc_with_millis() {
+        assert_eq!(iso8601_utc(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601_utc(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+        // Leap day, and the last millisecond of a year
+        assert_eq!(iso8601_utc(1_709_164_800_000), "2024-02-29T00:00:00.000Z");
+        assert_eq!(iso8601_utc(1_704_067_199_999), "2023-12-31T23:59:59.999Z");
+        assert_eq!(iso8601_utc(-1), "1969-12-31T23:59:59.999Z");
+    }
+
+    #[test]
+    fn sandbox_rejects_out_of_range_parameters() {
+        assert!(SandboxDataSource::new(SandboxConfig { volatility: -0.1, ..SANDBOX_DEFAULTS }).is_err());
+        assert!(SandboxDataSource::new(SandboxConfig { drift: f64::NAN, ..SANDBOX_DEFAULTS }).is_err());
+    }
+
+    #[test]
+    fn dex_source_timeouts_have_a_floor() {
+        let timeouts = DexSourceTimeouts { jupiter_ms: 0, ..DexSourceTimeouts::default() };
+        assert_eq!(timeouts.for_source("jupiter"), Duration::from_millis(MIN_DEX_SOURCE_TIMEOUT_MS));
+        assert_eq!(timeouts.for_source("gecko"), Duration::from_millis(4_000));
+    }
+
+    #[test]
+    fn only_evm_addresses_are_case_folded() {
+        assert_eq!(address_key("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"), "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        assert_eq!(address_key("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"), "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+    }
+
+    #[test]
+    fn bad_proxy_fails_instead_of_going_direct() {
+        let api = ApiConfig { proxy_url: Some("socks5://127.0.0.1:1080".to_string()), ..ApiConfig::default() };
+        let err = http_client(&api, Duration::from_secs(1)).unwrap_err();
+        assert!(err.starts_with("Invalid proxy_url"), "{}", err);
+    }
+
+    #[test]
+    fn append_line_terminates_a_torn_record() {
+        let path = std::env::temp_dir().join(format!("trades-{}.jsonl", std::process::id()));
+        std::fs::write(&path, "{\"ok\":1}\n{\"torn\"").unwrap();
+        append_line(&path, "{\"ok\":2}").unwrap();
+        append_line(&path, "{\"ok\":3}").unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "{\"ok\":1}\n{\"torn\"\n{\"ok\":2}\n{\"ok\":3}\n");
+    }
+}
@@ -0,0 +1,151 @@
+// Resting orders for the simulator: stops, limits and OCO pairs. They are
+// matched against each new quote and fill at the quote price, the way a
+// market order placed at that moment would.
+use crate::positions::TradeSide;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderKind {
+    // Becomes a market order once the price trades through the stop
+    StopMarket { stop_price: f64 },
+    // Becomes a limit order at limit_price once the stop is touched
+    StopLimit { stop_price: f64, limit_price: f64 },
+    // Fills at limit_price or better; the take-profit leg of a bracket
+    Limit { limit_price: f64 },
+}
+
+impl OrderKind {
+    pub fn validate(&self) -> Result<(), String> {
+        let prices = match *self {
+            OrderKind::StopMarket { stop_price } => vec![stop_price],
+            OrderKind::StopLimit { stop_price, limit_price } => vec![stop_price, limit_price],
+            OrderKind::Limit { limit_price } => vec![limit_price],
+        };
+        match prices.into_iter().find(|p| !(p.is_finite() && *p > 0.0)) {
+            Some(bad) => Err(format!("Order prices must be positive, got {}", bad)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestingOrder {
+    pub id: u64,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub kind: OrderKind,
+    // Orders sharing a group are one-cancels-other: the first fill cancels the rest
+    #[serde(default)]
+    pub oco_group: Option<u64>,
+    // A stop-limit whose stop has been touched and now rests as a limit
+    #[serde(default)]
+    pub triggered: bool,
+    // May only shrink the position it protects (bracket exits); the portfolio
+    // clamps the fill to what is still open and drops it if nothing is
+    #[serde(default)]
+    pub reduce_only: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFill {
+    pub order: RestingOrder,
+    pub price: f64,
+    pub filled_at: i64,
+    // OCO siblings cancelled by this fill
+    pub cancelled: Vec<u64>,
+}
+
+// Buy stops sit above the market, sell stops below it
+fn stop_touched(side: TradeSide, stop_price: f64, price: f64) -> bool {
+    match side {
+        TradeSide::Buy => price >= stop_price,
+        TradeSide::Sell => price <= stop_price,
+    }
+}
+
+fn limit_reached(side: TradeSide, limit_price: f64, price: f64) -> bool {
+    match side {
+        TradeSide::Buy => price <= limit_price,
+        TradeSide::Sell => price >= limit_price,
+    }
+}
+
+impl RestingOrder {
+    // Advances the stop-limit trigger for a quote and says whether the order fills
+    fn on_quote(&mut self, price: f64) -> bool {
+        match self.kind {
+            OrderKind::StopMarket { stop_price } => stop_touched(self.side, stop_price, price),
+            OrderKind::StopLimit { stop_price, limit_price } => {
+                if !self.triggered && stop_touched(self.side, stop_price, price) {
+                    self.triggered = true;
+                }
+                self.triggered && limit_reached(self.side, limit_price, price)
+            }
+            OrderKind::Limit { limit_price } => limit_reached(self.side, limit_price, price),
+        }
+    }
+}
+
+// Fills the resting orders on `symbol` that `price` reaches and removes them,
+// along with their OCO siblings, from the book. When several legs of one group
+// hit on the same quote, the one placed first wins.
+pub fn match_orders(orders: &mut Vec<RestingOrder>, symbol: &str, price: f64, now: i64) -> Vec<OrderFill> {
+    let mut fills: Vec<OrderFill> = Vec::new();
+    for order in orders.iter_mut().filter(|o| o.symbol == symbol) {
+        if !order.on_quote(price) {
+            continue;
+        }
+        let sibling_filled = order.oco_group
+            .is_some_and(|group| fills.iter().any(|f| f.order.oco_group == Some(group)));
+        if !sibling_filled {
+            fills.push(OrderFill {
+                order: order.clone(),
+                price,
+                filled_at: now,
+                cancelled: Vec::new(),
+            });
+        }
+    }
+
+    for fill in &mut fills {
+        if let Some(group) = fill.order.oco_group {
+            fill.cancelled = orders.iter()
+                .filter(|o| o.oco_group == Some(group) && o.id != fill.order.id)
+                .map(|o| o.id)
+                .collect();
+        }
+    }
+    orders.retain(|o| {
+        !fills.iter().any(|f| f.order.id == o.id || (o.oco_group.is_some() && f.order.oco_group == o.oco_group))
+    });
+    fills
+}
+
+// Exit legs protecting a position opened on `entry_side`: a stop-market
+// stop-loss and a limit take-profit in one OCO group. Returns (stop, target)
+// kinds and the exit side after checking they straddle the entry correctly.
+pub fn bracket_legs(entry_side: TradeSide, stop_loss: f64, take_profit: f64) -> Result<(TradeSide, OrderKind, OrderKind), String> {
+    let stop = OrderKind::StopMarket { stop_price: stop_loss };
+    let target = OrderKind::Limit { limit_price: take_profit };
+    stop.validate()?;
+    target.validate()?;
+
+    let exit_side = match entry_side {
+        TradeSide::Buy => {
+            if stop_loss >= take_profit {
+                return Err(format!("A long's stop-loss ({}) must be below its take-profit ({})", stop_loss, take_profit));
+            }
+            TradeSide::Sell
+        }
+        TradeSide::Sell => {
+            if stop_loss <= take_profit {
+                return Err(format!("A short's stop-loss ({}) must be above its take-profit ({})", stop_loss, take_profit));
+            }
+            TradeSide::Buy
+        }
+    };
+    Ok((exit_side, stop, target))
+}
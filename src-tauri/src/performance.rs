@@ -0,0 +1,199 @@
+// Performance statistics for an equity curve of (timestamp ms, equity) points,
+// e.g. from a backtest or the replayed trade journal. Rates are fractions
+// (0.12 = 12%); annualization infers the sampling frequency from the curve.
+use serde::Serialize;
+
+const YEAR_MS: f64 = 365.25 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceStats {
+    pub start: i64,
+    pub end: i64,
+    pub total_return: f64,
+    // None when the curve spans no time
+    pub cagr: Option<f64>,
+    // Largest peak-to-trough decline as a positive fraction of the peak
+    pub max_drawdown: f64,
+    pub max_drawdown_peak: Option<i64>,
+    pub max_drawdown_trough: Option<i64>,
+    // First point back at the peak's equity; None if it never recovered
+    pub max_drawdown_recovery: Option<i64>,
+    // Annualized standard deviation of per-point returns
+    pub volatility: Option<f64>,
+    pub sharpe: Option<f64>,
+    pub risk_free_rate: f64,
+}
+
+pub fn compute(curve: &[(i64, f64)], risk_free_rate: f64) -> Result<PerformanceStats, String> {
+    if curve.len() < 2 {
+        return Err(format!("Need at least 2 equity points, got {}", curve.len()));
+    }
+    if let Some((t, v)) = curve.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+        return Err(format!("Equity must be positive, got {} at {}", v, t));
+    }
+    if !risk_free_rate.is_finite() {
+        return Err(format!("Invalid risk-free rate: {}", risk_free_rate));
+    }
+    let mut points = curve.to_vec();
+    points.sort_by_key(|(t, _)| *t);
+
+    let (start, first) = points[0];
+    let (end, last) = points[points.len() - 1];
+    let total_return = last / first - 1.0;
+    let years = (end - start) as f64 / YEAR_MS;
+    let cagr = (years > 0.0).then(|| (last / first).powf(1.0 / years) - 1.0);
+
+    // Track the running peak; a drawdown's recovery is the first point at or
+    // above the peak it fell from
+    let mut peak = points[0];
+    let mut max_drawdown = 0.0;
+    let mut worst: Option<((i64, f64), i64)> = None;
+    let mut recovery = None;
+    for &(t, v) in &points {
+        if v >= peak.1 {
+            if recovery.is_none() && worst.is_some_and(|((_, worst_peak), _)| v >= worst_peak) {
+                recovery = Some(t);
+            }
+            peak = (t, v);
+            continue;
+        }
+        let drawdown = 1.0 - v / peak.1;
+        if drawdown > max_drawdown {
+            max_drawdown = drawdown;
+            worst = Some((peak, t));
+            recovery = None;
+        }
+    }
+
+    let returns: Vec<f64> = points.windows(2).map(|w| w[1].1 / w[0].1 - 1.0).collect();
+    let periods_per_year = (years > 0.0).then(|| returns.len() as f64 / years);
+    let (volatility, sharpe) = match periods_per_year {
+        Some(periods) if returns.len() >= 2 => {
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+            let stdev = variance.sqrt();
+            let excess = mean - risk_free_rate / periods;
+            let sharpe = (stdev > 0.0).then(|| excess / stdev * periods.sqrt());
+            (Some(stdev * periods.sqrt()), sharpe)
+        }
+        _ => (None, None),
+    };
+
+    Ok(PerformanceStats {
+        start,
+        end,
+        total_return,
+        cagr,
+        max_drawdown,
+        max_drawdown_peak: worst.map(|((t, _), _)| t),
+        max_drawdown_trough: worst.map(|(_, t)| t),
+        max_drawdown_recovery: recovery,
+        volatility,
+        sharpe,
+        risk_free_rate,
+    })
+}
+
+// Pearson correlation of two equal-length series, e.g. two assets' returns
+pub fn correlation(a: &[f64], b: &[f64]) -> Result<f64, String> {
+    if a.len() != b.len() {
+        return Err(format!("Series lengths differ: {} vs {}", a.len(), b.len()));
+    }
+    if a.len() < 2 {
+        return Err(format!("Need at least 2 points, got {}", a.len()));
+    }
+    if a.iter().chain(b).any(|v| !v.is_finite()) {
+        return Err("Series values must be finite".to_string());
+    }
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return Err("Correlation is undefined for a series that never moves".to_string());
+    }
+    // Rounding can push a perfect fit a hair past +-1
+    Ok((cov / (var_a.sqrt() * var_b.sqrt())).clamp(-1.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    // Quarterly points over exactly one year
+    fn quarterly(equity: &[f64]) -> Vec<(i64, f64)> {
+        let quarter = (YEAR_MS / 4.0) as i64;
+        equity.iter().enumerate().map(|(i, v)| (i as i64 * quarter, *v)).collect()
+    }
+
+    #[test]
+    fn stats_match_a_hand_computed_curve() {
+        // Returns +20%, -25%, +20%, +22.2%: 120 -> 90 is a 25% drawdown that
+        // 132 recovers; 108 doesn't
+        let curve = quarterly(&[100.0, 120.0, 90.0, 108.0, 132.0]);
+        let stats = compute(&curve, 0.02).unwrap();
+        let quarter = curve[1].0;
+
+        assert!(close(stats.total_return, 0.32));
+        assert!(close(stats.cagr.unwrap(), 0.32));
+        assert!(close(stats.max_drawdown, 0.25));
+        assert_eq!(stats.max_drawdown_peak, Some(quarter));
+        assert_eq!(stats.max_drawdown_trough, Some(2 * quarter));
+        assert_eq!(stats.max_drawdown_recovery, Some(4 * quarter));
+        // Sample stdev 0.228943... of the quarterly returns, times sqrt(4)
+        assert!((stats.volatility.unwrap() - 0.457_886_988_051_812).abs() < 1e-9);
+        // (mean 0.093056 - 0.02 / 4) / stdev * sqrt(4)
+        assert!((stats.sharpe.unwrap() - 0.769_233_962_556_644).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unrecovered_drawdown_and_unsorted_input() {
+        let mut curve = quarterly(&[100.0, 80.0, 90.0]);
+        curve.reverse();
+        let stats = compute(&curve, 0.0).unwrap();
+        assert!(close(stats.max_drawdown, 0.2));
+        assert_eq!(stats.max_drawdown_recovery, None);
+        assert!(close(stats.total_return, -0.1));
+    }
+
+    #[test]
+    fn stats_reject_bad_curves() {
+        assert!(compute(&[(0, 100.0)], 0.0).is_err());
+        assert!(compute(&[(0, 100.0), (1, 0.0)], 0.0).is_err());
+        assert!(compute(&[(0, 100.0), (1, 110.0)], f64::NAN).is_err());
+        // No elapsed time: nothing to annualize
+        let flat_time = compute(&[(5, 100.0), (5, 110.0)], 0.0).unwrap();
+        assert_eq!(flat_time.cagr, None);
+        assert_eq!(flat_time.sharpe, None);
+    }
+
+    #[test]
+    fn correlation_of_known_series() {
+        let a: Vec<f64> = (0..30).map(|i| ((i * 7) % 11) as f64 - 5.0).collect();
+        let scaled: Vec<f64> = a.iter().map(|x| 3.0 * x + 1.0).collect();
+        let inverted: Vec<f64> = a.iter().map(|x| -2.0 * x).collect();
+        assert!(close(correlation(&a, &scaled).unwrap(), 1.0));
+        assert!(close(correlation(&a, &inverted).unwrap(), -1.0));
+        // Textbook pair: cov 8 / (sqrt 10 * sqrt 10) = 0.8
+        assert!(close(correlation(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 1.0, 4.0, 3.0, 5.0]).unwrap(), 0.8));
+    }
+
+    #[test]
+    fn correlation_rejects_unequal_lengths() {
+        assert!(correlation(&[1.0, 2.0, 3.0], &[1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn correlation_rejects_zero_variance() {
+        assert!(correlation(&[1.0, 2.0, 3.0], &[4.0, 4.0, 4.0]).is_err());
+    }
+}
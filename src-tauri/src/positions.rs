@@ -0,0 +1,326 @@
+// Cost-basis accounting for simulated trades. Lots are matched first-in,
+// first-out per symbol; selling more than is held opens a short, and buying
+// against a short covers it the same way.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// Quantities below this are treated as fully closed (float dust)
+const QTY_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub symbol: String,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub price: f64,
+    pub timestamp: i64,
+}
+
+// An open lot; quantity is positive for longs, negative for shorts
+#[derive(Debug, Clone, Serialize)]
+pub struct Lot {
+    pub quantity: f64,
+    pub price: f64,
+    pub opened_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClosedLot {
+    pub symbol: String,
+    // Positive when a long was sold, negative when a short was covered
+    pub quantity: f64,
+    pub open_price: f64,
+    pub close_price: f64,
+    pub opened_at: i64,
+    pub closed_at: i64,
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenPosition {
+    pub symbol: String,
+    pub quantity: f64,
+    // Weighted-average price of the lots still open
+    pub avg_cost: f64,
+    pub lots: Vec<Lot>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlReport {
+    pub positions: Vec<OpenPosition>,
+    pub closed_lots: Vec<ClosedLot>,
+    pub realized_pnl: f64,
+}
+
+pub fn compute(trades: &[Trade]) -> PnlReport {
+    let mut ordered: Vec<&Trade> = trades
+        .iter()
+        .filter(|t| t.quantity.is_finite() && t.quantity > 0.0 && t.price.is_finite())
+        .collect();
+    ordered.sort_by_key(|t| t.timestamp);
+
+    let mut books: HashMap<&str, VecDeque<Lot>> = HashMap::new();
+    let mut closed_lots = Vec::new();
+
+    for trade in ordered {
+        let lots = books.entry(trade.symbol.as_str()).or_default();
+        let signed = match trade.side {
+            TradeSide::Buy => trade.quantity,
+            TradeSide::Sell => -trade.quantity,
+        };
+        let mut remaining = signed;
+
+        // Close opposite-signed lots first, oldest first
+        while remaining.abs() > QTY_EPSILON {
+            let Some(lot) = lots.front_mut() else { break };
+            if lot.quantity.signum() == remaining.signum() {
+                break;
+            }
+            let matched = lot.quantity.abs().min(remaining.abs()) * lot.quantity.signum();
+            closed_lots.push(ClosedLot {
+                symbol: trade.symbol.clone(),
+                quantity: matched,
+                open_price: lot.price,
+                close_price: trade.price,
+                opened_at: lot.opened_at,
+                closed_at: trade.timestamp,
+                realized_pnl: (trade.price - lot.price) * matched,
+            });
+            lot.quantity -= matched;
+            remaining += matched;
+            if lot.quantity.abs() <= QTY_EPSILON {
+                lots.pop_front();
+            }
+        }
+
+        if remaining.abs() > QTY_EPSILON {
+            lots.push_back(Lot {
+                quantity: remaining,
+                price: trade.price,
+                opened_at: trade.timestamp,
+            });
+        }
+    }
+
+    let mut positions: Vec<OpenPosition> = books
+        .into_iter()
+        .filter(|(_, lots)| !lots.is_empty())
+        .map(|(symbol, lots)| {
+            let quantity: f64 = lots.iter().map(|l| l.quantity).sum();
+            let cost: f64 = lots.iter().map(|l| l.quantity * l.price).sum();
+            OpenPosition {
+                symbol: symbol.to_string(),
+                quantity,
+                avg_cost: cost / quantity,
+                lots: lots.into_iter().collect(),
+            }
+        })
+        .collect();
+    positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    PnlReport {
+        realized_pnl: closed_lots.iter().map(|l| l.realized_pnl).sum(),
+        positions,
+        closed_lots,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionSize {
+    pub side: TradeSide,
+    pub quantity: f64,
+    // Loss if the stop is hit: quantity * |entry - stop|
+    pub risk_amount: f64,
+    pub notional: f64,
+}
+
+// Sizes a trade so hitting the stop loses `risk_percent` of equity. The side
+// follows from the stop (below entry is a long, above is a short); when the
+// caller names a side, a stop on the wrong side of entry is rejected.
+pub fn size_position(
+    account_equity: f64,
+    risk_percent: f64,
+    entry_price: f64,
+    stop_price: f64,
+    side: Option<TradeSide>,
+) -> Result<PositionSize, String> {
+    if !(account_equity.is_finite() && account_equity > 0.0) {
+        return Err(format!("Account equity must be positive, got {}", account_equity));
+    }
+    if !(risk_percent.is_finite() && risk_percent > 0.0 && risk_percent <= 100.0) {
+        return Err(format!("Risk percent must be in (0, 100], got {}", risk_percent));
+    }
+    if !(entry_price.is_finite() && entry_price > 0.0 && stop_price.is_finite() && stop_price > 0.0) {
+        return Err("Entry and stop prices must be positive".to_string());
+    }
+    if entry_price == stop_price {
+        return Err("Stop price must differ from entry price".to_string());
+    }
+
+    let implied = if stop_price < entry_price { TradeSide::Buy } else { TradeSide::Sell };
+    if let Some(side) = side {
+        if side != implied {
+            return Err(match side {
+                TradeSide::Buy => format!("A long's stop ({}) must be below entry ({})", stop_price, entry_price),
+                TradeSide::Sell => format!("A short's stop ({}) must be above entry ({})", stop_price, entry_price),
+            });
+        }
+    }
+
+    let risk_amount = account_equity * risk_percent / 100.0;
+    let quantity = risk_amount / (entry_price - stop_price).abs();
+    Ok(PositionSize {
+        side: implied,
+        quantity,
+        risk_amount,
+        notional: quantity * entry_price,
+    })
+}
+
+// Exit price at which closing the position nets zero after `fees_paid`: fees
+// push a long's break-even above its cost and a short's below it. `side` is
+// the side that opened the position. Only fees already paid count; the fee
+// on the closing trade itself is not included. A short whose fees exceed its
+// entry value can't break even at any price, which is an error.
+pub fn break_even(avg_cost: f64, quantity: f64, fees_paid: f64, side: TradeSide) -> Result<f64, String> {
+    if !(avg_cost.is_finite() && avg_cost > 0.0) {
+        return Err(format!("Average cost must be positive, got {}", avg_cost));
+    }
+    if !(quantity.is_finite() && quantity > QTY_EPSILON) {
+        return Err(format!("Quantity must be positive, got {}", quantity));
+    }
+    if !(fees_paid.is_finite() && fees_paid >= 0.0) {
+        return Err(format!("Fees must be zero or more, got {}", fees_paid));
+    }
+    let per_unit = fees_paid / quantity;
+    match side {
+        TradeSide::Buy => Ok(avg_cost + per_unit),
+        TradeSide::Sell if per_unit < avg_cost => Ok(avg_cost - per_unit),
+        TradeSide::Sell => Err(format!(
+            "Fees of {} exceed the short's entry value of {}; no exit price breaks even",
+            fees_paid, avg_cost * quantity
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(symbol: &str, side: TradeSide, quantity: f64, price: f64, timestamp: i64) -> Trade {
+        Trade { symbol: symbol.to_string(), side, quantity, price, timestamp }
+    }
+
+    #[test]
+    fn buy_buy_sell_realizes_fifo() {
+        // Sell 15 closes all 10 @ 100 (+200) and 5 of the 10 @ 110 (+50)
+        let report = compute(&[
+            trade("AAPL", TradeSide::Buy, 10.0, 100.0, 1),
+            trade("AAPL", TradeSide::Buy, 10.0, 110.0, 2),
+            trade("AAPL", TradeSide::Sell, 15.0, 120.0, 3),
+        ]);
+        assert_eq!(report.realized_pnl, 250.0);
+        assert_eq!(report.closed_lots.len(), 2);
+        assert_eq!(report.closed_lots[1].quantity, 5.0);
+
+        let position = &report.positions[0];
+        assert_eq!(position.quantity, 5.0);
+        assert_eq!(position.avg_cost, 110.0);
+        assert_eq!(position.lots[0].opened_at, 2);
+    }
+
+    #[test]
+    fn trades_are_matched_in_time_order() {
+        let report = compute(&[
+            trade("AAPL", TradeSide::Sell, 10.0, 120.0, 2),
+            trade("AAPL", TradeSide::Buy, 10.0, 100.0, 1),
+        ]);
+        assert_eq!(report.realized_pnl, 200.0);
+        assert!(report.positions.is_empty());
+    }
+
+    #[test]
+    fn overselling_opens_a_short_that_covers_fifo() {
+        let report = compute(&[
+            trade("TSLA", TradeSide::Buy, 5.0, 200.0, 1),
+            trade("TSLA", TradeSide::Sell, 8.0, 210.0, 2),
+            trade("TSLA", TradeSide::Buy, 2.0, 190.0, 3),
+        ]);
+        // +50 on the long, then the 3-share short @ 210 covers 2 @ 190 for +40
+        assert_eq!(report.realized_pnl, 90.0);
+        assert_eq!(report.closed_lots[1].quantity, -2.0);
+        let position = &report.positions[0];
+        assert_eq!(position.quantity, -1.0);
+        assert_eq!(position.avg_cost, 210.0);
+    }
+
+    #[test]
+    fn symbols_are_tracked_separately() {
+        let report = compute(&[
+            trade("MSFT", TradeSide::Buy, 1.0, 300.0, 1),
+            trade("AAPL", TradeSide::Buy, 2.0, 100.0, 2),
+            trade("AAPL", TradeSide::Sell, 0.0, 500.0, 3),
+        ]);
+        assert_eq!(report.realized_pnl, 0.0);
+        let symbols: Vec<&str> = report.positions.iter().map(|p| p.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+    }
+
+    #[test]
+    fn sizes_a_long_from_the_stop_distance() {
+        // 1% of 50k = 500 at risk over a $2 stop = 250 shares
+        let size = size_position(50_000.0, 1.0, 100.0, 98.0, None).unwrap();
+        assert_eq!(size.side, TradeSide::Buy);
+        assert_eq!(size.quantity, 250.0);
+        assert_eq!(size.risk_amount, 500.0);
+        assert_eq!(size.notional, 25_000.0);
+    }
+
+    #[test]
+    fn a_stop_above_entry_sizes_a_short() {
+        let size = size_position(10_000.0, 2.0, 50.0, 55.0, Some(TradeSide::Sell)).unwrap();
+        assert_eq!(size.side, TradeSide::Sell);
+        assert_eq!(size.quantity, 40.0);
+    }
+
+    #[test]
+    fn rejects_a_stop_on_the_wrong_side_or_bad_inputs() {
+        assert!(size_position(10_000.0, 1.0, 100.0, 105.0, Some(TradeSide::Buy)).is_err());
+        assert!(size_position(10_000.0, 1.0, 100.0, 95.0, Some(TradeSide::Sell)).is_err());
+        assert!(size_position(10_000.0, 1.0, 100.0, 100.0, None).is_err());
+        assert!(size_position(0.0, 1.0, 100.0, 95.0, None).is_err());
+        assert!(size_position(10_000.0, 0.0, 100.0, 95.0, None).is_err());
+        assert!(size_position(10_000.0, 150.0, 100.0, 95.0, None).is_err());
+        assert!(size_position(10_000.0, 1.0, f64::NAN, 95.0, None).is_err());
+    }
+
+    #[test]
+    fn break_even_spreads_fees_over_the_quantity() {
+        assert_eq!(break_even(100.0, 10.0, 5.0, TradeSide::Buy), Ok(100.5));
+        assert_eq!(break_even(100.0, 10.0, 5.0, TradeSide::Sell), Ok(99.5));
+        assert_eq!(break_even(100.0, 10.0, 0.0, TradeSide::Buy), Ok(100.0));
+    }
+
+    #[test]
+    fn break_even_rejects_a_short_buried_in_fees() {
+        // 10 shares shorted at 1 with 10 in fees would need an exit at 0
+        assert!(break_even(1.0, 10.0, 10.0, TradeSide::Sell).is_err());
+        assert!(break_even(1.0, 10.0, 15.0, TradeSide::Sell).is_err());
+        assert_eq!(break_even(1.0, 10.0, 15.0, TradeSide::Buy), Ok(2.5));
+    }
+
+    #[test]
+    fn break_even_validates_inputs() {
+        assert!(break_even(0.0, 10.0, 1.0, TradeSide::Buy).is_err());
+        assert!(break_even(100.0, 0.0, 1.0, TradeSide::Buy).is_err());
+        assert!(break_even(100.0, 10.0, -1.0, TradeSide::Buy).is_err());
+        assert!(break_even(100.0, 10.0, f64::NAN, TradeSide::Sell).is_err());
+    }
+}
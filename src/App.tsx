@@ -1,5 +1,6 @@
 import { useEffect, useRef, useState } from 'react';
-import { isTauri, getVersion, getChangelog, checkForUpdate } from './api';
+import { isTauri, getVersion, getChangelog, checkForUpdate, prefetch, pendingOrderFills, ackOrderFill } from './api';
+import type { OrderFilledEvent, PrefetchCompleteEvent } from './api';
 import { ChartGrid } from './components/Chart/ChartGrid';
 import { OrderForm } from './components/Trading/OrderForm';
 import { PositionList } from './components/Trading/PositionList';
@@ -229,6 +230,59 @@ function App() {
     return () => unlisten?.();
   }, []);
 
+  // Book resting stop/limit fills from the backend ticker (Tauri only). Fills
+  // stay pending in the backend until acknowledged, so any that happened before
+  // this listener existed are replayed once it is in place.
+  useEffect(() => {
+    if (!isTauri) return;
+    let unlisten: (() => void) | undefined;
+    const book = (fill: OrderFilledEvent) => {
+      useTradingStore.getState().applyOrderFill(fill);
+      ackOrderFill(fill.order.id).catch((err) => console.warn('Order fill ack failed:', err));
+    };
+    import('@tauri-apps/api/event').then(({ listen }) => {
+      listen<OrderFilledEvent>('order-filled', (event) => book(event.payload))
+        .then((fn) => {
+          unlisten = fn;
+          return pendingOrderFills();
+        })
+        .then((fills) => fills.forEach(book))
+        .catch((err) => console.warn('Pending order fills failed:', err));
+    });
+    return () => unlisten?.();
+  }, []);
+
+  // Warm quotes and DEX prices for the watchlists so they render from cache (Tauri only)
+  useEffect(() => {
+    if (!isTauri) return;
+    let unlisten: (() => void) | undefined;
+    import('@tauri-apps/api/event').then(({ listen }) => {
+      listen<PrefetchCompleteEvent>('prefetch-complete', (event) => {
+        if (event.payload.failed.length) {
+          console.log('Prefetch failed for:', event.payload.failed.join(', '));
+        }
+      }).then((fn) => { unlisten = fn; });
+    });
+
+    // After the saved watchlists load, so the prefetch covers what is shown
+    const { loadSavedWatchlists } = useTradingStore.getState();
+    loadSavedWatchlists()
+      .catch((e) => console.error('Loading watchlists failed:', e))
+      .finally(() => {
+        const items = useTradingStore.getState().watchlists.flatMap((w) => w.items);
+        const symbols = [...new Set(items.filter((i) => i.market === 'stock').map((i) => i.symbol))];
+        const dex = items
+          .filter((i) => i.symbol.toLowerCase().startsWith('dex:'))
+          .map((i) => {
+            const [, chain_id, address] = i.symbol.split(':');
+            return { chain_id, address };
+          })
+          .filter((r) => r.chain_id && r.address);
+        prefetch(symbols, dex).catch((e) => console.log('Prefetch failed:', e));
+      });
+    return () => unlisten?.();
+  }, []);
+
   // Poll to detect when detached windows are closed (Tauri only)
   const pollRef = useRef<ReturnType<typeof setInterval> | null>(null);
   useEffect(() => {
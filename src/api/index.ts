@@ -13,6 +13,8 @@ const PROXY_BASE = import.meta.env.VITE_PROXY_URL || '/api';
 // ─── Types ───────────────────────────────────────────────────────────
 
 export interface StockChartResponse {
+  // Normalized (trimmed, uppercased) form of the requested symbol
+  symbol?: string;
   candles: Array<{
     time: number;
     open: number;
@@ -20,31 +22,90 @@ export interface StockChartResponse {
     low: number;
     close: number;
     volume: number;
+    // UTC ISO-8601 form of time; only present with iso_timestamps (desktop)
+    time_iso?: string;
   }>;
   current_price: number;
   previous_close: number;
   day_high: number;
   day_low: number;
   volume: number;
+  // IANA zone and current UTC offset of the exchange; candle times stay UTC
+  timezone: string | null;
+  gmt_offset_secs: number;
+  // Missing session dates (local midnight, ms); only present with mark_gaps
+  gaps?: number[];
+  // Full series length when only a page was requested
+  total_candles?: number;
+  // Swing-high/low pivots; only present when a levels lookback was requested
+  levels?: Array<{ kind: 'support' | 'resistance'; price: number; time: number }>;
 }
 
 export interface StockQuote {
   symbol: string;
   price: number;
   change: number;
-  change_percent: number;
+  change_percent: number | null;
   high: number;
   low: number;
   volume: number;
   market_status: 'pre' | 'regular' | 'post' | 'closed';
+  // Pre/post-market move from the last regular close; change stays regular-session
+  session_change?: number | null;
+  session_change_percent?: number | null;
+  // Epoch ms the quote was taken at (desktop), plus its ISO form with iso_timestamps
+  as_of?: number;
+  as_of_iso?: string;
 }
 
 export interface DexPriceResult {
   price: number;
-  change_24h: number;
+  // Percent change over 24h; null when the source doesn't report one
+  change_24h: number | null;
   volume_24h: number;
   pair_address: string;
   source: string;
+  // Quote token of the DexScreener pair (SOL, WETH...); price is always USD
+  quote_symbol?: string | null;
+}
+
+// One source's answer from compareDexSources
+export interface SourcePrice {
+  source: string;
+  price: number | null;
+  error: string | null;
+  latency_ms: number;
+  // Against the median of the sources that answered
+  deviation_percent: number | null;
+}
+
+// Payload of the backend's "order-filled" event for a resting stop/limit order
+export interface OrderFilledEvent {
+  order: {
+    id: number;
+    symbol: string;
+    side: 'buy' | 'sell';
+    quantity: number;
+    kind:
+      | { type: 'stop_market'; stop_price: number }
+      | { type: 'stop_limit'; stop_price: number; limit_price: number }
+      | { type: 'limit'; limit_price: number };
+    oco_group: number | null;
+    // Bracket exits: may only shrink the open position
+    reduce_only?: boolean;
+  };
+  price: number;
+  filled_at: number;
+  // OCO siblings cancelled by this fill
+  cancelled: number[];
+}
+
+// Payload of the backend's "prefetch-complete" event
+export interface PrefetchCompleteEvent {
+  quotes: number;
+  dex_prices: number;
+  // Symbols and token addresses whose lookup failed
+  failed: string[];
 }
 
 export interface UpdateCheckResult {
@@ -103,15 +164,30 @@ export async function fetchStockCandles(
   symbol: string,
   interval: string,
   range: string,
+  page?: { limit?: number; offset?: number },
+  levelsLookback?: number,
 ): Promise<StockChartResponse> {
   if (isTauri) {
     const { invoke } = await import('@tauri-apps/api/core');
-    return invoke<StockChartResponse>('fetch_stock_candles', { symbol, interval, range });
+    return invoke<StockChartResponse>('fetch_stock_candles', {
+      symbol,
+      interval,
+      range,
+      limit: page?.limit ?? null,
+      offset: page?.offset ?? null,
+      levelsLookback: levelsLookback ?? null,
+    });
   }
 
-  // Web: proxy through serverless function
+  // Web: proxy through serverless function, then page the full series locally.
+  // Pivot levels are computed by the desktop backend only.
   const params = new URLSearchParams({ symbol, interval, range });
-  return tryFetchJson<StockChartResponse>(`${PROXY_BASE}/stock/candles?${params}`);
+  const response = await tryFetchJson<StockChartResponse>(`${PROXY_BASE}/stock/candles?${params}`);
+  if (!page) return response;
+  const total = response.candles.length;
+  const end = Math.max(0, total - (page.offset ?? 0));
+  const start = page.limit === undefined ? 0 : Math.max(0, end - page.limit);
+  return { ...response, candles: response.candles.slice(start, end), total_candles: total };
 }
 
 export async function fetchStockQuote(symbol: string): Promise<StockQuote> {
@@ -134,7 +210,7 @@ async function tryJupiter(address: string): Promise<DexPriceResult> {
   if (!token?.usdPrice || token.usdPrice <= 0) throw new Error('Jupiter: no price');
   return {
     price: token.usdPrice,
-    change_24h: token.priceChange24h ?? 0,
+    change_24h: token.priceChange24h ?? null,
     volume_24h: 0,
     pair_address: '',
     source: 'jupiter',
@@ -149,7 +225,7 @@ async function tryRaydium(address: string): Promise<DexPriceResult> {
   if (!priceStr) throw new Error('Raydium: no data');
   const price = parseFloat(priceStr);
   if (!price || price <= 0) throw new Error('Raydium: invalid price');
-  return { price, change_24h: 0, volume_24h: 0, pair_address: '', source: 'raydium' };
+  return { price, change_24h: null, volume_24h: 0, pair_address: '', source: 'raydium' };
 }
 
 interface DexScreenerPair {
@@ -173,7 +249,7 @@ async function tryGecko(chainId: string, address: string): Promise<DexPriceResul
   if (!priceStr) throw new Error('Gecko: token not found');
   const price = parseFloat(priceStr);
   if (!price || price <= 0) throw new Error('Gecko: invalid price');
-  return { price, change_24h: 0, volume_24h: 0, pair_address: '', source: 'gecko' };
+  return { price, change_24h: null, volume_24h: 0, pair_address: '', source: 'gecko' };
 }
 
 async function tryDexScreener(
@@ -193,7 +269,7 @@ async function tryDexScreener(
         if (price > 0) {
           return {
             price,
-            change_24h: pair.priceChange?.h24 ?? 0,
+            change_24h: pair.priceChange?.h24 ?? null,
             volume_24h: pair.volume?.h24 ?? 0,
             pair_address: pair.pairAddress ?? '',
             source: 'dexscreener',
@@ -222,7 +298,7 @@ async function tryDexScreener(
 
   return {
     price,
-    change_24h: best.priceChange?.h24 ?? 0,
+    change_24h: best.priceChange?.h24 ?? null,
     volume_24h: best.volume?.h24 ?? 0,
     pair_address: best.pairAddress ?? '',
     source: 'dexscreener',
@@ -283,6 +359,101 @@ export async function fetchDexStats(
   return tryDexScreener(chainId, address, pairAddress);
 }
 
+/**
+ * Warm the backend's quote and DEX price caches (Tauri only). Resolves at once;
+ * the backend emits "prefetch-complete" when the lookups finish.
+ */
+export async function prefetch(
+  symbols: string[],
+  dex: Array<{ chain_id: string; address: string }>,
+): Promise<void> {
+  if (!isTauri) return;
+  const { invoke } = await import('@tauri-apps/api/core');
+  await invoke('prefetch', { symbols, dex });
+}
+
+/**
+ * Price a token on every source at once to spot a stale or off feed (Tauri only).
+ */
+export async function compareDexSources(
+  chainId: string,
+  address: string,
+  pairAddress: string | null,
+  // CoinGecko asset id (e.g. 'ethereum'); without one the coingecko source is skipped
+  coingeckoId: string | null = null,
+): Promise<SourcePrice[]> {
+  if (!isTauri) return [];
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<SourcePrice[]>('compare_dex_sources', { chainId, address, pairAddress, coingeckoId });
+}
+
+// ─── Resting order fills ────────────────────────────────────────────
+
+/** Fills not yet acknowledged, e.g. ones that happened before the window listened. */
+export async function pendingOrderFills(): Promise<OrderFilledEvent[]> {
+  if (!isTauri) return [];
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<OrderFilledEvent[]>('pending_order_fills');
+}
+
+/** Tell the backend a fill has been booked so it stops re-delivering it. */
+export async function ackOrderFill(id: number): Promise<void> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  await invoke('ack_order_fill', { id });
+}
+
+// ─── Watchlists ─────────────────────────────────────────────────────
+
+/** Named watchlists keyed by name, stored in the app data dir (Tauri only). */
+export type Watchlists = Record<string, string[]>;
+
+export async function loadWatchlists(): Promise<Watchlists> {
+  if (!isTauri) return {};
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<Watchlists>('load_watchlists');
+}
+
+/** Create or replace a watchlist; resolves to every saved list. */
+export async function saveWatchlist(name: string, symbols: string[]): Promise<Watchlists> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<Watchlists>('save_watchlist', { name, symbols });
+}
+
+export async function deleteWatchlist(name: string): Promise<Watchlists> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<Watchlists>('delete_watchlist', { name });
+}
+
+// ─── Local storage ──────────────────────────────────────────────────
+
+export type StorageKind = 'trades' | 'orders' | 'watchlists' | 'cache';
+
+export interface StorageEntry {
+  kind: StorageKind;
+  path: string;
+  exists: boolean;
+  bytes: number;
+}
+
+export interface StorageInfo {
+  data_dir: string;
+  cache_dir: string;
+  entries: StorageEntry[];
+  total_bytes: number;
+}
+
+export async function getStorageInfo(): Promise<StorageInfo | null> {
+  if (!isTauri) return null;
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<StorageInfo>('get_storage_info');
+}
+
+/** Delete the given kinds of local state; resolves to what remains. */
+export async function clearStorage(kinds: StorageKind[]): Promise<StorageInfo> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<StorageInfo>('clear_storage', { kinds });
+}
+
 // ─── Version & Changelog ────────────────────────────────────────────
 
 export async function getVersion(): Promise<string> {
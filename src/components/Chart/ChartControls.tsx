@@ -111,7 +111,8 @@ export function ChartControls({ frozenData, layout, onLayoutChange, onPopout }:
     return price.toFixed(12);
   };
 
-  const formatChange = (change: number) => {
+  const formatChange = (change: number | null) => {
+    if (change === null) return '—';
     const sign = change >= 0 ? '+' : '';
     return `${sign}${change.toFixed(2)}%`;
   };
@@ -138,7 +139,7 @@ export function ChartControls({ frozenData, layout, onLayoutChange, onPopout }:
           {ticker && (
             <>
               <span className={`symbol-price ${isFrozen ? '' : flashClass}`}>{formatPrice(ticker.price)}</span>
-              <span className={`symbol-change ${ticker.changePercent24h >= 0 ? 'positive' : 'negative'}`}>
+              <span className={`symbol-change ${(ticker.changePercent24h ?? 0) >= 0 ? 'positive' : 'negative'}`}>
                 {formatChange(ticker.changePercent24h)}
               </span>
               {isStock && ticker.marketStatus && (
@@ -9,7 +9,7 @@ interface SettingsProps {
 }
 
 export function Settings({ updateAvailable = false, newVersion = '' }: SettingsProps) {
-  const { showSettings, setShowSettings } = useSettingsStore();
+  const { showSettings, setShowSettings, allowShorting, setAllowShorting } = useSettingsStore();
   const balance = useTradingStore((s) => s.portfolio.balance);
   const setBalance = useTradingStore((s) => s.setBalance);
 
@@ -175,6 +175,22 @@ export function Settings({ updateAvailable = false, newVersion = '' }: SettingsP
             </div>
           </div>
 
+          {/* Trading Rules */}
+          <div className="settings-section">
+            <h3>Trading</h3>
+            <p className="settings-description">
+              Turn off to simulate a cash account, where sells can't exceed the shares held.
+            </p>
+            <label className="settings-toggle">
+              <input
+                type="checkbox"
+                checked={allowShorting}
+                onChange={(e) => setAllowShorting(e.target.checked)}
+              />
+              Allow short selling
+            </label>
+          </div>
+
           {/* Reset App Section */}
           <div className="settings-section">
             <h3>Reset</h3>
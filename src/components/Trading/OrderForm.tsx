@@ -1,5 +1,6 @@
 import { useState, useMemo } from 'react';
-import { useTradingStore } from '../../stores/tradingStore';
+import { useTradingStore, orderSizeError } from '../../stores/tradingStore';
+import { useSettingsStore } from '../../stores/settingsStore';
 import type { OrderSide, OrderType, Watchlist } from '../../types/trading';
 
 function getDisplaySymbol(symbol: string, watchlists: Watchlist[]): string {
@@ -22,6 +23,7 @@ function getDisplaySymbol(symbol: string, watchlists: Watchlist[]): string {
 
 export function OrderForm() {
   const { currentSymbol, currentPrice, portfolio, executeMarketOrder, closePosition, watchlists } = useTradingStore();
+  const allowShorting = useSettingsStore((s) => s.allowShorting);
   const displaySymbol = (sym: string) => getDisplaySymbol(sym, watchlists);
 
   const [side, setSide] = useState<OrderSide>('buy');
@@ -32,6 +34,8 @@ export function OrderForm() {
   const [inputMode, setInputMode] = useState<'qty' | 'usd'>('qty');
   const [usdAmount, setUsdAmount] = useState('');
   const [sellAll, setSellAll] = useState(false);
+  const [reduceOnly, setReduceOnly] = useState(false);
+  const [orderError, setOrderError] = useState<string | null>(null);
 
   const existingPosition = useMemo(
     () => portfolio.positions.find((p) => p.symbol === currentSymbol),
@@ -49,10 +53,20 @@ export function OrderForm() {
 
   const totalValue = effectiveQty * effectivePrice;
 
+  // Sells against a long are clamped to it on submit, so check the clamped size
+  const orderQty = side === 'sell' && existingPosition?.side === 'buy'
+    ? Math.min(effectiveQty, existingPosition.quantity)
+    : effectiveQty;
+  const sizeError = orderQty > 0 && !(sellAll && existingPosition)
+    ? orderSizeError(portfolio, currentSymbol, side, orderQty, reduceOnly, allowShorting)
+    : null;
+  const shownError = orderError ?? sizeError;
+
   const maxBuyQty = effectivePrice > 0 ? portfolio.buyingPower / effectivePrice : 0;
 
   const setPercentage = (pct: number) => {
     setSellAll(false);
+    setOrderError(null);
     if (side === 'buy') {
       const qty = maxBuyQty * (pct / 100);
       if (inputMode === 'usd') {
@@ -97,10 +111,9 @@ export function OrderForm() {
 
     if (effectiveQty <= 0) return;
 
-    // Clamp sell quantity to position size to avoid dust issues
-    let qty = effectiveQty;
-    if (side === 'sell' && existingPosition) {
-      if (qty > existingPosition.quantity) qty = existingPosition.quantity;
+    // Sell quantity is clamped to a long position's size to avoid dust issues
+    const qty = orderQty;
+    if (side === 'sell' && existingPosition?.side === 'buy') {
       // If within 0.01% of full position, treat as full close
       if (qty > existingPosition.quantity * 0.9999) {
         closePosition(currentSymbol);
@@ -115,10 +128,14 @@ export function OrderForm() {
 
     // Validation
     if (side === 'buy' && orderValue > portfolio.buyingPower) return;
-    if (side === 'sell' && !existingPosition) return;
+    if (sizeError) return;
 
     if (orderType === 'market') {
-      executeMarketOrder(side, qty, price);
+      const error = executeMarketOrder(side, qty, price, reduceOnly);
+      if (error) {
+        setOrderError(error);
+        return;
+      }
     }
 
     // Reset form
@@ -127,16 +144,19 @@ export function OrderForm() {
     setLimitPrice('');
     setStopPrice('');
     setSellAll(false);
+    setOrderError(null);
   };
 
   // Clear sellAll when user manually types
   const handleQuantityChange = (value: string) => {
     setSellAll(false);
+    setOrderError(null);
     setQuantity(value);
   };
 
   const handleUsdChange = (value: string) => {
     setSellAll(false);
+    setOrderError(null);
     setUsdAmount(value);
   };
 
@@ -146,7 +166,7 @@ export function OrderForm() {
     if (sellAll && existingPosition) return false;
     if (effectiveQty <= 0) return true;
     if (side === 'buy' && totalValue > portfolio.buyingPower) return true;
-    if (side === 'sell' && !existingPosition) return true;
+    if (sizeError) return true;
     return false;
   })();
 
@@ -157,7 +177,7 @@ export function OrderForm() {
       if (existingPosition && existingPosition.side === 'buy') return `Buy More ${displaySymbol(currentSymbol)}`;
       return `Buy ${displaySymbol(currentSymbol)}`;
     } else {
-      if (!existingPosition) return `No Position to Sell`;
+      if (!existingPosition) return allowShorting && !reduceOnly ? `Sell Short ${displaySymbol(currentSymbol)}` : `No Position to Sell`;
       if (sellAll) return `Close ${displaySymbol(currentSymbol)}`;
       if (effectiveQty > 0 && effectiveQty < existingPosition.quantity * 0.9999) return `Sell Partial ${displaySymbol(currentSymbol)}`;
       return `Sell ${displaySymbol(currentSymbol)}`;
@@ -170,14 +190,14 @@ export function OrderForm() {
         <button
           type="button"
           className={`side-tab buy ${side === 'buy' ? 'active' : ''}`}
-          onClick={() => { setSide('buy'); setSellAll(false); }}
+          onClick={() => { setSide('buy'); setSellAll(false); setOrderError(null); }}
         >
           Buy
         </button>
         <button
           type="button"
           className={`side-tab sell ${side === 'sell' ? 'active' : ''}`}
-          onClick={() => setSide('sell')}
+          onClick={() => { setSide('sell'); setOrderError(null); }}
         >
           Sell
         </button>
@@ -321,6 +341,17 @@ export function OrderForm() {
         </div>
       </div>
 
+      <label className="reduce-only-toggle">
+        <input
+          type="checkbox"
+          checked={reduceOnly}
+          onChange={(e) => { setReduceOnly(e.target.checked); setOrderError(null); }}
+        />
+        Reduce only
+      </label>
+
+      {shownError && <div className="order-error">{shownError}</div>}
+
       <button type="submit" className={`order-submit-btn ${side}`} disabled={isDisabled}>
         {buttonLabel}
       </button>
@@ -8,7 +8,7 @@ export function useDexScreenerData(disabled = false) {
   const statsIntervalRef = useRef<ReturnType<typeof setInterval> | null>(null);
   const lastPriceRef = useRef<number>(0);
   const pairAddressRef = useRef<string>('');
-  const statsRef = useRef<{ change_24h: number; volume_24h: number }>({ change_24h: 0, volume_24h: 0 });
+  const statsRef = useRef<{ change_24h: number | null; volume_24h: number }>({ change_24h: null, volume_24h: 0 });
   const preferredSourceRef = useRef<string>('');
 
   const isDexToken = currentSymbol.toLowerCase().startsWith('dex:');
@@ -68,7 +68,7 @@ export function useDexScreenerData(disabled = false) {
         }
 
         // Use 24h stats from DexScreener if available, otherwise use cached stats
-        if (result.change_24h !== 0 || result.volume_24h !== 0) {
+        if (result.change_24h != null || result.volume_24h !== 0) {
           statsRef.current = { change_24h: result.change_24h, volume_24h: result.volume_24h };
         }
 
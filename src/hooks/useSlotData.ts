@@ -343,7 +343,7 @@ export function useSlotData(slotId: string, symbol: string, timeframe: Timeframe
       if (cancelled) return;
 
       // Cached 24h stats (filled by slower DexScreener poll)
-      let cachedStats = { change_24h: 0, volume_24h: 0 };
+      let cachedStats: { change_24h: number | null; volume_24h: number } = { change_24h: null, volume_24h: 0 };
       let preferredSource = '';
       let pollCount = 0;
 
@@ -364,7 +364,7 @@ export function useSlotData(slotId: string, symbol: string, timeframe: Timeframe
           preferredSource = result.source;
 
           if (result.pair_address && !poolAddress) poolAddress = result.pair_address;
-          if (result.change_24h !== 0 || result.volume_24h !== 0) {
+          if (result.change_24h != null || result.volume_24h !== 0) {
             cachedStats = { change_24h: result.change_24h, volume_24h: result.volume_24h };
           }
 
@@ -76,6 +76,26 @@ export function useYahooFinanceData(disabled = false) {
           candlesRef.current = candles;
           setCandles(candles);
           console.log(`[Yahoo] Loaded ${candles.length} candles`);
+        } else {
+          // Meta-only response: drop the previous symbol's bars and fill the
+          // quote header from the meta prices until bars arrive
+          console.log(`[Yahoo] No ${interval} data yet for ${currentSymbol}`);
+          candlesRef.current = [];
+          setCandles([]);
+          if (response.current_price > 0) {
+            const { current_price: price, previous_close: previousClose } = response;
+            lastPriceRef.current = price;
+            setCurrentPrice(price);
+            setTicker({
+              symbol: currentSymbol,
+              price,
+              change24h: previousClose > 0 ? price - previousClose : 0,
+              changePercent24h: previousClose > 0 ? ((price - previousClose) / previousClose) * 100 : null,
+              high24h: response.day_high,
+              low24h: response.day_low,
+              volume24h: response.volume,
+            });
+          }
         }
       } catch (error) {
         console.error('[Yahoo] Candles error:', error);
@@ -14,6 +14,8 @@ interface SettingsState {
   panelVisibility: PanelVisibility;
   detachedPanels: Record<string, boolean>;
   chartType: ChartType;
+  // Off for a long-only cash account: sells can't exceed what is held
+  allowShorting: boolean;
 
   // Actions
   setShowSettings: (show: boolean) => void;
@@ -21,6 +23,7 @@ interface SettingsState {
   togglePanel: (panel: keyof PanelVisibility) => void;
   setDetached: (panel: string, detached: boolean) => void;
   setChartType: (type: ChartType) => void;
+  setAllowShorting: (allow: boolean) => void;
 }
 
 export const useSettingsStore = create<SettingsState>()(
@@ -31,6 +34,7 @@ export const useSettingsStore = create<SettingsState>()(
       panelVisibility: { leftSidebar: true, rightSidebar: true },
       detachedPanels: {},
       chartType: 'candlestick' as ChartType,
+      allowShorting: true,
 
       setShowSettings: (show) => set({ showSettings: show }),
       setLastSeenVersion: (version) => set({ lastSeenVersion: version }),
@@ -46,6 +50,7 @@ export const useSettingsStore = create<SettingsState>()(
           detachedPanels: { ...state.detachedPanels, [panel]: detached },
         })),
       setChartType: (chartType) => set({ chartType }),
+      setAllowShorting: (allowShorting) => set({ allowShorting }),
     }),
     {
       name: 'tradesim-settings',
@@ -53,6 +58,7 @@ export const useSettingsStore = create<SettingsState>()(
         lastSeenVersion: state.lastSeenVersion,
         panelVisibility: state.panelVisibility,
         chartType: state.chartType,
+        allowShorting: state.allowShorting,
       }),
     }
   )
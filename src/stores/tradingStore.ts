@@ -8,8 +8,12 @@ import type {
   Timeframe,
   Ticker,
   TradeRecord,
-  Watchlist
+  Watchlist,
+  WatchlistItem
 } from '../types/trading';
+import type { OrderFilledEvent } from '../api';
+import { isTauri, loadWatchlists, saveWatchlist, deleteWatchlist as deleteSavedWatchlist } from '../api';
+import { useSettingsStore } from './settingsStore';
 
 interface TradingState {
   // Market Data
@@ -35,6 +39,11 @@ interface TradingState {
 
   // Watchlists
   watchlists: Watchlist[];
+  // Market and display name of every listed symbol; the backend keeps only
+  // each list's symbols
+  watchlistItems: Record<string, WatchlistItem>;
+  // Set once the lists kept in localStorage have been copied to the backend
+  watchlistsMigrated: boolean;
 
   // UI State
   selectedPanel: 'orders' | 'positions' | 'history';
@@ -55,12 +64,44 @@ interface TradingState {
   addPosition: (position: Position) => void;
   updatePosition: (symbol: string, updates: Partial<Position>) => void;
   closePosition: (symbol: string) => void;
-  executeMarketOrder: (side: 'buy' | 'sell', qty: number, price: number) => void;
+  // Returns why the order was rejected, or null once it has filled
+  executeMarketOrder: (side: 'buy' | 'sell', qty: number, price: number, reduceOnly?: boolean) => string | null;
+  previewMarketOrder: (side: 'buy' | 'sell', qty: number, price: number, reduceOnly?: boolean) => OrderPreview;
+  applyOrderFill: (fill: OrderFilledEvent) => void;
   setBalance: (balance: number) => void;
   addTradeRecord: (trade: TradeRecord) => void;
   setSelectedPanel: (panel: 'orders' | 'positions' | 'history') => void;
   addCustomSymbol: (symbol: string, name: string, market: 'crypto' | 'stock') => void;
   removeCustomSymbol: (symbol: string) => void;
+  // Replaces the lists with the ones saved in the app data dir (Tauri only)
+  loadSavedWatchlists: () => Promise<void>;
+  removeWatchlist: (id: string) => Promise<void>;
+}
+
+interface MarketOrderResult {
+  balance: number;
+  positions: Position[];
+  realizedPnL: number;
+  tradeRecords: TradeRecord[];
+}
+
+// Projected outcome of a market order, for confirmation dialogs
+export interface OrderPreview {
+  symbol: string;
+  side: 'buy' | 'sell';
+  quantity: number;
+  fillPrice: number;
+  orderValue: number;
+  realizedPnL: number;
+  balanceBefore: number;
+  balanceAfter: number;
+  equityAfter: number;
+  buyingPowerAfter: number;
+  // Position in the symbol after the fill; null when it closes out
+  position: Position | null;
+  fills: TradeRecord[];
+  // Set when executing this order would be rejected
+  error: string | null;
 }
 
 const INITIAL_BALANCE = 100000;
@@ -74,6 +115,238 @@ function recalcPortfolio(balance: number, positions: Position[]): Pick<Portfolio
   return { equity, buyingPower: balance };
 }
 
+// Reduce-only orders must shrink the existing position, and with shorting
+// disabled a sell can't exceed the long that is held. Null when allowed.
+export function orderSizeError(
+  portfolio: Portfolio,
+  symbol: string,
+  side: 'buy' | 'sell',
+  qty: number,
+  reduceOnly: boolean,
+  allowShorting: boolean,
+): string | null {
+  const position = portfolio.positions.find((p) => p.symbol === symbol);
+  const reducible = position && position.side !== side ? position.quantity : 0;
+  if (reduceOnly && qty > reducible) {
+    return reducible > 0
+      ? `Reduce-only ${side} of ${qty} ${symbol} exceeds the ${reducible} open`
+      : `Reduce-only ${side} of ${symbol} has no opposite position to reduce`;
+  }
+  if (side === 'sell' && !allowShorting && qty > reducible) {
+    return reducible > 0
+      ? `Cannot sell ${qty} ${symbol}: only ${reducible} held and shorting is disabled`
+      : `Cannot sell ${symbol}: no position held and shorting is disabled`;
+  }
+  return null;
+}
+
+// Fills a market order against the portfolio without touching the store.
+// executeMarketOrder and previewMarketOrder both go through here, so a
+// preview always matches what executing the same order would do.
+function applyMarketOrder(
+  portfolio: Portfolio,
+  symbol: string,
+  side: 'buy' | 'sell',
+  qty: number,
+  price: number,
+  now: number,
+): MarketOrderResult {
+  const orderValue = qty * price;
+  const existingPosition = portfolio.positions.find((p) => p.symbol === symbol);
+
+  let newBalance = portfolio.balance;
+  let newPositions = [...portfolio.positions];
+  let realizedPnL = 0;
+  const tradeRecords: TradeRecord[] = [];
+
+  const tradeId = crypto.randomUUID();
+
+  if (existingPosition) {
+    if (existingPosition.side === side) {
+      // Adding to existing position (same direction)
+      const newQty = existingPosition.quantity + qty;
+      const newAvgPrice = (existingPosition.avgEntryPrice * existingPosition.quantity + price * qty) / newQty;
+
+      // Deduct cost
+      newBalance -= orderValue;
+
+      newPositions = newPositions.map((p) =>
+        p.symbol === symbol
+          ? {
+              ...p,
+              quantity: newQty,
+              avgEntryPrice: newAvgPrice,
+              currentPrice: price,
+              unrealizedPnL: (price - newAvgPrice) * newQty * (side === 'buy' ? 1 : -1),
+              unrealizedPnLPercent: ((price - newAvgPrice) / newAvgPrice) * 100 * (side === 'buy' ? 1 : -1),
+            }
+          : p
+      );
+
+      tradeRecords.push({
+        id: tradeId,
+        symbol,
+        side,
+        quantity: qty,
+        price,
+        pnl: 0,
+        timestamp: now,
+      });
+    } else {
+      // Opposite direction - reducing or flipping
+      if (qty < existingPosition.quantity) {
+        // Partial close
+        const closedQty = qty;
+        const remainingQty = existingPosition.quantity - closedQty;
+
+        realizedPnL = existingPosition.side === 'buy'
+          ? (price - existingPosition.avgEntryPrice) * closedQty
+          : (existingPosition.avgEntryPrice - price) * closedQty;
+
+        // Return closed portion cost + P&L
+        newBalance += (existingPosition.avgEntryPrice * closedQty) + realizedPnL;
+
+        const unrealizedPnL = existingPosition.side === 'buy'
+          ? (price - existingPosition.avgEntryPrice) * remainingQty
+          : (existingPosition.avgEntryPrice - price) * remainingQty;
+        const unrealizedPnLPercent = (unrealizedPnL / (existingPosition.avgEntryPrice * remainingQty)) * 100;
+
+        newPositions = newPositions.map((p) =>
+          p.symbol === symbol
+            ? {
+                ...p,
+                quantity: remainingQty,
+                currentPrice: price,
+                unrealizedPnL,
+                unrealizedPnLPercent,
+              }
+            : p
+        );
+
+        tradeRecords.push({
+          id: tradeId,
+          symbol,
+          side,
+          quantity: closedQty,
+          price,
+          pnl: realizedPnL,
+          timestamp: now,
+        });
+      } else if (qty === existingPosition.quantity) {
+        // Full close
+        realizedPnL = existingPosition.side === 'buy'
+          ? (price - existingPosition.avgEntryPrice) * existingPosition.quantity
+          : (existingPosition.avgEntryPrice - price) * existingPosition.quantity;
+
+        // Return full cost + P&L
+        newBalance += (existingPosition.avgEntryPrice * existingPosition.quantity) + realizedPnL;
+
+        newPositions = newPositions.filter((p) => p.symbol !== symbol);
+
+        tradeRecords.push({
+          id: tradeId,
+          symbol,
+          side,
+          quantity: existingPosition.quantity,
+          price,
+          pnl: realizedPnL,
+          timestamp: now,
+        });
+      } else {
+        // Close existing + open opposite direction with remainder
+        const closedQty = existingPosition.quantity;
+        const newQty = qty - closedQty;
+
+        realizedPnL = existingPosition.side === 'buy'
+          ? (price - existingPosition.avgEntryPrice) * closedQty
+          : (existingPosition.avgEntryPrice - price) * closedQty;
+
+        // Return full close cost + P&L, then deduct new position cost
+        newBalance += (existingPosition.avgEntryPrice * closedQty) + realizedPnL;
+        newBalance -= newQty * price;
+
+        // Remove old, add new opposite position
+        newPositions = newPositions.filter((p) => p.symbol !== symbol);
+        newPositions.push({
+          symbol,
+          side,
+          quantity: newQty,
+          avgEntryPrice: price,
+          currentPrice: price,
+          unrealizedPnL: 0,
+          unrealizedPnLPercent: 0,
+          realizedPnL: 0,
+        });
+
+        tradeRecords.push({
+          id: tradeId,
+          symbol,
+          side: existingPosition.side === 'buy' ? 'sell' : 'buy',
+          quantity: closedQty,
+          price,
+          pnl: realizedPnL,
+          timestamp: now,
+        });
+        tradeRecords.push({
+          id: crypto.randomUUID(),
+          symbol,
+          side,
+          quantity: newQty,
+          price,
+          pnl: 0,
+          timestamp: now,
+        });
+      }
+    }
+  } else {
+    // New position
+    newBalance -= orderValue;
+
+    newPositions.push({
+      symbol,
+      side,
+      quantity: qty,
+      avgEntryPrice: price,
+      currentPrice: price,
+      unrealizedPnL: 0,
+      unrealizedPnLPercent: 0,
+      realizedPnL: 0,
+    });
+
+    tradeRecords.push({
+      id: tradeId,
+      symbol,
+      side,
+      quantity: qty,
+      price,
+      pnl: 0,
+      timestamp: now,
+    });
+  }
+
+  return {
+    balance: newBalance,
+    positions: newPositions,
+    realizedPnL,
+    tradeRecords,
+  };
+}
+
+// Crypto pairs and DEX tokens vs stocks, as the data hooks tell them apart
+function marketFor(symbol: string): 'crypto' | 'stock' {
+  return symbol.endsWith('USDT') || symbol.toLowerCase().startsWith('dex:') ? 'crypto' : 'stock';
+}
+
+// Saves to the backend only after its lists were loaded this session, so a
+// change made during startup can't overwrite them with the defaults
+let savedWatchlistsLoaded = false;
+
+function persistWatchlist(list: Watchlist) {
+  if (!isTauri || !savedWatchlistsLoaded) return;
+  saveWatchlist(list.name, list.items.map((i) => i.symbol))
+    .catch((e) => console.error(`[Watchlist] Saving ${list.name} failed:`, e));
+}
+
 export const useTradingStore = create<TradingState>()(
   persist(
     (set, get) => ({
@@ -122,6 +395,8 @@ export const useTradingStore = create<TradingState>()(
       items: [],
     },
   ],
+  watchlistItems: {},
+  watchlistsMigrated: false,
 
   // UI State
   selectedPanel: 'positions',
@@ -290,182 +565,19 @@ export const useTradingStore = create<TradingState>()(
     });
   },
 
-  executeMarketOrder: (side, qty, price) => {
+  executeMarketOrder: (side, qty, price, reduceOnly = false) => {
     const state = get();
-    const orderValue = qty * price;
     const symbol = state.currentSymbol;
-    const existingPosition = state.portfolio.positions.find((p) => p.symbol === symbol);
-
-    let newBalance = state.portfolio.balance;
-    let newPositions = [...state.portfolio.positions];
-    let realizedPnL = 0;
-    let tradeRecords: TradeRecord[] = [];
-
+    const { allowShorting } = useSettingsStore.getState();
+    const error = orderSizeError(state.portfolio, symbol, side, qty, reduceOnly, allowShorting);
+    if (error) return error;
     const now = Date.now();
-    const tradeId = crypto.randomUUID();
-
-    if (existingPosition) {
-      if (existingPosition.side === side) {
-        // Adding to existing position (same direction)
-        const newQty = existingPosition.quantity + qty;
-        const newAvgPrice = (existingPosition.avgEntryPrice * existingPosition.quantity + price * qty) / newQty;
-
-        // Deduct cost
-        newBalance -= orderValue;
-
-        newPositions = newPositions.map((p) =>
-          p.symbol === symbol
-            ? {
-                ...p,
-                quantity: newQty,
-                avgEntryPrice: newAvgPrice,
-                currentPrice: price,
-                unrealizedPnL: (price - newAvgPrice) * newQty * (side === 'buy' ? 1 : -1),
-                unrealizedPnLPercent: ((price - newAvgPrice) / newAvgPrice) * 100 * (side === 'buy' ? 1 : -1),
-              }
-            : p
-        );
-
-        tradeRecords.push({
-          id: tradeId,
-          symbol,
-          side,
-          quantity: qty,
-          price,
-          pnl: 0,
-          timestamp: now,
-        });
-      } else {
-        // Opposite direction - reducing or flipping
-        if (qty < existingPosition.quantity) {
-          // Partial close
-          const closedQty = qty;
-          const remainingQty = existingPosition.quantity - closedQty;
-
-          realizedPnL = existingPosition.side === 'buy'
-            ? (price - existingPosition.avgEntryPrice) * closedQty
-            : (existingPosition.avgEntryPrice - price) * closedQty;
-
-          // Return closed portion cost + P&L
-          newBalance += (existingPosition.avgEntryPrice * closedQty) + realizedPnL;
-
-          const unrealizedPnL = existingPosition.side === 'buy'
-            ? (price - existingPosition.avgEntryPrice) * remainingQty
-            : (existingPosition.avgEntryPrice - price) * remainingQty;
-          const unrealizedPnLPercent = (unrealizedPnL / (existingPosition.avgEntryPrice * remainingQty)) * 100;
-
-          newPositions = newPositions.map((p) =>
-            p.symbol === symbol
-              ? {
-                  ...p,
-                  quantity: remainingQty,
-                  currentPrice: price,
-                  unrealizedPnL,
-                  unrealizedPnLPercent,
-                }
-              : p
-          );
-
-          tradeRecords.push({
-            id: tradeId,
-            symbol,
-            side,
-            quantity: closedQty,
-            price,
-            pnl: realizedPnL,
-            timestamp: now,
-          });
-        } else if (qty === existingPosition.quantity) {
-          // Full close
-          realizedPnL = existingPosition.side === 'buy'
-            ? (price - existingPosition.avgEntryPrice) * existingPosition.quantity
-            : (existingPosition.avgEntryPrice - price) * existingPosition.quantity;
-
-          // Return full cost + P&L
-          newBalance += (existingPosition.avgEntryPrice * existingPosition.quantity) + realizedPnL;
-
-          newPositions = newPositions.filter((p) => p.symbol !== symbol);
-
-          tradeRecords.push({
-            id: tradeId,
-            symbol,
-            side,
-            quantity: existingPosition.quantity,
-            price,
-            pnl: realizedPnL,
-            timestamp: now,
-          });
-        } else {
-          // Close existing + open opposite direction with remainder
-          const closedQty = existingPosition.quantity;
-          const newQty = qty - closedQty;
-
-          realizedPnL = existingPosition.side === 'buy'
-            ? (price - existingPosition.avgEntryPrice) * closedQty
-            : (existingPosition.avgEntryPrice - price) * closedQty;
-
-          // Return full close cost + P&L, then deduct new position cost
-          newBalance += (existingPosition.avgEntryPrice * closedQty) + realizedPnL;
-          newBalance -= newQty * price;
-
-          // Remove old, add new opposite position
-          newPositions = newPositions.filter((p) => p.symbol !== symbol);
-          newPositions.push({
-            symbol,
-            side,
-            quantity: newQty,
-            avgEntryPrice: price,
-            currentPrice: price,
-            unrealizedPnL: 0,
-            unrealizedPnLPercent: 0,
-            realizedPnL: 0,
-          });
-
-          tradeRecords.push({
-            id: tradeId,
-            symbol,
-            side: existingPosition.side === 'buy' ? 'sell' : 'buy',
-            quantity: closedQty,
-            price,
-            pnl: realizedPnL,
-            timestamp: now,
-          });
-          tradeRecords.push({
-            id: crypto.randomUUID(),
-            symbol,
-            side,
-            quantity: newQty,
-            price,
-            pnl: 0,
-            timestamp: now,
-          });
-        }
-      }
-    } else {
-      // New position
-      newBalance -= orderValue;
-
-      newPositions.push({
-        symbol,
-        side,
-        quantity: qty,
-        avgEntryPrice: price,
-        currentPrice: price,
-        unrealizedPnL: 0,
-        unrealizedPnLPercent: 0,
-        realizedPnL: 0,
-      });
-
-      tradeRecords.push({
-        id: tradeId,
-        symbol,
-        side,
-        quantity: qty,
-        price,
-        pnl: 0,
-        timestamp: now,
-      });
-    }
+    const {
+      balance: newBalance,
+      positions: newPositions,
+      realizedPnL,
+      tradeRecords,
+    } = applyMarketOrder(state.portfolio, symbol, side, qty, price, now);
 
     const { equity, buyingPower } = recalcPortfolio(newBalance, newPositions);
 
@@ -476,6 +588,7 @@ export const useTradingStore = create<TradingState>()(
       side,
       type: 'market',
       quantity: qty,
+      reduceOnly: reduceOnly || undefined,
       status: 'filled',
       filledQuantity: qty,
       avgFillPrice: price,
@@ -483,6 +596,101 @@ export const useTradingStore = create<TradingState>()(
       updatedAt: now,
     };
 
+    set({
+      portfolio: {
+        ...state.portfolio,
+        balance: newBalance,
+        equity,
+        buyingPower,
+        positions: newPositions,
+        totalPnL: state.portfolio.totalPnL + realizedPnL,
+        dailyPnL: state.portfolio.dailyPnL + realizedPnL,
+      },
+      tradeHistory: [...tradeRecords, ...state.tradeHistory],
+      orderHistory: [order, ...state.orderHistory],
+    });
+    return null;
+  },
+
+  previewMarketOrder: (side, qty, price, reduceOnly = false) => {
+    const state = get();
+    const symbol = state.currentSymbol;
+    const { allowShorting } = useSettingsStore.getState();
+    const result = applyMarketOrder(state.portfolio, symbol, side, qty, price, Date.now());
+    const { equity, buyingPower } = recalcPortfolio(result.balance, result.positions);
+    return {
+      symbol,
+      side,
+      quantity: qty,
+      fillPrice: price,
+      orderValue: qty * price,
+      realizedPnL: result.realizedPnL,
+      balanceBefore: state.portfolio.balance,
+      balanceAfter: result.balance,
+      equityAfter: equity,
+      buyingPowerAfter: buyingPower,
+      position: result.positions.find((p) => p.symbol === symbol) ?? null,
+      fills: result.tradeRecords,
+      error: orderSizeError(state.portfolio, symbol, side, qty, reduceOnly, allowShorting),
+    };
+  },
+
+  // Resting orders live in the backend and fill on its ticker loop; this books
+  // the fill against the portfolio exactly like a market order at that price.
+  // A fill can be delivered twice (event and startup replay), so it is booked
+  // once per resting order id.
+  applyOrderFill: (fill) => {
+    const state = get();
+    const id = `resting-${fill.order.id}`;
+    if (state.orderHistory.some((o) => o.id === id)) return;
+
+    const { symbol, side, kind } = fill.order;
+    const reduceOnly = fill.order.reduce_only ?? false;
+    let quantity = fill.order.quantity;
+    let rejected = false;
+    if (reduceOnly) {
+      // A bracket exit only closes what is still open on the other side
+      const position = state.portfolio.positions.find((p) => p.symbol === symbol);
+      quantity = position && position.side !== side ? Math.min(quantity, position.quantity) : 0;
+    } else {
+      // Shorting may have been turned off while the order rested
+      const { allowShorting } = useSettingsStore.getState();
+      const error = orderSizeError(state.portfolio, symbol, side, quantity, false, allowShorting);
+      if (error) {
+        console.warn('[Order] Fill rejected:', error);
+        quantity = 0;
+        rejected = true;
+      }
+    }
+
+    const order: Order = {
+      id,
+      symbol,
+      side,
+      type: kind.type === 'stop_market' ? 'stop' : kind.type,
+      quantity: fill.order.quantity,
+      price: kind.type === 'stop_market' ? undefined : kind.limit_price,
+      stopPrice: kind.type === 'limit' ? undefined : kind.stop_price,
+      reduceOnly,
+      status: rejected ? 'rejected' : quantity > 0 ? 'filled' : 'cancelled',
+      filledQuantity: quantity,
+      avgFillPrice: quantity > 0 ? fill.price : undefined,
+      createdAt: fill.filled_at,
+      updatedAt: fill.filled_at,
+    };
+    if (quantity === 0) {
+      set({ orderHistory: [order, ...state.orderHistory] });
+      return;
+    }
+
+    const {
+      balance: newBalance,
+      positions: newPositions,
+      realizedPnL,
+      tradeRecords,
+    } = applyMarketOrder(state.portfolio, symbol, side, quantity, fill.price, fill.filled_at);
+    const { equity, buyingPower } = recalcPortfolio(newBalance, newPositions);
+
     set({
       portfolio: {
         ...state.portfolio,
@@ -512,7 +720,8 @@ export const useTradingStore = create<TradingState>()(
 
   setSelectedPanel: (panel) => set({ selectedPanel: panel }),
 
-  addCustomSymbol: (symbol, name, market) => set((state) => {
+  addCustomSymbol: (symbol, name, market) => {
+    const state = get();
     // Don't uppercase DEX tokens - addresses are case-sensitive
     const normalizedSymbol = symbol.startsWith('dex:') ? symbol.trim() : symbol.toUpperCase().trim();
     const customWatchlist = state.watchlists.find((w) => w.id === 'custom');
@@ -521,27 +730,65 @@ export const useTradingStore = create<TradingState>()(
     const exists = state.watchlists.some((w) =>
       w.items.some((item) => item.symbol === normalizedSymbol)
     );
-    if (exists) return state;
-
-    if (customWatchlist) {
-      return {
-        watchlists: state.watchlists.map((w) =>
-          w.id === 'custom'
-            ? { ...w, items: [...w.items, { symbol: normalizedSymbol, market, name }] }
-            : w
-        ),
-      };
+    if (exists || !customWatchlist) return;
+
+    const item = { symbol: normalizedSymbol, market, name };
+    const updated = { ...customWatchlist, items: [...customWatchlist.items, item] };
+    set({
+      watchlists: state.watchlists.map((w) => (w.id === 'custom' ? updated : w)),
+      watchlistItems: { ...state.watchlistItems, [normalizedSymbol]: item },
+    });
+    persistWatchlist(updated);
+  },
+
+  removeCustomSymbol: (symbol) => {
+    const state = get();
+    const customWatchlist = state.watchlists.find((w) => w.id === 'custom');
+    if (!customWatchlist) return;
+
+    const updated = { ...customWatchlist, items: customWatchlist.items.filter((item) => item.symbol !== symbol) };
+    set({ watchlists: state.watchlists.map((w) => (w.id === 'custom' ? updated : w)) });
+    persistWatchlist(updated);
+  },
+
+  loadSavedWatchlists: async () => {
+    if (!isTauri) return;
+    const state = get();
+    let saved = await loadWatchlists();
+    if (!state.watchlistsMigrated) {
+      // One-time copy of the lists kept in localStorage; a list already saved
+      // under the same name wins
+      for (const list of state.watchlists) {
+        if (!(list.name in saved)) {
+          saved = await saveWatchlist(list.name, list.items.map((i) => i.symbol));
+        }
+      }
     }
-    return state;
-  }),
 
-  removeCustomSymbol: (symbol) => set((state) => ({
-    watchlists: state.watchlists.map((w) =>
-      w.id === 'custom'
-        ? { ...w, items: w.items.filter((item) => item.symbol !== symbol) }
-        : w
-    ),
-  })),
+    const known: Record<string, WatchlistItem> = { ...state.watchlistItems };
+    for (const item of state.watchlists.flatMap((w) => w.items)) {
+      known[item.symbol] ??= item;
+    }
+    const toItems = (symbols: string[]) =>
+      symbols.map((symbol) => known[symbol] ?? { symbol, market: marketFor(symbol) });
+    const watchlists = state.watchlists.map((w) => (w.name in saved ? { ...w, items: toItems(saved[w.name]) } : w));
+    for (const name of Object.keys(saved)) {
+      if (!watchlists.some((w) => w.name === name)) {
+        watchlists.push({ id: name, name, items: toItems(saved[name]) });
+      }
+    }
+
+    savedWatchlistsLoaded = true;
+    set({ watchlists, watchlistItems: known, watchlistsMigrated: true });
+  },
+
+  // The built-in Favorites and Custom lists can't be removed
+  removeWatchlist: async (id) => {
+    const list = get().watchlists.find((w) => w.id === id);
+    if (!list || id === 'default' || id === 'custom') return;
+    if (isTauri) await deleteSavedWatchlist(list.name);
+    set((state) => ({ watchlists: state.watchlists.filter((w) => w.id !== id) }));
+  },
 }),
     {
       name: 'tradesim-trading',
@@ -551,8 +798,11 @@ export const useTradingStore = create<TradingState>()(
         // Persist trade and order history
         tradeHistory: state.tradeHistory,
         orderHistory: state.orderHistory,
-        // Persist watchlists (including custom symbols)
-        watchlists: state.watchlists,
+        // Persist watchlists (including custom symbols) until the desktop app
+        // has moved them to the app data dir; item details stay here
+        ...(isTauri && state.watchlistsMigrated ? {} : { watchlists: state.watchlists }),
+        watchlistItems: state.watchlistItems,
+        watchlistsMigrated: state.watchlistsMigrated,
         // Persist last viewed symbol and timeframe
         currentSymbol: state.currentSymbol,
         currentTimeframe: state.currentTimeframe,
@@ -19,7 +19,7 @@ export interface Ticker {
   symbol: string;
   price: number;
   change24h: number;
-  changePercent24h: number;
+  changePercent24h: number | null; // null when the previous close is unknown
   high24h: number;
   low24h: number;
   volume24h: number;
@@ -41,6 +41,8 @@ export interface Order {
   stopPrice?: number;
   trailingAmount?: number;
   trailingPercent?: number;
+  // May only shrink the existing position, never open or flip one
+  reduceOnly?: boolean;
   status: OrderStatus;
   filledQuantity: number;
   avgFillPrice?: number;